@@ -6,7 +6,6 @@ pub struct CloneResponse {
 }
 
 pub async fn clone(req: fpm::http::Request) -> fpm::Result<fpm::http::Response> {
-    // TODO: implement authentication
     match clone_worker(req).await {
         Ok(data) => fpm::http::api_ok(data),
         Err(err) => fpm::http::api_error(err.to_string()),
@@ -49,6 +48,15 @@ async fn clone_worker(req: fpm::http::Request) -> fpm::Result<CloneResponse> {
     .flatten()
     .flatten()
     .collect::<std::collections::BTreeMap<String, Vec<u8>>>();
+
+    // files the requester can't read are left out of the clone
+    let mut readable_files = std::collections::BTreeMap::new();
+    for (path, content) in files {
+        if config.can_read(&req, path.as_str(), true).await? {
+            readable_files.insert(path, content);
+        }
+    }
+    let files = readable_files;
     Ok(CloneResponse {
         package_name: config.package.name.to_string(),
         files,
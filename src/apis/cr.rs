@@ -56,3 +56,204 @@ async fn create_cr_page_worker(req: fpm::http::Request) -> fpm::Result<Vec<u8>>
 
     fpm::package::package_doc::read_ftd(&mut config, &main_document, "/", false).await
 }
+
+#[derive(serde::Deserialize, serde::Serialize, std::fmt::Debug)]
+pub struct CRReviewRequest {
+    pub cr: usize,
+    pub status: fpm::cr::ReviewStatus,
+    pub message: Option<String>,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, std::fmt::Debug)]
+pub struct CRCommentRequest {
+    pub cr: usize,
+    pub filename: String,
+    pub line: usize,
+    pub message: String,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, std::fmt::Debug)]
+pub struct CRReviewResponse {
+    pub url: String,
+}
+
+pub async fn cr_review(
+    req: &fpm::http::Request,
+    review_req: CRReviewRequest,
+) -> fpm::Result<fpm::http::Response> {
+    match cr_review_worker(req, review_req).await {
+        Ok(url) => fpm::http::api_ok(CRReviewResponse { url }),
+        Err(err) => fpm::http::api_error(err.to_string()),
+    }
+}
+
+async fn cr_review_worker(
+    req: &fpm::http::Request,
+    review_req: CRReviewRequest,
+) -> fpm::Result<String> {
+    let config = fpm::Config::read(None, false, Some(req)).await?;
+    // approvals gate merging into main, so only the writers can review
+    if !config
+        .can_write(req, fpm::cr::cr_path(review_req.cr).as_str())
+        .await?
+    {
+        return fpm::usage_error(format!(
+            "You are unauthorized to review CR#{}",
+            review_req.cr
+        ));
+    }
+    let author = reviewer(req)?;
+    let cr_reviews = fpm::cr::add_cr_review(
+        &config,
+        review_req.cr,
+        review_req.status,
+        review_req.message.as_deref(),
+        Some(author.as_str()),
+    )
+    .await?;
+    let message = match review_req.status {
+        fpm::cr::ReviewStatus::Approved => format!("Approved CR#{}", review_req.cr),
+        fpm::cr::ReviewStatus::ChangesRequested => {
+            format!("Requested changes in CR#{}", review_req.cr)
+        }
+    };
+    sync_cr_reviews(
+        &config,
+        review_req.cr,
+        &cr_reviews,
+        message.as_str(),
+        author.as_str(),
+    )
+    .await?;
+    Ok(cr_review_url(review_req.cr))
+}
+
+pub async fn cr_comment(
+    req: &fpm::http::Request,
+    comment_req: CRCommentRequest,
+) -> fpm::Result<fpm::http::Response> {
+    match cr_comment_worker(req, comment_req).await {
+        Ok(url) => fpm::http::api_ok(CRReviewResponse { url }),
+        Err(err) => fpm::http::api_error(err.to_string()),
+    }
+}
+
+async fn cr_comment_worker(
+    req: &fpm::http::Request,
+    comment_req: CRCommentRequest,
+) -> fpm::Result<String> {
+    let config = fpm::Config::read(None, false, Some(req)).await?;
+    if !config
+        .can_read(req, fpm::cr::cr_path(comment_req.cr).as_str(), true)
+        .await?
+    {
+        return fpm::usage_error(format!(
+            "You are unauthorized to comment on CR#{}",
+            comment_req.cr
+        ));
+    }
+    let author = reviewer(req)?;
+    let cr_reviews = fpm::cr::add_cr_comment(
+        &config,
+        comment_req.cr,
+        comment_req.filename.as_str(),
+        comment_req.line,
+        comment_req.message.as_str(),
+        Some(author.as_str()),
+    )
+    .await?;
+    sync_cr_reviews(
+        &config,
+        comment_req.cr,
+        &cr_reviews,
+        format!(
+            "Commented on {}:{} in CR#{}",
+            comment_req.filename, comment_req.line, comment_req.cr
+        )
+        .as_str(),
+        author.as_str(),
+    )
+    .await?;
+    Ok(cr_review_url(comment_req.cr))
+}
+
+fn reviewer(req: &fpm::http::Request) -> fpm::Result<String> {
+    match fpm::auth::author_identity(req.cookies())? {
+        Some(author) => Ok(author),
+        None => fpm::usage_error("Login to review change requests".to_string()),
+    }
+}
+
+fn cr_review_url(cr_number: usize) -> String {
+    format!("/-/{}/-/review/", cr_number)
+}
+
+/// Records the reviews in history, like the synced files of a clone
+async fn sync_cr_reviews(
+    config: &fpm::Config,
+    cr_number: usize,
+    cr_reviews: &fpm::cr::CRReviews,
+    message: &str,
+    author: &str,
+) -> fpm::Result<()> {
+    let path = config
+        .path_without_root(&config.cr_reviews_path(cr_number))?
+        .to_string();
+    let content = fpm::cr::generate_cr_reviews_content(cr_reviews).into_bytes();
+    let file = match config.get_remote_manifest(false).await?.get(path.as_str()) {
+        Some(file_edit) => fpm::apis::sync2::SyncRequestFile::Update {
+            path,
+            content,
+            version: file_edit.version,
+            src_cr: None,
+        },
+        None => fpm::apis::sync2::SyncRequestFile::Add {
+            path,
+            content,
+            src_cr: None,
+        },
+    };
+    let not_synced =
+        fpm::apis::sync2::do_sync(config, &[file], Some(message), Some(author)).await?;
+    if !not_synced.is_empty() {
+        return fpm::usage_error(format!(
+            "Reviews of CR#{} are updated by someone else, try again",
+            cr_number
+        ));
+    }
+    Ok(())
+}
+
+pub async fn cr_review_page(req: fpm::http::Request) -> fpm::Result<fpm::http::Response> {
+    let cr_number = match fpm::cr::get_cr_number_from_review_url(req.path()) {
+        Some(cr_number) => cr_number,
+        None => return Ok(fpm::not_found!("not a cr review url: {}", req.path())),
+    };
+    match cr_review_page_worker(req, cr_number).await {
+        Ok(body) => Ok(fpm::http::ok(body)),
+        Err(err) => fpm::http::api_error(err.to_string()),
+    }
+}
+
+async fn cr_review_page_worker(req: fpm::http::Request, cr_number: usize) -> fpm::Result<Vec<u8>> {
+    let mut config = fpm::Config::read(None, false, Some(&req)).await?;
+    if !config
+        .can_read(&req, fpm::cr::cr_path(cr_number).as_str(), true)
+        .await?
+    {
+        return fpm::usage_error(format!("You are unauthorized to access: CR#{}", cr_number));
+    }
+    let cr_meta = fpm::cr::get_cr_meta(&config, cr_number).await?;
+    let cr_reviews = fpm::cr::get_cr_reviews(&config, cr_number).await?;
+    let cr_diff = fpm::cr::cr_diff(&config, cr_number).await?;
+    let cr_review_ftd = fpm::package_info_cr_review(&config, &cr_meta, &cr_reviews, &cr_diff)?;
+
+    let main_document = fpm::Document {
+        id: "cr-review.ftd".to_string(),
+        content: cr_review_ftd,
+        parent_path: config.root.as_str().to_string(),
+        package_name: config.package.name.clone(),
+    };
+
+    fpm::package::package_doc::read_ftd(&mut config, &main_document, "/", false).await
+}
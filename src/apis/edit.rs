@@ -5,6 +5,12 @@ pub struct EditRequest {
     pub path: String,
     pub operation: Option<String>, // todo: convert it to enum
     pub data: Option<String>,
+    /// For `rename`, also add a redirect from the old url to the new one in `fpm.redirects`
+    #[serde(rename = "add-redirect", default)]
+    pub add_redirect: bool,
+    /// Recorded in history along with the logged in user as author
+    #[serde(default)]
+    pub message: Option<String>,
 }
 
 impl EditRequest {
@@ -52,7 +58,11 @@ pub async fn edit(
         }
     };
 
-    match edit_worker(config, req_data).await {
+    let author = match fpm::auth::author_identity(req.cookies()) {
+        Ok(author) => author,
+        Err(err) => return fpm::http::api_error(err.to_string()),
+    };
+    match edit_worker(config, req_data, author).await {
         Ok(data) => fpm::http::api_ok(data),
         Err(err) => fpm::http::api_error(err.to_string()),
     }
@@ -61,14 +71,29 @@ pub async fn edit(
 pub(crate) async fn edit_worker(
     config: fpm::Config,
     request: EditRequest,
+    author: Option<String>,
 ) -> fpm::Result<EditResponse> {
+    let message = fpm::history::clean_message(request.message.as_deref())?;
+    let author = fpm::history::clean_author(author.as_deref())?;
+
     if request.is_delete() {
         let path = config.root.join(&request.path);
+        let deleted = files_in_history(&config, request.path.as_str()).await?;
         if path.is_dir() {
             tokio::fs::remove_dir_all(&path).await?;
         } else if path.is_file() {
             tokio::fs::remove_file(&path).await?;
         }
+        record_in_history(
+            &config,
+            deleted
+                .into_iter()
+                .map(|v| (v, fpm::history::FileOperation::Deleted))
+                .collect(),
+            message,
+            author,
+        )
+        .await?;
         return Ok(EditResponse {
             path: request.path,
             url: None,
@@ -92,7 +117,33 @@ pub(crate) async fn edit_worker(
             rename
         };
 
-        tokio::fs::rename(config.root.join(&request.path), config.root.join(new_path)).await?;
+        let renamed = files_in_history(&config, request.path.as_str()).await?;
+        tokio::fs::rename(
+            config.root.join(&request.path),
+            config.root.join(new_path.as_str()),
+        )
+        .await?;
+        let mut changes = std::collections::BTreeMap::new();
+        for old in renamed {
+            let new = format!(
+                "{}{}",
+                new_path,
+                old.strip_prefix(request.path.trim_end_matches('/'))
+                    .unwrap_or_default()
+            );
+            changes.insert(old, fpm::history::FileOperation::Deleted);
+            changes.insert(new, fpm::history::FileOperation::Added);
+        }
+        record_in_history(&config, changes, message, author).await?;
+
+        if request.add_redirect {
+            fpm::package::redirects::add(
+                &config,
+                path_to_url(request.path.as_str()).as_str(),
+                path_to_url(new_path.as_str()).as_str(),
+            )
+            .await?;
+        }
 
         // TODO: redirect to renamed file, if folder so it will redirect to renamed folder with
         // index.ftd, if index.ftd does not exists so it will redirected to main project index.ftd
@@ -146,6 +197,21 @@ pub(crate) async fn edit_worker(
         request.value.unwrap_or_default().into_bytes().as_slice(),
     )
     .await?;
+    let operation = if files_in_history(&config, file_name.as_str())
+        .await?
+        .contains(&file_name)
+    {
+        fpm::history::FileOperation::Updated
+    } else {
+        fpm::history::FileOperation::Added
+    };
+    record_in_history(
+        &config,
+        std::iter::once((file_name.to_string(), operation)).collect(),
+        message,
+        author,
+    )
+    .await?;
 
     if let Some(before_update_status) = before_update_status {
         let snapshots = fpm::snapshot::get_latest_snapshots(&config.root).await?;
@@ -174,6 +240,58 @@ pub(crate) async fn edit_worker(
     })
 }
 
+/// Files in the latest version of history, which are `path` or inside the folder `path`
+async fn files_in_history(config: &fpm::Config, path: &str) -> fpm::Result<Vec<String>> {
+    if !config.history_file().exists() {
+        return Ok(vec![]);
+    }
+    let folder = format!("{}/", path.trim_end_matches('/'));
+    Ok(config
+        .get_remote_manifest(false)
+        .await?
+        .into_keys()
+        .filter(|v| v.eq(path) || v.starts_with(folder.as_str()))
+        .collect())
+}
+
+/// If the package is served from its remote state, i.e. it keeps history, edits made from the
+/// browser are recorded in history right away, like `fpm sync` from a clone would. A clone has
+/// `.remote-state/history.ftd` too, but as its copy of the history of the server, the edits of
+/// a clone get in history when they are synced.
+async fn record_in_history(
+    config: &fpm::Config,
+    changes: std::collections::BTreeMap<String, fpm::history::FileOperation>,
+    message: Option<String>,
+    author: Option<String>,
+) -> fpm::Result<()> {
+    if changes.is_empty() || !config.history_file().exists() || config.clone_dir().exists() {
+        return Ok(());
+    }
+    let mut history = config.get_history().await?;
+    let file_list = changes
+        .into_iter()
+        .map(|(file, operation)| {
+            (
+                file,
+                fpm::history::FileEditTemp {
+                    message: message.clone(),
+                    author: author.clone(),
+                    src_cr: None,
+                    operation,
+                },
+            )
+        })
+        .collect();
+    fpm::history::insert_into_history(&config.root, &file_list, &mut history).await
+}
+
+/// foo/bar.ftd => /foo/bar/, foo/index.ftd => /foo/, foo => /foo/
+fn path_to_url(path: &str) -> String {
+    let url =
+        fpm::utils::id_to_path(path.trim_matches('/')).replace(std::path::MAIN_SEPARATOR, "/");
+    format!("/{}/", url.trim_matches('/')).replace("//", "/")
+}
+
 pub async fn sync(req: fpm::http::Request) -> fpm::Result<fpm::http::Response> {
     let config = match fpm::Config::read(None, false, Some(&req)).await {
         Ok(config) => config,
@@ -216,3 +334,40 @@ pub async fn revert(
         Err(err) => fpm::http::api_error(err.to_string()),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn record_in_history() {
+        fpm::test_utils::block_on(async {
+            let package = fpm::test_utils::TempPackage::new("edit-history");
+            let root = &package.root;
+            package.write("index.ftd", "-- ftd.text: hello\n");
+            package.write(
+                ".remote-state/history.ftd",
+                fpm::history::FileHistory::to_ftd(&[]).as_str(),
+            );
+            let changes = || {
+                std::iter::once(("index.ftd".to_string(), fpm::history::FileOperation::Added))
+                    .collect()
+            };
+
+            // a clone: `.remote-state/history.ftd` is the copy of the history of the server
+            std::fs::create_dir_all(root.join(".clone-state")).unwrap();
+            let config = package.config().await;
+            super::record_in_history(&config, changes(), None, None)
+                .await
+                .unwrap();
+            assert!(config.get_history().await.unwrap().is_empty());
+
+            // the server
+            std::fs::remove_dir_all(root.join(".clone-state")).unwrap();
+            super::record_in_history(&config, changes(), None, None)
+                .await
+                .unwrap();
+            let history = config.get_history().await.unwrap();
+            assert_eq!(history.len(), 1);
+            assert_eq!(history[0].filename, "index.ftd");
+        });
+    }
+}
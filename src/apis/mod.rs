@@ -4,6 +4,7 @@ pub(crate) mod cr;
 pub(crate) mod edit;
 pub(crate) mod sync;
 pub(crate) mod sync2;
+pub(crate) mod sync2_binary;
 pub(crate) mod view_source;
 
 pub(crate) use self::edit::edit;
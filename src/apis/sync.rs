@@ -4,6 +4,8 @@ pub enum SyncStatus {
     NoConflict,
     CloneEditedRemoteDeleted,
     CloneDeletedRemoteEdited,
+    /// The requester is not in the `writers` of the file, it is not synced
+    PermissionDenied,
 }
 
 #[derive(serde::Serialize, serde::Deserialize, std::fmt::Debug)]
@@ -88,6 +90,10 @@ pub(crate) async fn sync_worker(
     let timestamp = fpm::timestamp_nanosecond();
     let mut synced_files = std::collections::HashMap::new();
     for file in request.files.iter() {
+        if let Some(denied) = permission_denied(&config, req, file, &snapshots).await? {
+            synced_files.insert(denied.0, denied.1);
+            continue;
+        }
         match file {
             SyncRequestFile::Add { path, content } => {
                 // We need to check if, file is already available on server
@@ -119,7 +125,7 @@ pub(crate) async fn sync_worker(
                 let snapshot_path =
                     fpm::utils::history_path(path, config.root.as_str(), remote_timestamp);
 
-                let data = tokio::fs::read(snapshot_path).await?;
+                let data = fpm::history_store::read(&snapshot_path).await?;
 
                 // if: Client Says Deleted and server says modified
                 // that means Remote timestamp is greater than client timestamp
@@ -166,13 +172,14 @@ pub(crate) async fn sync_worker(
                             config.root.as_str(),
                             client_snapshot_timestamp,
                         );
-                        let ancestor_content = tokio::fs::read_to_string(ancestor_path).await?;
+                        let ancestor_content =
+                            fpm::history_store::read_to_string(&ancestor_path).await?;
                         let ours_path = fpm::utils::history_path(
                             path,
                             config.root.as_str(),
                             snapshot_timestamp,
                         );
-                        let theirs_content = tokio::fs::read_to_string(ours_path).await?;
+                        let theirs_content = fpm::history_store::read_to_string(&ours_path).await?;
                         let ours_content = String::from_utf8(content.clone())
                             .map_err(|e| fpm::Error::APIResponseError(e.to_string()))?;
 
@@ -254,6 +261,50 @@ pub(crate) async fn sync_worker(
     Ok(r)
 }
 
+/// Response for a file the requester can't write, `None` if it can. The client keeps its edit,
+/// and gets back the server content of the file it deleted.
+async fn permission_denied(
+    config: &fpm::Config,
+    req: &fpm::http::Request,
+    file: &SyncRequestFile,
+    snapshots: &std::collections::BTreeMap<String, u128>,
+) -> fpm::Result<Option<(String, SyncResponseFile)>> {
+    let path = match file {
+        SyncRequestFile::Add { path, .. }
+        | SyncRequestFile::Update { path, .. }
+        | SyncRequestFile::Delete { path } => path,
+    };
+    if config.can_write(req, path).await? {
+        return Ok(None);
+    }
+    let response = match file {
+        SyncRequestFile::Add { content, .. } | SyncRequestFile::Update { content, .. } => {
+            SyncResponseFile::Update {
+                path: path.to_string(),
+                status: SyncStatus::PermissionDenied,
+                content: content.clone(),
+            }
+        }
+        SyncRequestFile::Delete { .. } => {
+            let timestamp = match snapshots.get(path) {
+                Some(timestamp) => timestamp,
+                None => return Ok(None),
+            };
+            SyncResponseFile::Add {
+                path: path.to_string(),
+                status: SyncStatus::PermissionDenied,
+                content: fpm::history_store::read(&fpm::utils::history_path(
+                    path,
+                    config.root.as_str(),
+                    timestamp,
+                ))
+                .await?,
+            }
+        }
+    };
+    Ok(Some((path.to_string(), response)))
+}
+
 fn snapshot_diff(
     server_snapshot: &std::collections::BTreeMap<String, u128>,
     client_snapshot: &std::collections::BTreeMap<String, u128>,
@@ -340,7 +391,7 @@ async fn clone_history_files(
 
     let diff = snapshot_diff(server_snapshot, client_snapshot);
 
-    let history = ignore::WalkBuilder::new(config.history_dir())
+    let mut history = ignore::WalkBuilder::new(config.history_dir())
         .build()
         .into_iter()
         .flatten()
@@ -353,6 +404,8 @@ async fn clone_history_files(
                 .to_string()
         })
         .collect::<Vec<String>>();
+    // versions moved to the pack by `fpm gc`
+    history.extend(fpm::history_store::packed_files(&config.history_dir())?);
 
     let mut dot_history = vec![];
     for (path, _) in diff.iter() {
@@ -362,7 +415,7 @@ async fn clone_history_files(
             .filter(|x| client_timestamp.map(|c| x.0.gt(c)).unwrap_or(true))
             .collect_vec();
         for (_, path) in history_paths {
-            let content = tokio::fs::read(config.history_dir().join(&path)).await?;
+            let content = fpm::history_store::read(&config.history_dir().join(&path)).await?;
             dot_history.push(File { path, content });
         }
     }
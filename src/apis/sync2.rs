@@ -24,6 +24,11 @@ pub struct SyncRequest {
     pub package_name: String,
     pub files: Vec<SyncRequestFile>,
     pub history: String,
+    /// Recorded in history for all the synced files, `fpm sync -m <message>`
+    #[serde(default)]
+    pub message: Option<String>,
+    #[serde(default)]
+    pub author: Option<String>,
 }
 
 #[derive(serde::Serialize, serde::Deserialize, std::fmt::Debug)]
@@ -40,6 +45,8 @@ pub enum SyncStatus {
     CloneEditedRemoteDeleted,
     CloneDeletedRemoteEdited,
     CloneAddedRemoteAdded,
+    /// The requester is not in the `writers` of the file, it is not synced
+    PermissionDenied,
 }
 
 impl SyncStatus {
@@ -55,6 +62,9 @@ impl SyncStatus {
     pub(crate) fn edit_edit_conflict(&self) -> bool {
         SyncStatus::RegularConflict.eq(self)
     }
+    pub(crate) fn permission_denied(&self) -> bool {
+        SyncStatus::PermissionDenied.eq(self)
+    }
 }
 
 #[derive(serde::Serialize, serde::Deserialize, std::fmt::Debug)]
@@ -77,7 +87,55 @@ pub enum SyncResponseFile {
     },
 }
 
+impl SyncRequestFile {
+    pub(crate) fn path(&self) -> &str {
+        match self {
+            SyncRequestFile::Add { path, .. }
+            | SyncRequestFile::Update { path, .. }
+            | SyncRequestFile::Delete { path, .. } => path.as_str(),
+        }
+    }
+
+    pub(crate) fn content_mut(&mut self) -> Option<&mut Vec<u8>> {
+        match self {
+            SyncRequestFile::Add { content, .. } | SyncRequestFile::Update { content, .. } => {
+                Some(content)
+            }
+            SyncRequestFile::Delete { .. } => None,
+        }
+    }
+
+    /// Sent back for a file the requester can't write, the client keeps its change
+    fn permission_denied(self) -> SyncResponseFile {
+        match self {
+            SyncRequestFile::Add { path, content, .. } => SyncResponseFile::Add {
+                path,
+                status: SyncStatus::PermissionDenied,
+                content,
+            },
+            SyncRequestFile::Update { path, content, .. } => SyncResponseFile::Update {
+                path,
+                status: SyncStatus::PermissionDenied,
+                content,
+            },
+            SyncRequestFile::Delete { path, .. } => SyncResponseFile::Delete {
+                path,
+                status: SyncStatus::PermissionDenied,
+                content: vec![],
+            },
+        }
+    }
+}
+
 impl SyncResponseFile {
+    pub(crate) fn content_mut(&mut self) -> &mut Vec<u8> {
+        match self {
+            SyncResponseFile::Add { content, .. }
+            | SyncResponseFile::Update { content, .. }
+            | SyncResponseFile::Delete { content, .. } => content,
+        }
+    }
+
     pub(crate) fn is_conflicted(&self) -> bool {
         let status = match self {
             SyncResponseFile::Add { status, .. }
@@ -124,6 +182,8 @@ pub async fn sync2(
 pub(crate) async fn do_sync(
     config: &fpm::Config,
     files: &[SyncRequestFile],
+    message: Option<&str>,
+    author: Option<&str>,
 ) -> fpm::Result<std::collections::HashMap<String, SyncResponseFile>> {
     let mut remote_history = config.get_history().await?;
     let remote_manifest =
@@ -132,7 +192,6 @@ pub(crate) async fn do_sync(
         Default::default();
     let mut synced_files = std::collections::HashMap::new();
     for file in files {
-        // TODO: get all data like message, author, src-cr from request
         match file {
             SyncRequestFile::Add {
                 path,
@@ -155,8 +214,8 @@ pub(crate) async fn do_sync(
                 to_be_in_history.insert(
                     path.to_string(),
                     fpm::history::FileEditTemp {
-                        message: None,
-                        author: None,
+                        message: message.map(ToString::to_string),
+                        author: author.map(ToString::to_string),
                         src_cr: *src_cr,
                         operation: fpm::history::FileOperation::Added,
                     },
@@ -171,12 +230,11 @@ pub(crate) async fn do_sync(
                 if let Some(file_edit) = remote_manifest.get(path) {
                     if file_edit.version.eq(version) {
                         fpm::utils::update(&config.root.join(path), content).await?;
-                        // TODO: get all data like message, author, src-cr from request
                         to_be_in_history.insert(
                             path.to_string(),
                             fpm::history::FileEditTemp {
-                                message: None,
-                                author: None,
+                                message: message.map(ToString::to_string),
+                                author: author.map(ToString::to_string),
                                 src_cr: *src_cr,
                                 operation: fpm::history::FileOperation::Updated,
                             },
@@ -184,38 +242,22 @@ pub(crate) async fn do_sync(
                     } else {
                         // else: Both has modified the same file
                         let ancestor_path = config.history_path(path, *version);
-                        let ancestor_content = if let Ok(ancestor_content) =
-                            tokio::fs::read_to_string(ancestor_path).await
-                        {
-                            ancestor_content
-                        } else {
-                            // It's a binary file like image etc, can't try merging
-                            synced_files.insert(
-                                path.to_string(),
-                                SyncResponseFile::Update {
-                                    path: path.to_string(),
-                                    status: SyncStatus::RegularConflict,
-                                    content: content.clone(),
-                                },
-                            );
-                            continue;
-                        };
+                        let ancestor_content =
+                            fpm::sync_utils::read_ancestor(&ancestor_path).await?;
                         let theirs_path = config.history_path(path, file_edit.version);
-                        let theirs_content = tokio::fs::read_to_string(theirs_path).await?;
-                        let ours_content = String::from_utf8(content.clone())
-                            .map_err(|e| fpm::Error::APIResponseError(e.to_string()))?;
-                        match diffy::MergeOptions::new()
-                            .set_conflict_style(diffy::ConflictStyle::Merge)
-                            .merge(&ancestor_content, &ours_content, &theirs_content)
-                        {
-                            Ok(data) => {
-                                fpm::utils::update(&config.root.join(path), data.as_bytes())
-                                    .await?;
+                        let theirs_content = fpm::history_store::read(&theirs_path).await?;
+                        match fpm::sync_utils::merge(
+                            ancestor_content.as_deref(),
+                            content,
+                            &theirs_content,
+                        ) {
+                            fpm::sync_utils::MergeResult::Merged(data) => {
+                                fpm::utils::update(&config.root.join(path), &data).await?;
                                 to_be_in_history.insert(
                                     path.to_string(),
                                     fpm::history::FileEditTemp {
-                                        message: None,
-                                        author: None,
+                                        message: message.map(ToString::to_string),
+                                        author: author.map(ToString::to_string),
                                         src_cr: *src_cr,
                                         operation: fpm::history::FileOperation::Updated,
                                     },
@@ -225,18 +267,29 @@ pub(crate) async fn do_sync(
                                     SyncResponseFile::Update {
                                         path: path.to_string(),
                                         status: SyncStatus::NoConflict,
-                                        content: data.as_bytes().to_vec(),
+                                        content: data,
                                     },
                                 );
                             }
-                            Err(data) => {
-                                // Return conflicted content
+                            fpm::sync_utils::MergeResult::Conflicted(data) => {
+                                // Return content with the overlapping hunks marked
                                 synced_files.insert(
                                     path.to_string(),
                                     SyncResponseFile::Update {
                                         path: path.to_string(),
                                         status: SyncStatus::RegularConflict,
-                                        content: data.as_bytes().to_vec(),
+                                        content: data,
+                                    },
+                                );
+                            }
+                            fpm::sync_utils::MergeResult::Binary => {
+                                // It's a binary file like image etc, can't try merging
+                                synced_files.insert(
+                                    path.to_string(),
+                                    SyncResponseFile::Update {
+                                        path: path.to_string(),
+                                        status: SyncStatus::RegularConflict,
+                                        content: content.clone(),
                                     },
                                 );
                             }
@@ -269,7 +322,7 @@ pub(crate) async fn do_sync(
                     continue;
                 };
                 let server_content =
-                    tokio::fs::read(config.history_path(path, file_edit.version)).await?;
+                    fpm::history_store::read(&config.history_path(path, file_edit.version)).await?;
 
                 // if: Client Says Deleted and server says modified
                 // that means Remote timestamp is greater than client timestamp
@@ -289,8 +342,8 @@ pub(crate) async fn do_sync(
                     to_be_in_history.insert(
                         path.to_string(),
                         fpm::history::FileEditTemp {
-                            message: None,
-                            author: None,
+                            message: message.map(ToString::to_string),
+                            author: author.map(ToString::to_string),
                             src_cr: *src_cr,
                             operation: fpm::history::FileOperation::Deleted,
                         },
@@ -312,7 +365,38 @@ pub(crate) async fn sync_worker(
 
     // TODO: Need to call at once only
     let config = fpm::Config::read(None, false, Some(req)).await?;
-    let mut synced_files = do_sync(&config, request.files.as_slice()).await?;
+    let message = fpm::history::clean_message(request.message.as_deref())?;
+    // author sent by the client can't be verified, unless the request is from a logged in user
+    let author = match (
+        fpm::auth::author_identity(req.cookies())?,
+        fpm::history::clean_author(request.author.as_deref())?,
+    ) {
+        (Some(identity), Some(author)) if !identity.eq(&author) => {
+            return Err(fpm::Error::APIResponseError(format!(
+                "author `{}` does not match the logged in identity `{}`",
+                author, identity
+            )));
+        }
+        (Some(identity), _) => Some(identity),
+        (None, author) => author,
+    };
+    let mut files = vec![];
+    let mut denied_files = std::collections::HashMap::new();
+    for file in request.files {
+        if config.can_write(req, file.path()).await? {
+            files.push(file);
+        } else {
+            denied_files.insert(file.path().to_string(), file.permission_denied());
+        }
+    }
+    let mut synced_files = do_sync(
+        &config,
+        files.as_slice(),
+        message.as_deref(),
+        author.as_deref(),
+    )
+    .await?;
+    synced_files.extend(denied_files);
     let remote_history = config.get_history().await?;
     let remote_manifest =
         fpm::history::FileHistory::get_remote_manifest(remote_history.as_slice(), true)?;
@@ -321,9 +405,16 @@ pub(crate) async fn sync_worker(
     let client_latest =
         fpm::history::FileHistory::get_remote_manifest(clone_history.as_slice(), true)?;
 
-    client_current_files(&config, &remote_manifest, &client_latest, &mut synced_files).await?;
+    client_current_files(
+        &config,
+        req,
+        &remote_manifest,
+        &client_latest,
+        &mut synced_files,
+    )
+    .await?;
 
-    let history_files = clone_history_files(&config, &remote_manifest, &client_latest).await?;
+    let history_files = clone_history_files(&config, req, &remote_manifest, &client_latest).await?;
 
     Ok(SyncResponse {
         files: synced_files.into_values().collect_vec(),
@@ -332,44 +423,63 @@ pub(crate) async fn sync_worker(
     })
 }
 
+/// Versions of the files the client does not have yet, of the files the requester can read
 async fn clone_history_files(
     config: &fpm::Config,
+    req: &fpm::http::Request,
     remote_manifest: &std::collections::BTreeMap<String, fpm::history::FileEdit>,
     client_latest: &std::collections::BTreeMap<String, fpm::history::FileEdit>,
 ) -> fpm::Result<Vec<File>> {
     use itertools::Itertools;
 
     let diff = snapshot_diff(remote_manifest, client_latest);
-    let history = ignore::WalkBuilder::new(config.remote_history_dir())
-        .hidden(false)
-        .build()
-        .into_iter()
-        .flatten()
-        .map(|x| {
-            x.into_path()
-                .to_str()
-                .unwrap()
-                .trim_start_matches(config.remote_history_dir().as_str())
-                .trim_matches('/')
-                .to_string()
-        })
-        .collect::<Vec<String>>();
+    let history = remote_history_files(config)?;
 
     let mut dot_history = vec![];
     for (path, _) in diff.iter() {
+        if !config.can_read(req, path, true).await? {
+            continue;
+        }
         let client_file_edit = client_latest.get(path);
         let history_paths = get_all_versions(path, history.as_slice())?
             .into_iter()
             .filter(|x| client_file_edit.map(|c| x.0.gt(&c.version)).unwrap_or(true))
             .collect_vec();
         for (_, path) in history_paths {
-            let content = tokio::fs::read(config.remote_history_dir().join(&path)).await?;
+            let content =
+                fpm::history_store::read(&config.remote_history_dir().join(&path)).await?;
             dot_history.push(File { path, content });
         }
     }
     Ok(dot_history)
 }
 
+/// Paths of all the versions in `.remote-state/history`, relative to it
+pub(crate) fn remote_history_files(config: &fpm::Config) -> fpm::Result<Vec<String>> {
+    let mut history = ignore::WalkBuilder::new(config.remote_history_dir())
+        .hidden(false)
+        .build()
+        .into_iter()
+        .flatten()
+        .filter(|x| x.file_type().map(|v| v.is_file()).unwrap_or(false))
+        .map(|x| {
+            x.into_path()
+                .to_str()
+                .unwrap()
+                .trim_start_matches(config.remote_history_dir().as_str())
+                .trim_matches('/')
+                .to_string()
+        })
+        // `fpm gc` packs
+        .filter(|x| !x.starts_with(".packs/"))
+        .collect::<Vec<String>>();
+    // versions moved to the pack by `fpm gc`
+    history.extend(fpm::history_store::packed_files(
+        &config.remote_history_dir(),
+    )?);
+    Ok(history)
+}
+
 fn get_all_versions(path: &str, history: &[String]) -> fpm::Result<Vec<(i32, String)>> {
     let (path_prefix, ext) = if let Some((path_prefix, ext)) = path.rsplit_once('.') {
         (format!("{}.", path_prefix), Some(ext))
@@ -391,15 +501,18 @@ fn get_all_versions(path: &str, history: &[String]) -> fpm::Result<Vec<(i32, Str
     Ok(versions)
 }
 
+/// Files changed on the server since the client synced, the ones the requester can't read are
+/// left out like in the clone
 async fn client_current_files(
     config: &fpm::Config,
+    req: &fpm::http::Request,
     remote_manifest: &std::collections::BTreeMap<String, fpm::history::FileEdit>,
     client_latest: &std::collections::BTreeMap<String, fpm::history::FileEdit>,
     synced_files: &mut std::collections::HashMap<String, SyncResponseFile>,
 ) -> fpm::Result<()> {
     let diff = snapshot_diff(remote_manifest, client_latest);
     for (path, operation) in diff.iter() {
-        if synced_files.contains_key(path) {
+        if synced_files.contains_key(path) || !config.can_read(req, path, true).await? {
             continue;
         }
         if operation.is_deleted() {
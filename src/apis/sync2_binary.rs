@@ -0,0 +1,551 @@
+//! Binary encoding of `/-/sync2/`, used by the clients which support it.
+//!
+//! The client first sends the hashes of the contents it wants to upload to
+//! `/-/sync2/negotiate/`, and the server answers with the hashes it does not have in its history.
+//! The request then carries only the missing contents, and neither the request nor the response
+//! repeats a content, files refer to it by its sha256. The `application/json` encoding of
+//! `/-/sync2/` keeps working for the older clients.
+//!
+//! The body is zlib compressed, and has the big endian u32 length of the metadata JSON, the
+//! metadata JSON, and then every content as its 64 byte hex sha256, big endian u64 length and
+//! the bytes.
+
+pub(crate) const CONTENT_TYPE: &str = "application/x-fpm-sync";
+/// Upper bound on the decompressed size of a body, so a small body can't exhaust the memory
+const MAX_BODY_SIZE: u64 = 1 << 30;
+const HASH_LEN: usize = 64;
+
+#[derive(serde::Deserialize, serde::Serialize, std::fmt::Debug)]
+pub struct NegotiateRequest {
+    pub package_name: String,
+    pub hashes: Vec<String>,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, std::fmt::Debug)]
+pub struct NegotiateResponse {
+    pub missing: Vec<String>,
+}
+
+/// `SyncRequest` with the file contents taken out, `content_hashes` is in the order of `files`,
+/// `None` for deleted files
+#[derive(serde::Deserialize, serde::Serialize, std::fmt::Debug)]
+struct BinarySyncRequest {
+    request: fpm::apis::sync2::SyncRequest,
+    content_hashes: Vec<Option<String>>,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, std::fmt::Debug)]
+struct BinarySyncResponse {
+    response: fpm::apis::sync2::SyncResponse,
+    file_hashes: Vec<String>,
+    dot_history_hashes: Vec<String>,
+}
+
+pub async fn negotiate(
+    req: &fpm::http::Request,
+    negotiate_req: NegotiateRequest,
+) -> fpm::Result<fpm::http::Response> {
+    match negotiate_worker(req, negotiate_req).await {
+        Ok(data) => fpm::http::api_ok(data),
+        Err(err) => fpm::http::api_error(err.to_string()),
+    }
+}
+
+async fn negotiate_worker(
+    req: &fpm::http::Request,
+    negotiate_req: NegotiateRequest,
+) -> fpm::Result<NegotiateResponse> {
+    let config = fpm::Config::read(None, false, Some(req)).await?;
+    let known = readable_contents(&config, req).await?;
+    Ok(NegotiateResponse {
+        missing: negotiate_req
+            .hashes
+            .into_iter()
+            .filter(|v| !known.contains_key(v))
+            .collect(),
+    })
+}
+
+pub async fn sync2(req: &fpm::http::Request) -> fpm::Result<fpm::http::Response> {
+    match sync2_worker(req).await {
+        Ok(body) => Ok(fpm::http::ok_with_content_type(
+            body,
+            CONTENT_TYPE.parse().unwrap(),
+        )),
+        Err(err) => fpm::http::api_error(err.to_string()),
+    }
+}
+
+async fn sync2_worker(req: &fpm::http::Request) -> fpm::Result<Vec<u8>> {
+    let config = fpm::Config::read(None, false, Some(req)).await?;
+    let request = decode_request(&config, req).await?;
+    let response = fpm::apis::sync2::sync_worker(req, request).await?;
+    encode_response(response)
+}
+
+/// Hashes of the versions in `.remote-state/history`, the server does not need these contents
+/// from the clients. Versions in history never change, so their hashes are cached in
+/// `.remote-state/content-hashes.json`.
+async fn known_contents(
+    config: &fpm::Config,
+) -> fpm::Result<std::collections::BTreeMap<String, camino::Utf8PathBuf>> {
+    let history_dir = config.remote_history_dir();
+    let cache_path = config.remote_dir().join("content-hashes.json");
+    // version path => hash
+    let mut cache: std::collections::BTreeMap<String, String> =
+        match tokio::fs::read(&cache_path).await {
+            Ok(content) => serde_json::from_slice(content.as_slice()).unwrap_or_default(),
+            Err(_) => Default::default(),
+        };
+
+    let history = if history_dir.exists() {
+        fpm::apis::sync2::remote_history_files(config)?
+    } else {
+        vec![]
+    };
+    let mut updated = cache.len();
+    cache.retain(|k, _| history.contains(k));
+    updated -= cache.len();
+
+    let mut known = std::collections::BTreeMap::new();
+    for path in history {
+        let hash = match cache.get(path.as_str()) {
+            Some(hash) => hash.to_string(),
+            None => {
+                let content = fpm::history_store::read(&history_dir.join(path.as_str())).await?;
+                let hash = fpm::history_store::content_hash(content.as_slice());
+                cache.insert(path.to_string(), hash.to_string());
+                updated += 1;
+                hash
+            }
+        };
+        known.insert(hash, history_dir.join(path.as_str()));
+    }
+
+    if updated > 0 {
+        fpm::utils::update(&cache_path, serde_json::to_vec(&cache)?.as_slice()).await?;
+    }
+    Ok(known)
+}
+
+/// `known_contents` of the versions of the files the requester can read. Otherwise the answer
+/// of negotiate would tell if the server has a content the requester can't read, and a request
+/// referring to it by its hash would copy it to a file the requester can read.
+async fn readable_contents(
+    config: &fpm::Config,
+    req: &fpm::http::Request,
+) -> fpm::Result<std::collections::BTreeMap<String, camino::Utf8PathBuf>> {
+    let history_dir = config.remote_history_dir();
+    // file => can read
+    let mut readable: std::collections::HashMap<String, bool> = Default::default();
+    let mut contents = std::collections::BTreeMap::new();
+    for (hash, path) in known_contents(config).await? {
+        let file = match path
+            .strip_prefix(&history_dir)
+            .ok()
+            .and_then(|v| file_of_version(v.as_str()))
+        {
+            Some(file) => file,
+            None => continue,
+        };
+        let can_read = match readable.get(file.as_str()) {
+            Some(can_read) => *can_read,
+            None => {
+                let can_read = config.can_read(req, file.as_str(), true).await?;
+                readable.insert(file, can_read);
+                can_read
+            }
+        };
+        if can_read {
+            contents.insert(hash, path);
+        }
+    }
+    Ok(contents)
+}
+
+/// `a/b.ftd` for the version `a/b.<version>.ftd`, `LICENSE` for `LICENSE.<version>`
+fn file_of_version(path: &str) -> Option<String> {
+    let is_version = |v: &str| !v.is_empty() && v.chars().all(|c| c.is_ascii_digit());
+    let (rest, last) = path.rsplit_once('.')?;
+    match rest.rsplit_once('.') {
+        Some((id, version)) if is_version(version) => Some(format!("{}.{}", id, last)),
+        _ if is_version(last) => Some(rest.to_string()),
+        _ => None,
+    }
+}
+
+/// Request with the contents the server does not have, `missing` as answered by
+/// `/-/sync2/negotiate/`
+pub(crate) fn encode_request(
+    request: &fpm::apis::sync2::SyncRequest,
+    missing: &std::collections::HashSet<String>,
+) -> fpm::Result<Vec<u8>> {
+    let mut contents = std::collections::BTreeMap::new();
+    let mut content_hashes = vec![];
+    let mut files = vec![];
+    for file in request.files.iter() {
+        let (file, content) = without_content(file);
+        content_hashes.push(content.map(|content| {
+            let hash = fpm::history_store::content_hash(content);
+            if missing.contains(&hash) {
+                contents.insert(hash.to_string(), content);
+            }
+            hash
+        }));
+        files.push(file);
+    }
+
+    encode(
+        &BinarySyncRequest {
+            request: fpm::apis::sync2::SyncRequest {
+                package_name: request.package_name.to_string(),
+                files,
+                history: request.history.to_string(),
+                message: request.message.clone(),
+                author: request.author.clone(),
+            },
+            content_hashes,
+        },
+        &contents,
+    )
+}
+
+/// Hashes of the contents the request would upload, to send to `/-/sync2/negotiate/`
+pub(crate) fn request_hashes(request: &fpm::apis::sync2::SyncRequest) -> Vec<String> {
+    use itertools::Itertools;
+
+    request
+        .files
+        .iter()
+        .filter_map(|file| without_content(file).1)
+        .map(fpm::history_store::content_hash)
+        .unique()
+        .collect()
+}
+
+fn without_content(
+    file: &fpm::apis::sync2::SyncRequestFile,
+) -> (fpm::apis::sync2::SyncRequestFile, Option<&[u8]>) {
+    match file {
+        fpm::apis::sync2::SyncRequestFile::Add {
+            path,
+            content,
+            src_cr,
+        } => (
+            fpm::apis::sync2::SyncRequestFile::Add {
+                path: path.to_string(),
+                content: vec![],
+                src_cr: *src_cr,
+            },
+            Some(content.as_slice()),
+        ),
+        fpm::apis::sync2::SyncRequestFile::Update {
+            path,
+            content,
+            version,
+            src_cr,
+        } => (
+            fpm::apis::sync2::SyncRequestFile::Update {
+                path: path.to_string(),
+                content: vec![],
+                version: *version,
+                src_cr: *src_cr,
+            },
+            Some(content.as_slice()),
+        ),
+        fpm::apis::sync2::SyncRequestFile::Delete { .. } => (file.clone(), None),
+    }
+}
+
+async fn decode_request(
+    config: &fpm::Config,
+    req: &fpm::http::Request,
+) -> fpm::Result<fpm::apis::sync2::SyncRequest> {
+    let (binary_request, mut contents): (BinarySyncRequest, _) = decode(req.body())?;
+    let mut request = binary_request.request;
+    if request.files.len() != binary_request.content_hashes.len() {
+        return Err(fpm::Error::APIResponseError(
+            "content hashes do not match the files".to_string(),
+        ));
+    }
+
+    let mut known = None;
+    for (file, hash) in request
+        .files
+        .iter_mut()
+        .zip(binary_request.content_hashes.into_iter())
+    {
+        let (path, hash) = match (file.path().to_string(), hash) {
+            (path, Some(hash)) => (path, hash),
+            (_, None) => continue,
+        };
+        let content = match contents.get(hash.as_str()) {
+            Some(content) => content.clone(),
+            None => {
+                if known.is_none() {
+                    known = Some(readable_contents(config, req).await?);
+                }
+                match known.as_ref().and_then(|v| v.get(hash.as_str())) {
+                    Some(history_path) => {
+                        let content = fpm::history_store::read(history_path).await?;
+                        contents.insert(hash.to_string(), content.clone());
+                        content
+                    }
+                    None => {
+                        return Err(fpm::Error::APIResponseError(format!(
+                            "content {} of {} is neither in the request nor on the server",
+                            hash, path
+                        )))
+                    }
+                }
+            }
+        };
+        if let Some(file_content) = file.content_mut() {
+            *file_content = content;
+        }
+    }
+    Ok(request)
+}
+
+fn encode_response(mut response: fpm::apis::sync2::SyncResponse) -> fpm::Result<Vec<u8>> {
+    let mut contents = std::collections::BTreeMap::new();
+    let mut take = |content: &mut Vec<u8>| {
+        let content = std::mem::take(content);
+        let hash = fpm::history_store::content_hash(content.as_slice());
+        contents.insert(hash.to_string(), content);
+        hash
+    };
+    let file_hashes = response
+        .files
+        .iter_mut()
+        .map(|file| take(file.content_mut()))
+        .collect();
+    let dot_history_hashes = response
+        .dot_history
+        .iter_mut()
+        .map(|file| take(&mut file.content))
+        .collect();
+
+    let contents = contents
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.as_slice()))
+        .collect();
+    encode(
+        &BinarySyncResponse {
+            response,
+            file_hashes,
+            dot_history_hashes,
+        },
+        &contents,
+    )
+}
+
+pub(crate) fn decode_response(body: &[u8]) -> fpm::Result<fpm::apis::sync2::SyncResponse> {
+    let (binary_response, contents): (BinarySyncResponse, _) = decode(body)?;
+    let mut response = binary_response.response;
+    let content = |hash: &str| {
+        contents.get(hash).cloned().ok_or_else(|| {
+            fpm::Error::APIResponseError(format!("content {} missing in sync response", hash))
+        })
+    };
+    if response.files.len() != binary_response.file_hashes.len()
+        || response.dot_history.len() != binary_response.dot_history_hashes.len()
+    {
+        return Err(fpm::Error::APIResponseError(
+            "content hashes do not match the files in sync response".to_string(),
+        ));
+    }
+    for (file, hash) in response
+        .files
+        .iter_mut()
+        .zip(binary_response.file_hashes.iter())
+    {
+        *file.content_mut() = content(hash)?;
+    }
+    for (file, hash) in response
+        .dot_history
+        .iter_mut()
+        .zip(binary_response.dot_history_hashes.iter())
+    {
+        file.content = content(hash)?;
+    }
+    Ok(response)
+}
+
+fn encode(
+    metadata: &impl serde::Serialize,
+    contents: &std::collections::BTreeMap<String, &[u8]>,
+) -> fpm::Result<Vec<u8>> {
+    use std::io::Write;
+
+    let metadata = serde_json::to_vec(metadata)?;
+    let mut encoder = flate2::write::ZlibEncoder::new(vec![], flate2::Compression::default());
+    encoder.write_all(&(metadata.len() as u32).to_be_bytes())?;
+    encoder.write_all(metadata.as_slice())?;
+    for (hash, content) in contents {
+        encoder.write_all(hash.as_bytes())?;
+        encoder.write_all(&(content.len() as u64).to_be_bytes())?;
+        encoder.write_all(content)?;
+    }
+    Ok(encoder.finish()?)
+}
+
+fn decode<T: serde::de::DeserializeOwned>(
+    body: &[u8],
+) -> fpm::Result<(T, std::collections::HashMap<String, Vec<u8>>)> {
+    use std::io::Read;
+
+    let invalid =
+        |message: &str| fpm::Error::APIResponseError(format!("invalid sync body: {}", message));
+
+    let mut data = vec![];
+    flate2::read::ZlibDecoder::new(body)
+        .take(MAX_BODY_SIZE + 1)
+        .read_to_end(&mut data)?;
+    if data.len() as u64 > MAX_BODY_SIZE {
+        return Err(invalid("too large"));
+    }
+
+    let mut data = data.as_slice();
+    let metadata_len = split(&mut data, 4).ok_or_else(|| invalid("truncated"))?;
+    let metadata_len = u32::from_be_bytes(metadata_len.try_into().unwrap()) as usize;
+    let metadata = split(&mut data, metadata_len).ok_or_else(|| invalid("truncated"))?;
+    let metadata: T = serde_json::from_slice(metadata)?;
+
+    let mut contents = std::collections::HashMap::new();
+    while !data.is_empty() {
+        let hash = split(&mut data, HASH_LEN).ok_or_else(|| invalid("truncated"))?;
+        let hash = String::from_utf8(hash.to_vec())?;
+        let len = split(&mut data, 8).ok_or_else(|| invalid("truncated"))?;
+        let len = u64::from_be_bytes(len.try_into().unwrap()) as usize;
+        let content = split(&mut data, len).ok_or_else(|| invalid("truncated"))?;
+        if fpm::history_store::content_hash(content) != hash {
+            return Err(invalid(
+                format!("content does not match its hash {}", hash).as_str(),
+            ));
+        }
+        contents.insert(hash, content.to_vec());
+    }
+    Ok((metadata, contents))
+}
+
+/// First `len` bytes of `data`, which is moved past them
+fn split<'a>(data: &mut &'a [u8], len: usize) -> Option<&'a [u8]> {
+    if data.len() < len {
+        return None;
+    }
+    let (head, rest) = data.split_at(len);
+    *data = rest;
+    Some(head)
+}
+
+#[cfg(test)]
+mod tests {
+    fn request() -> fpm::apis::sync2::SyncRequest {
+        fpm::apis::sync2::SyncRequest {
+            package_name: "amitu.com".to_string(),
+            files: vec![
+                fpm::apis::sync2::SyncRequestFile::Add {
+                    path: "a.ftd".to_string(),
+                    content: b"hello".to_vec(),
+                    src_cr: None,
+                },
+                fpm::apis::sync2::SyncRequestFile::Update {
+                    path: "b.ftd".to_string(),
+                    content: b"hello".to_vec(),
+                    version: 2,
+                    src_cr: None,
+                },
+                fpm::apis::sync2::SyncRequestFile::Delete {
+                    path: "c.ftd".to_string(),
+                    version: 1,
+                    src_cr: None,
+                },
+            ],
+            history: "".to_string(),
+            message: Some("hello".to_string()),
+            author: None,
+        }
+    }
+
+    #[test]
+    fn request_hashes() {
+        // both the files have the same content
+        assert_eq!(super::request_hashes(&request()).len(), 1);
+    }
+
+    #[test]
+    fn encode_request() {
+        let hash = super::request_hashes(&request()).remove(0);
+        let missing = std::iter::once(hash.to_string()).collect();
+        let (mut binary_request, contents): (super::BinarySyncRequest, _) = super::decode(
+            super::encode_request(&request(), &missing)
+                .unwrap()
+                .as_slice(),
+        )
+        .unwrap();
+        assert_eq!(contents.len(), 1);
+        assert_eq!(contents.get(hash.as_str()).unwrap(), b"hello");
+        assert_eq!(
+            binary_request.content_hashes,
+            vec![Some(hash.to_string()), Some(hash), None]
+        );
+        assert!(binary_request
+            .request
+            .files
+            .iter_mut()
+            .all(|v| v.content_mut().map(|v| v.is_empty()).unwrap_or(true)));
+
+        // the server has the content, it's not sent
+        let (_, contents): (super::BinarySyncRequest, _) = super::decode(
+            super::encode_request(&request(), &Default::default())
+                .unwrap()
+                .as_slice(),
+        )
+        .unwrap();
+        assert!(contents.is_empty());
+    }
+
+    #[test]
+    fn encode_response() {
+        let response = fpm::apis::sync2::SyncResponse {
+            files: vec![fpm::apis::sync2::SyncResponseFile::Add {
+                path: "a.ftd".to_string(),
+                status: fpm::apis::sync2::SyncStatus::NoConflict,
+                content: b"hello".to_vec(),
+            }],
+            dot_history: vec![fpm::apis::sync2::File {
+                path: "a.1.ftd".to_string(),
+                content: b"hello".to_vec(),
+            }],
+            latest_ftd: "-- import: fpm".to_string(),
+        };
+        let mut response =
+            super::decode_response(super::encode_response(response).unwrap().as_slice()).unwrap();
+        assert_eq!(response.files[0].content_mut(), b"hello");
+        assert_eq!(response.dot_history[0].content, b"hello");
+        assert_eq!(response.latest_ftd, "-- import: fpm");
+    }
+
+    #[test]
+    fn decode_corrupt() {
+        assert!(super::decode::<super::NegotiateResponse>(b"not zlib").is_err());
+    }
+
+    #[test]
+    fn file_of_version() {
+        assert_eq!(
+            super::file_of_version("a/b.1.ftd").as_deref(),
+            Some("a/b.ftd")
+        );
+        assert_eq!(
+            super::file_of_version("LICENSE.12").as_deref(),
+            Some("LICENSE")
+        );
+        assert_eq!(
+            super::file_of_version("v1.2/README.3").as_deref(),
+            Some("v1.2/README")
+        );
+        assert_eq!(super::file_of_version("a/b.ftd"), None);
+    }
+}
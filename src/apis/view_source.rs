@@ -50,7 +50,7 @@ pub(crate) async fn get_diff(
         let path = fpm::utils::history_path(&doc.get_id(), &doc.get_base_path(), timestamp);
         let content = tokio::fs::read_to_string(&doc.get_full_path()).await?;
 
-        let existing_doc = tokio::fs::read_to_string(&path).await?;
+        let existing_doc = fpm::history_store::read_to_string(&path).await?;
         if content.eq(&existing_doc) {
             return Ok(None);
         }
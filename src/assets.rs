@@ -0,0 +1,394 @@
+/// Length of the content hash put in fingerprinted file names: `a.css` => `a.<hash>.css`
+const FINGERPRINT_LEN: usize = 16;
+
+/// The fingerprint is computed on the content served at the fingerprinted url, after the
+/// references in it are rewritten and it is minified, so the url changes whenever those bytes do.
+pub(crate) fn fingerprint(content: &[u8]) -> String {
+    use sha2::Digest;
+    let hash = format!("{:x}", sha2::Sha256::digest(content));
+    hash[..FINGERPRINT_LEN].to_string()
+}
+
+/// `a/b.css`, `0123456789abcdef` => `a/b.0123456789abcdef.css`
+fn fingerprinted_path(path: &str, fingerprint: &str) -> String {
+    match path.rsplit_once('.') {
+        Some((name, ext)) if !ext.contains('/') => format!("{}.{}.{}", name, fingerprint, ext),
+        _ => format!("{}.{}", path, fingerprint),
+    }
+}
+
+/// `a/b.0123456789abcdef.css` => (`a/b.css`, `0123456789abcdef`)
+pub(crate) fn split_fingerprint(path: &str) -> Option<(String, String)> {
+    static FINGERPRINT: once_cell::sync::Lazy<regex::Regex> = once_cell::sync::Lazy::new(|| {
+        regex::Regex::new(r"^(?P<name>.+)\.(?P<hash>[0-9a-f]{16})(?P<ext>\.[^./]+)?$").unwrap()
+    });
+
+    let captures = FINGERPRINT.captures(path)?;
+    Some((
+        format!(
+            "{}{}",
+            &captures["name"],
+            captures.name("ext").map(|e| e.as_str()).unwrap_or_default()
+        ),
+        captures["hash"].to_string(),
+    ))
+}
+
+/// The urls in `href` and `src` attributes and in css `url()`, the only places asset urls are
+/// rewritten. Paths elsewhere, e.g. in the text of a code sample, are left alone.
+fn urls() -> &'static regex::Regex {
+    static URLS: once_cell::sync::Lazy<regex::Regex> = once_cell::sync::Lazy::new(|| {
+        regex::Regex::new(
+            r#"(?i)(?:\b(?:href|src)\s*=\s*|\burl\(\s*)(?:"(?P<double>[^"]*)"|'(?P<single>[^']*)'|(?P<bare>[^\s"'>)]+))"#,
+        )
+        .unwrap()
+    });
+    &URLS
+}
+
+fn url_in<'a>(captures: &regex::Captures<'a>) -> Option<regex::Match<'a>> {
+    captures
+        .name("double")
+        .or_else(|| captures.name("single"))
+        .or_else(|| captures.name("bare"))
+}
+
+/// `/x/-/a/b.css?v=1` with base url `/x/` => (`/x/`, `-/a/b.css`, `?v=1`), the path in `.build`
+/// a url refers to, and what is kept around it. A path is an asset only if the whole of it is,
+/// so `/-/a/b.css.map` is not `-/a/b.css`.
+fn asset_path<'a>(url: &'a str, base_url: &str) -> (&'a str, &'a str, &'a str) {
+    let (path, suffix) = url.split_at(url.find(['?', '#']).unwrap_or(url.len()));
+    let base_url = format!("/{}/", base_url.trim_matches('/')).replace("//", "/");
+    let prefix = if path.starts_with(base_url.as_str()) {
+        base_url.len()
+    } else {
+        path.len() - path.trim_start_matches('/').len()
+    };
+    (&path[..prefix], &path[prefix..], suffix)
+}
+
+/// `content` with the asset urls in `manifest` replaced by their fingerprinted urls
+fn rewrite(
+    content: &str,
+    manifest: &std::collections::BTreeMap<String, String>,
+    base_url: &str,
+) -> String {
+    urls()
+        .replace_all(content, |c: &regex::Captures| {
+            let whole = c.get(0).unwrap();
+            let url = match url_in(c) {
+                Some(url) => url,
+                None => return whole.as_str().to_string(),
+            };
+            let (prefix, path, suffix) = asset_path(url.as_str(), base_url);
+            match manifest.get(path) {
+                Some(hashed) => format!(
+                    "{}{}{}{}{}",
+                    &content[whole.start()..url.start()],
+                    prefix,
+                    hashed,
+                    suffix,
+                    &content[url.end()..whole.end()]
+                ),
+                None => whole.as_str().to_string(),
+            }
+        })
+        .to_string()
+}
+
+fn is_css(name: &str) -> bool {
+    matches!(camino::Utf8Path::new(name).extension(), Some("css"))
+}
+
+/// The other assets a css file refers to
+fn references(
+    name: &str,
+    assets: &std::collections::BTreeMap<String, Vec<u8>>,
+    base_url: &str,
+) -> Vec<String> {
+    let content = match assets.get(name).map(|v| std::str::from_utf8(v.as_slice())) {
+        Some(Ok(content)) if is_css(name) => content,
+        _ => return vec![],
+    };
+    urls()
+        .captures_iter(content)
+        .filter_map(|c| url_in(&c).map(|v| asset_path(v.as_str(), base_url).1))
+        .filter(|v| *v != name && assets.contains_key(*v))
+        .map(|v| v.to_string())
+        .collect()
+}
+
+/// Every asset after the ones it refers to, as its fingerprint depends on their fingerprinted
+/// urls. In a cycle the reference back stays on the original url, which is kept.
+fn dependency_order(
+    assets: &std::collections::BTreeMap<String, Vec<u8>>,
+    base_url: &str,
+) -> Vec<String> {
+    fn visit(
+        name: &str,
+        assets: &std::collections::BTreeMap<String, Vec<u8>>,
+        base_url: &str,
+        visited: &mut std::collections::HashSet<String>,
+        order: &mut Vec<String>,
+    ) {
+        if !visited.insert(name.to_string()) {
+            return;
+        }
+        for reference in references(name, assets, base_url) {
+            visit(reference.as_str(), assets, base_url, visited, order);
+        }
+        order.push(name.to_string());
+    }
+
+    let mut visited = Default::default();
+    let mut order = vec![];
+    for name in assets.keys() {
+        visit(name.as_str(), assets, base_url, &mut visited, &mut order);
+    }
+    order
+}
+
+/// (original, fingerprinted, content) of the `assets`, by path in `.build`. css files refer to
+/// the fingerprinted urls, css and js are minified.
+fn fingerprint_assets(
+    assets: &std::collections::BTreeMap<String, Vec<u8>>,
+    base_url: &str,
+) -> Vec<(String, String, Vec<u8>)> {
+    let mut manifest = std::collections::BTreeMap::new();
+    let mut fingerprinted = vec![];
+    for name in dependency_order(assets, base_url) {
+        let content = assets[&name].clone();
+        let content = match camino::Utf8Path::new(name.as_str()).extension() {
+            Some("css") => minify_css(match String::from_utf8(content) {
+                Ok(css) => rewrite(css.as_str(), &manifest, base_url).into_bytes(),
+                Err(e) => e.into_bytes(),
+            }),
+            Some("js") => minify_js(content),
+            _ => content,
+        };
+        let hashed = fingerprinted_path(name.as_str(), fingerprint(content.as_slice()).as_str());
+        manifest.insert(name.clone(), hashed.clone());
+        fingerprinted.push((name, hashed, content));
+    }
+    fingerprinted
+}
+
+/// `fpm build --fingerprint`, runs after all the documents are built.
+///
+/// Every static file in `.build/-/` is copied to a content hashed name, css and js files are
+/// minified, references to them in css files and the generated html files are replaced by the
+/// hashed names, with the `--base` url they are built with, and html files are minified. The mapping from original to hashed urls is
+/// written to `.build/-/assets.json`.
+///
+/// The original files are kept, so the urls not found in html, e.g. created by javascript,
+/// keep working.
+pub(crate) async fn build(config: &fpm::Config, base_url: &str) -> fpm::Result<()> {
+    let build_dir = config.build_dir();
+    let mut assets: std::collections::BTreeMap<String, Vec<u8>> = Default::default();
+
+    for path in files(build_dir.join("-").as_path())? {
+        let rel = path
+            .strip_prefix(&build_dir)?
+            .as_str()
+            .replace(std::path::MAIN_SEPARATOR, "/");
+        // responsive images are already content addressed
+        if rel.starts_with("-/images/")
+            || rel.eq("-/assets.json")
+            || rel.ends_with(".html")
+            || split_fingerprint(rel.as_str()).is_some()
+        {
+            continue;
+        }
+        assets.insert(rel, tokio::fs::read(&path).await?);
+    }
+
+    if assets.is_empty() {
+        return Ok(());
+    }
+
+    let mut manifest: std::collections::BTreeMap<String, String> = Default::default();
+    for (original, hashed, content) in fingerprint_assets(&assets, base_url) {
+        fpm::utils::update(build_dir.join(hashed.as_str()), content.as_slice()).await?;
+        manifest.insert(original, hashed);
+    }
+
+    for path in files(build_dir.as_path())? {
+        if !matches!(path.extension(), Some("html")) {
+            continue;
+        }
+        let content = tokio::fs::read_to_string(&path).await?;
+        let content = minify_html(rewrite(content.as_str(), &manifest, base_url).as_bytes());
+        fpm::utils::update(&path, content.as_slice()).await?;
+    }
+
+    let manifest: std::collections::BTreeMap<String, String> = manifest
+        .into_iter()
+        .map(|(original, hashed)| (format!("/{}", original), format!("/{}", hashed)))
+        .collect();
+    fpm::utils::update(
+        build_dir.join("-").join("assets.json"),
+        serde_json::to_vec_pretty(&manifest)?.as_slice(),
+    )
+    .await
+}
+
+fn files(dir: &camino::Utf8Path) -> fpm::Result<Vec<camino::Utf8PathBuf>> {
+    if !dir.exists() {
+        return Ok(vec![]);
+    }
+    let mut files = vec![];
+    for entry in ignore::WalkBuilder::new(dir)
+        .hidden(false)
+        .git_ignore(false)
+        .build()
+    {
+        let path = camino::Utf8PathBuf::from_path_buf(entry?.into_path())
+            .map_err(|p| fpm::Error::GenericError(format!("non utf8 path: {:?}", p)))?;
+        if path.is_file() {
+            files.push(path);
+        }
+    }
+    Ok(files)
+}
+
+fn minify_css(content: Vec<u8>) -> Vec<u8> {
+    if let Ok(css) = std::str::from_utf8(content.as_slice()) {
+        if let Ok(minified) = minifier::css::minify(css) {
+            return minified.to_string().into_bytes();
+        }
+    }
+    // invalid css is shipped as is, browsers are more forgiving than the minifier
+    content
+}
+
+fn minify_js(content: Vec<u8>) -> Vec<u8> {
+    if let Ok(js) = std::str::from_utf8(content.as_slice()) {
+        return minifier::js::minify(js).to_string().into_bytes();
+    }
+    content
+}
+
+fn minify_html(content: &[u8]) -> Vec<u8> {
+    let mut cfg = minify_html::Cfg::new();
+    cfg.minify_css = true;
+    cfg.minify_js = true;
+    minify_html::minify(content, &cfg)
+}
+
+/// `fpm serve` serves the `/-/<package>/<name>.<fingerprint>.<ext>` urls in the html built by
+/// `fpm build --fingerprint` from `.build`, where the build wrote the final content, with a long
+/// cache header. The original file is not served in its place, it is neither minified nor
+/// rewritten, so it is not the content the fingerprint is of.
+pub(crate) async fn serve(config: &fpm::Config, path: &str) -> Option<fpm::http::Response> {
+    let path = path.trim_start_matches('/');
+    let (original, hash) = split_fingerprint(path)?;
+    if !path.starts_with("-/") || path.split('/').any(|v| v == "..") {
+        return None;
+    }
+    let content = tokio::fs::read(config.build_dir().join(path)).await.ok()?;
+    if fingerprint(content.as_slice()) != hash {
+        return None;
+    }
+
+    Some(
+        actix_web::HttpResponse::Ok()
+            .content_type(mime_guess::from_path(original.as_str()).first_or_octet_stream())
+            .insert_header((
+                actix_web::http::header::CACHE_CONTROL,
+                "public, max-age=31536000, immutable",
+            ))
+            .body(content),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn fingerprinted_path() {
+        assert_eq!(
+            super::fingerprinted_path("-/fpm.dev/static/a.css", "0123456789abcdef"),
+            "-/fpm.dev/static/a.0123456789abcdef.css"
+        );
+        assert_eq!(
+            super::fingerprinted_path("-/fpm.dev/LICENSE", "0123456789abcdef"),
+            "-/fpm.dev/LICENSE.0123456789abcdef"
+        );
+    }
+
+    #[test]
+    fn split_fingerprint() {
+        assert_eq!(
+            super::split_fingerprint("-/fpm.dev/static/a.0123456789abcdef.css"),
+            Some((
+                "-/fpm.dev/static/a.css".to_string(),
+                "0123456789abcdef".to_string()
+            ))
+        );
+        assert_eq!(
+            super::split_fingerprint("-/fpm.dev/LICENSE.0123456789abcdef"),
+            Some((
+                "-/fpm.dev/LICENSE".to_string(),
+                "0123456789abcdef".to_string()
+            ))
+        );
+        assert_eq!(super::split_fingerprint("-/fpm.dev/static/a.css"), None);
+    }
+
+    #[test]
+    fn rewrite() {
+        let manifest = std::collections::BTreeMap::from([(
+            "-/fpm.dev/a.css".to_string(),
+            "-/fpm.dev/a.0123456789abcdef.css".to_string(),
+        )]);
+        assert_eq!(
+            super::rewrite(
+                r#"<link href="/-/fpm.dev/a.css"><link href='/-/fpm.dev/a.css.map'><a href=/x/-/fpm.dev/a.css>"#,
+                &manifest,
+                "/"
+            ),
+            r#"<link href="/-/fpm.dev/a.0123456789abcdef.css"><link href='/-/fpm.dev/a.css.map'><a href=/x/-/fpm.dev/a.css>"#
+        );
+        assert_eq!(
+            super::rewrite(
+                r#"<link href="/x/-/fpm.dev/a.css?v=1"><style>b { background: url('/x/-/fpm.dev/a.css'); }</style>"#,
+                &manifest,
+                "/x/"
+            ),
+            r#"<link href="/x/-/fpm.dev/a.0123456789abcdef.css?v=1"><style>b { background: url('/x/-/fpm.dev/a.0123456789abcdef.css'); }</style>"#
+        );
+        // a path in the text of the page is not a reference
+        let code = "<pre>&lt;link href=&quot;x&quot;&gt; /-/fpm.dev/a.css</pre>";
+        assert_eq!(super::rewrite(code, &manifest, "/"), code);
+    }
+
+    #[test]
+    fn fingerprint_assets() {
+        let assets = std::collections::BTreeMap::from([
+            (
+                "-/fpm.dev/a.css".to_string(),
+                b"@import url(/-/fpm.dev/b.css);".to_vec(),
+            ),
+            (
+                "-/fpm.dev/b.css".to_string(),
+                b"body { background: url(/-/fpm.dev/c.png); }".to_vec(),
+            ),
+            ("-/fpm.dev/c.png".to_string(), b"png".to_vec()),
+        ]);
+        let fingerprinted = super::fingerprint_assets(&assets, "/");
+        let names: Vec<&str> = fingerprinted.iter().map(|v| v.0.as_str()).collect();
+        assert_eq!(
+            names,
+            vec!["-/fpm.dev/c.png", "-/fpm.dev/b.css", "-/fpm.dev/a.css"]
+        );
+        for (original, hashed, content) in fingerprinted.iter() {
+            assert_eq!(
+                super::split_fingerprint(hashed),
+                Some((original.to_string(), super::fingerprint(content)))
+            );
+        }
+        let b = String::from_utf8(fingerprinted[1].2.clone()).unwrap();
+        assert!(b.contains(fingerprinted[0].1.as_str()), "{}", b);
+        let a = String::from_utf8(fingerprinted[2].2.clone()).unwrap();
+        assert!(a.contains(fingerprinted[1].1.as_str()), "{}", a);
+    }
+}
@@ -6,6 +6,7 @@ pub(crate) mod processor;
 pub(crate) mod routes;
 pub(crate) mod slack;
 pub(crate) mod telegram;
+pub(crate) mod token;
 pub mod utils;
 
 pub(crate) enum AuthProviders {
@@ -39,6 +40,55 @@ pub fn secret_key() -> String {
     }
 }
 
+/// Identity of the logged in user, recorded as author of the edits made from the browser,
+/// `github:<user name>` or `telegram:<user name>`, `None` if nobody is logged in. Google, Discord
+/// and Slack logins carry no user name yet, they are refused for authoring with a usage error.
+pub(crate) fn author_identity(
+    cookies: &std::collections::HashMap<String, String>,
+) -> fpm::Result<Option<String>> {
+    use magic_crypt::MagicCryptTrait;
+    let mc_obj = magic_crypt::new_magic_crypt!(&fpm::auth::secret_key(), 256);
+    let decrypt = |provider: AuthProviders| {
+        cookies
+            .get(provider.as_str())
+            .and_then(|v| mc_obj.decrypt_base64_to_string(v).ok())
+    };
+
+    if let Some(ud) = decrypt(AuthProviders::GitHub)
+        .and_then(|v| serde_json::from_str::<github::UserDetail>(v.as_str()).ok())
+    {
+        return Ok(Some(format!(
+            "{}:{}",
+            AuthProviders::GitHub.as_str(),
+            ud.user_name
+        )));
+    }
+    if let Some(ud) = decrypt(AuthProviders::TeleGram)
+        .and_then(|v| serde_json::from_str::<telegram::UserDetail>(v.as_str()).ok())
+    {
+        return Ok(Some(format!(
+            "{}:{}",
+            AuthProviders::TeleGram.as_str(),
+            ud.user_name
+        )));
+    }
+    for provider in [
+        AuthProviders::Google,
+        AuthProviders::Discord,
+        AuthProviders::Slack,
+    ] {
+        if cookies.contains_key(provider.as_str()) {
+            return fpm::usage_error(format!(
+                "Logged in with `{}`, which is not supported for authoring, login with `{}` or `{}`",
+                provider.as_str(),
+                AuthProviders::GitHub.as_str(),
+                AuthProviders::TeleGram.as_str()
+            ));
+        }
+    }
+    Ok(None)
+}
+
 // TODO: rename the method later
 // bridge between fpm to auth to check
 pub async fn get_auth_identities(
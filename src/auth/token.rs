@@ -0,0 +1,129 @@
+//! API tokens of the command line clients, `fpm login` gets one from `/-/login/token/`. A token
+//! carries the auth cookies of the logged in user, encrypted with the secret key like the cookies
+//! themselves, so the server maps it to the same identities as the browser session.
+
+/// A token is refused this long after it was created, so a leaked token does not give access
+/// forever, `fpm login` gets a new one
+const MAX_AGE: u128 = 30 * 24 * 60 * 60 * 1_000_000_000;
+
+#[derive(serde::Deserialize, serde::Serialize)]
+struct Token {
+    cookies: std::collections::HashMap<String, String>,
+    created: u128,
+}
+
+// route: /-/login/token/
+pub async fn token_page(req: &fpm::http::Request) -> fpm::Result<fpm::http::Response> {
+    let identity = match fpm::auth::author_identity(req.cookies())? {
+        Some(identity) => identity,
+        None => return Ok(fpm::unauthorised!(
+            "Login on this server first, then open this page again to get a token for `fpm login`"
+        )),
+    };
+    Ok(fpm::http::ok_with_content_type(
+        format!(
+            "Logged in as {}, paste this token in `fpm login`:\n\n{}\n",
+            identity,
+            create_token(req.cookies())?
+        )
+        .into_bytes(),
+        mime_guess::mime::TEXT_PLAIN_UTF_8,
+    ))
+}
+
+fn create_token(cookies: &std::collections::HashMap<String, String>) -> fpm::Result<String> {
+    let cookies = [
+        fpm::auth::AuthProviders::GitHub,
+        fpm::auth::AuthProviders::TeleGram,
+        fpm::auth::AuthProviders::Google,
+        fpm::auth::AuthProviders::Discord,
+        fpm::auth::AuthProviders::Slack,
+    ]
+    .iter()
+    .filter_map(|provider| {
+        cookies
+            .get(provider.as_str())
+            .map(|v| (provider.as_str().to_string(), v.to_string()))
+    })
+    .collect();
+    encrypt(&Token {
+        cookies,
+        created: fpm::timestamp_nanosecond(),
+    })
+}
+
+fn encrypt(token: &Token) -> fpm::Result<String> {
+    use magic_crypt::MagicCryptTrait;
+
+    let token = serde_json::to_string(token)?;
+    Ok(magic_crypt::new_magic_crypt!(&fpm::auth::secret_key(), 256).encrypt_str_to_base64(token))
+}
+
+/// Request with the cookies of the token in its `Authorization: Bearer <token>` header, the
+/// identities of the token are then checked as if the request came from the browser session
+pub(crate) fn authenticate(req: fpm::http::Request) -> fpm::Result<fpm::http::Request> {
+    use magic_crypt::MagicCryptTrait;
+
+    let token = match req
+        .headers()
+        .get(reqwest::header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+    {
+        Some(token) => token.trim().to_string(),
+        None => return Ok(req),
+    };
+    let token: Token = magic_crypt::new_magic_crypt!(&fpm::auth::secret_key(), 256)
+        .decrypt_base64_to_string(token)
+        .ok()
+        .and_then(|v| serde_json::from_str(v.as_str()).ok())
+        .ok_or_else(|| fpm::Error::UsageError {
+            message: "Invalid API token, get a new one with `fpm login`".to_string(),
+        })?;
+    if fpm::timestamp_nanosecond().saturating_sub(token.created) > MAX_AGE {
+        return fpm::usage_error(
+            "API token has expired, get a new one with `fpm login`".to_string(),
+        );
+    }
+    Ok(req.with_cookies(token.cookies))
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn token() {
+        let mut cookies = std::collections::HashMap::new();
+        cookies.insert("github".to_string(), "encrypted".to_string());
+        cookies.insert("other".to_string(), "value".to_string());
+        let token = super::create_token(&cookies).unwrap();
+
+        let req = actix_web::test::TestRequest::default()
+            .insert_header(("authorization", format!("Bearer {}", token)))
+            .to_http_request();
+        let req = super::authenticate(fpm::http::Request::from_actix(
+            req,
+            actix_web::web::Bytes::new(),
+        ))
+        .unwrap();
+        assert_eq!(req.cookie("github").as_deref(), Some("encrypted"));
+        assert_eq!(req.cookie("other"), None);
+    }
+
+    #[test]
+    fn expired_token() {
+        let token = super::encrypt(&super::Token {
+            cookies: Default::default(),
+            created: fpm::timestamp_nanosecond() - super::MAX_AGE - 1,
+        })
+        .unwrap();
+
+        let req = actix_web::test::TestRequest::default()
+            .insert_header(("authorization", format!("Bearer {}", token)))
+            .to_http_request();
+        assert!(super::authenticate(fpm::http::Request::from_actix(
+            req,
+            actix_web::web::Bytes::new(),
+        ))
+        .is_err());
+    }
+}
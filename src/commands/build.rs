@@ -3,6 +3,7 @@ pub async fn build(
     file: Option<&str>,
     base_url: &str,
     ignore_failed: bool,
+    fingerprint: bool,
 ) -> fpm::Result<()> {
     tokio::fs::create_dir_all(config.build_dir()).await?;
     let documents = get_documents_for_current_package(config).await?;
@@ -101,8 +102,115 @@ pub async fn build(
         );
     }
 
+    if file.is_none() {
+        build_dynamic_urls(config, base_url, ignore_failed).await?;
+    }
+
     if !no_static {
         config.download_fonts().await?;
+        fpm::error_page::build(config, base_url).await?;
+        fpm::package::redirects::build(config).await?;
+        fpm::package::languages::build_sitemap(config, &documents).await?;
+        if fingerprint {
+            fpm::assets::build(config, base_url).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Renders a page for every row of the parameter source of each dynamic url, with the path
+/// parameters set like `fpm serve` does for a request to the url
+async fn build_dynamic_urls(
+    config: &mut fpm::Config,
+    base_url: &str,
+    ignore_failed: bool,
+) -> fpm::Result<()> {
+    let dynamic_urls = match config.package.dynamic_urls.as_ref() {
+        Some(dynamic_urls) => dynamic_urls.dynamic_urls()?,
+        None => return Ok(()),
+    };
+
+    for dynamic_url in dynamic_urls {
+        let param_source = match dynamic_url.param_source {
+            Some(ref param_source) => param_source,
+            None => {
+                fpm::warning!(
+                    "{} has no parameter source, it is only available with `fpm serve`",
+                    dynamic_url.url
+                );
+                continue;
+            }
+        };
+        for row in param_source.rows(config).await? {
+            // the path parameters are the values of the row as they are, and the page is written
+            // where a static host looks for the decoded request path
+            let path = dynamic_url.path_with_params(&row)?;
+            let path_parameters =
+                fpm::sitemap::utils::parse_named_params(path.as_str(), dynamic_url.url.as_str())
+                    .map_err(|e| fpm::Error::UsageError {
+                        message: format!(
+                            "{} does not match {}, check the parameter types: {}",
+                            path, dynamic_url.url, e
+                        ),
+                    })?;
+            let mut main = fpm::get_file(
+                config.package.name.to_string(),
+                &config
+                    .root
+                    .join(dynamic_url.document.trim_start_matches('/')),
+                &config.root,
+            )
+            .await?;
+            let extension = if matches!(main, fpm::File::Markdown(_)) {
+                "index.md"
+            } else {
+                "index.ftd"
+            };
+            main.set_id(format!("{}/{}", path.trim_matches('/'), extension).as_str());
+            config.current_document = Some(main.get_id());
+            config.path_parameters = path_parameters;
+            let start = std::time::Instant::now();
+
+            print!(
+                "Processing {}/{} ... ",
+                config.package.name.as_str(),
+                main.get_id()
+            );
+            let resp = match main {
+                fpm::File::Ftd(ref doc) => {
+                    fpm::package::package_doc::process_ftd(config, doc, base_url, false)
+                        .await
+                        .map(|_| ())
+                }
+                fpm::File::Markdown(ref doc) => {
+                    process_markdown(config, doc, base_url, false).await
+                }
+                _ => fpm::usage_error(format!(
+                    "The document of {} should be an ftd or markdown file: {}",
+                    dynamic_url.url, dynamic_url.document
+                )),
+            };
+            config.path_parameters = vec![];
+            match (resp, ignore_failed) {
+                (Ok(_), _) => (),
+                (_, true) => {
+                    println!("Failed");
+                    continue;
+                }
+                (Err(e), _) => {
+                    return Err(e);
+                }
+            }
+            fpm::utils::print_end(
+                format!(
+                    "Processed {}/{}",
+                    config.package.name.as_str(),
+                    main.get_id()
+                )
+                .as_str(),
+                start,
+            );
+        }
     }
     Ok(())
 }
@@ -195,6 +303,15 @@ async fn process_image(
     base_url: &str,
     no_static: bool,
 ) -> fpm::Result<()> {
+    // an image the variants can't be generated for, e.g. a corrupt or unsupported one, is still
+    // shipped as is, `process_static` has already copied the original
+    if let Err(e) = fpm::responsive_image::build(config, main).await {
+        fpm::warning!(
+            "{}: responsive variants are not generated, using the original image: {}",
+            main.id,
+            e
+        );
+    }
     let main = convert_to_ftd(config, main)?;
 
     fpm::package::package_doc::process_ftd(config, &main, base_url, no_static).await?;
@@ -0,0 +1,53 @@
+pub const COMMAND: &str = "check-sitemap";
+
+pub fn command() -> clap::Command {
+    clap::Command::new(COMMAND)
+        .about("Check the sitemap for orphan or missing documents, duplicate urls, undefined user groups and shadowed entries")
+        .hide(true) // hidden since the feature is not being released yet.
+}
+
+pub async fn handle_command(_matches: &clap::ArgMatches) -> fpm::Result<()> {
+    let config = fpm::Config::read(None, false, None).await?;
+    if config.package.sitemap.is_none() && config.package.dynamic_urls.is_none() {
+        println!("No `fpm.sitemap` or `fpm.dynamic-urls` in FPM.ftd, nothing to check");
+        return Ok(());
+    }
+
+    let documents: Vec<String> = config
+        .get_files(&config.package)
+        .await?
+        .into_iter()
+        .filter(|v| matches!(v, fpm::File::Ftd(_) | fpm::File::Markdown(_)))
+        .map(|v| v.get_id())
+        .filter(|v| !fpm::sitemap::auto::NOT_IN_SITEMAP.contains(&v.as_str()))
+        .collect();
+    let groups: Vec<&str> = config.package.groups.keys().map(String::as_str).collect();
+    let resolve = |id: &str| {
+        let document = id.trim_start_matches('/');
+        if document.ends_with(".ftd") || document.ends_with(".md") {
+            return if config.root.join(document).exists() {
+                Some(document.to_string())
+            } else {
+                None
+            };
+        }
+        fpm::Config::get_file_name(&config.root, id).ok()
+    };
+
+    let problems = fpm::sitemap::check::check(
+        config.package.sitemap.as_ref(),
+        config.package.dynamic_urls.as_ref(),
+        config.package.name.as_str(),
+        &documents,
+        &groups,
+        resolve,
+    );
+    if problems.is_empty() {
+        println!("The sitemap is fine");
+        return Ok(());
+    }
+    for problem in problems.iter() {
+        println!("{}", problem);
+    }
+    fpm::usage_error(format!("{} problems found in the sitemap", problems.len()))
+}
@@ -0,0 +1,233 @@
+pub const COMMAND: &str = "cr";
+
+pub fn command() -> clap::Command {
+    let cr = || clap::arg!(cr: <CR> "The change request number");
+    clap::Command::new(COMMAND)
+        .about("Review change requests")
+        .subcommand_required(true)
+        .subcommand(
+            clap::Command::new("list")
+                .about("List the change requests with their review status")
+                .arg(clap::arg!(--all "Also list the closed change requests")),
+        )
+        .subcommand(
+            clap::Command::new("show")
+                .about("Show a change request, its reviews and the combined diff against main")
+                .arg(cr()),
+        )
+        .subcommand(
+            clap::Command::new("comment")
+                .about("Comment on a line of a file changed in the change request")
+                .arg(cr())
+                .arg(clap::arg!(file: <FILE> "The file to comment on"))
+                .arg(
+                    clap::arg!(--line <LINE> "The line number in the file of the change request")
+                        .value_parser(clap::value_parser!(usize))
+                        .required(true),
+                )
+                .arg(clap::arg!(-m --message <MESSAGE> "The comment").required(true)),
+        )
+        .subcommand(
+            clap::Command::new("approve")
+                .about("Approve the change request for merging into main")
+                .arg(cr())
+                .arg(clap::arg!(-m --message <MESSAGE> "Review message")),
+        )
+        .subcommand(
+            clap::Command::new("request-changes")
+                .about("Request changes before the change request is merged into main")
+                .arg(cr())
+                .arg(clap::arg!(-m --message <MESSAGE> "What needs to change").required(true)),
+        )
+        .hide(true) // hidden since the feature is not being released yet.
+}
+
+pub async fn handle_command(matches: &clap::ArgMatches) -> fpm::Result<()> {
+    use fpm::utils::ValueOf;
+
+    let config = fpm::Config::read(None, true, None).await?;
+    let cr_number = |matches: &clap::ArgMatches| -> fpm::Result<usize> {
+        Ok(matches.value_of_("cr").unwrap().parse::<usize>()?)
+    };
+
+    match matches.subcommand() {
+        Some(("list", matches)) => list(&config, matches.get_flag("all")).await,
+        Some(("show", matches)) => show(&config, cr_number(matches)?).await,
+        Some(("comment", matches)) => {
+            send_to_fpm_serve(
+                "/-/cr-comment/",
+                &fpm::apis::cr::CRCommentRequest {
+                    cr: cr_number(matches)?,
+                    filename: matches.value_of_("file").unwrap().to_string(),
+                    line: matches.get_one::<usize>("line").copied().unwrap(),
+                    message: matches.value_of_("message").unwrap().to_string(),
+                },
+            )
+            .await
+        }
+        Some((command, matches)) => {
+            let status = match command {
+                "approve" => fpm::cr::ReviewStatus::Approved,
+                "request-changes" => fpm::cr::ReviewStatus::ChangesRequested,
+                _ => unreachable!("unknown cr subcommand: {}", command),
+            };
+            send_to_fpm_serve(
+                "/-/cr-review/",
+                &fpm::apis::cr::CRReviewRequest {
+                    cr: cr_number(matches)?,
+                    status,
+                    message: matches.value_of_("message").map(ToString::to_string),
+                },
+            )
+            .await
+        }
+        None => unreachable!("subcommand is required"),
+    }
+}
+
+async fn list(config: &fpm::Config, all: bool) -> fpm::Result<()> {
+    use colored::Colorize;
+
+    for cr_meta in fpm::cr::get_cr_list(config).await? {
+        if !cr_meta.open && !all {
+            continue;
+        }
+        let status = if cr_meta.open {
+            match fpm::cr::get_cr_reviews(config, cr_meta.cr_number)
+                .await?
+                .status()
+            {
+                Some(status) => status.as_str(),
+                None => "not reviewed",
+            }
+        } else {
+            "closed"
+        };
+        println!(
+            "{} {} ({})",
+            format!("#{}", cr_meta.cr_number).yellow(),
+            cr_meta.title,
+            status
+        );
+    }
+    Ok(())
+}
+
+async fn show(config: &fpm::Config, cr_number: usize) -> fpm::Result<()> {
+    use colored::Colorize;
+
+    let cr_meta = fpm::cr::get_cr_meta(config, cr_number).await?;
+    let cr_reviews = fpm::cr::get_cr_reviews(config, cr_number).await?;
+
+    println!("{} {}", format!("CR#{}", cr_number).yellow(), cr_meta.title);
+    println!(
+        "Status: {}",
+        if !cr_meta.open {
+            "closed"
+        } else {
+            cr_reviews
+                .status()
+                .map(|v| v.as_str())
+                .unwrap_or("not reviewed")
+        }
+    );
+    for review in cr_reviews.latest().values() {
+        println!(
+            "    {} {} on {}",
+            review.author,
+            review.status.as_str(),
+            fpm::commands::log::format_timestamp(review.timestamp)
+        );
+        if let Some(ref message) = review.message {
+            for line in message.lines() {
+                println!("        {}", line);
+            }
+        }
+    }
+    println!();
+
+    let cr_diff = fpm::cr::cr_diff(config, cr_number).await?;
+    for file_diff in cr_diff.iter() {
+        println!(
+            "{}",
+            format!("diff {} ({:?})", file_diff.filename, file_diff.operation).yellow()
+        );
+        match file_diff.diff {
+            Some(ref diff) => {
+                let patch = diffy::Patch::from_str(diff.as_str())
+                    .map_err(|e| fpm::Error::GenericError(e.to_string()))?;
+                println!(
+                    "{}",
+                    diffy::PatchFormatter::new().with_color().fmt_patch(&patch)
+                );
+            }
+            None => println!("Binary file\n"),
+        }
+        print_comments(
+            cr_reviews
+                .comments
+                .iter()
+                .filter(|v| v.filename.eq(&file_diff.filename)),
+        );
+    }
+
+    // comments on files which are not changed in the cr anymore
+    print_comments(
+        cr_reviews
+            .comments
+            .iter()
+            .filter(|v| !cr_diff.iter().any(|d| d.filename.eq(&v.filename))),
+    );
+    Ok(())
+}
+
+fn print_comments<'a>(comments: impl Iterator<Item = &'a fpm::cr::CRComment>) {
+    use colored::Colorize;
+
+    for comment in comments {
+        println!(
+            "{} {} on {}",
+            format!("{}:{}", comment.filename, comment.line).blue(),
+            comment.author,
+            fpm::commands::log::format_timestamp(comment.timestamp)
+        );
+        for line in comment.message.lines() {
+            println!("    {}", line);
+        }
+        println!();
+    }
+}
+
+/// Reviews are recorded by the server, as the user of the token saved by `fpm login`, so nobody
+/// can review in the name of someone else. They are in the cr directory after the next `fpm sync`.
+async fn send_to_fpm_serve(api: &str, body: &impl serde::Serialize) -> fpm::Result<()> {
+    #[derive(serde::Deserialize, std::fmt::Debug)]
+    struct ApiResponse {
+        message: Option<String>,
+        data: Option<fpm::apis::cr::CRReviewResponse>,
+        success: bool,
+    }
+
+    let response: ApiResponse = crate::http::post_json(
+        format!("http://127.0.0.1:8000{}", api).as_str(),
+        serde_json::to_string(body)?,
+    )
+    .await?;
+    if !response.success {
+        return Err(fpm::Error::APIResponseError(
+            response
+                .message
+                .unwrap_or_else(|| "Some Error occurred".to_string()),
+        ));
+    }
+
+    match response.data {
+        Some(data) => {
+            println!("Review recorded: http://127.0.0.1:8000{}", data.url);
+            Ok(())
+        }
+        None => Err(fpm::Error::APIResponseError(
+            "Unexpected API behaviour".to_string(),
+        )),
+    }
+}
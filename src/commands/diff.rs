@@ -22,6 +22,64 @@ pub async fn diff(config: &fpm::Config, files: Option<Vec<String>>, all: bool) -
     Ok(())
 }
 
+/// `fpm diff --from <version> [--to <version>] <file>...`, compares two versions of the files
+/// from history, or a version with the current content if `to` is not passed.
+pub async fn diff_versions(
+    config: &fpm::Config,
+    files: Vec<String>,
+    from: &str,
+    to: Option<&str>,
+) -> fpm::Result<()> {
+    if files.is_empty() {
+        return fpm::usage_error(
+            "`--from` needs the file(s) to compare, Help: Use `fpm diff --from <version> <file>`"
+                .to_string(),
+        );
+    }
+    let from = from.parse::<i32>()?;
+    let to = match to {
+        Some(to) => Some(to.parse::<i32>()?),
+        None => None,
+    };
+
+    for file in files {
+        let from_edit =
+            fpm::commands::show::get_file_edit(config, file.as_str(), Some(from)).await?;
+        let from_content = fpm::history_store::read_to_string(
+            &config.history_path(file.as_str(), from_edit.version),
+        )
+        .await?;
+        let (to_content, to_name) = match to {
+            Some(to) => {
+                let to_edit =
+                    fpm::commands::show::get_file_edit(config, file.as_str(), Some(to)).await?;
+                (
+                    fpm::history_store::read_to_string(
+                        &config.history_path(file.as_str(), to_edit.version),
+                    )
+                    .await?,
+                    format!("{}@{}", file, to),
+                )
+            }
+            None => (
+                tokio::fs::read_to_string(config.root.join(file.as_str())).await?,
+                file.to_string(),
+            ),
+        };
+        if from_content.eq(&to_content) {
+            continue;
+        }
+        let patch = diffy::create_patch(&from_content, &to_content);
+        let diff = diffy::PatchFormatter::new()
+            .with_color()
+            .fmt_patch(&patch)
+            .to_string();
+        println!("diff {}@{} -> {}", file, from, to_name);
+        println!("{}", diff);
+    }
+    Ok(())
+}
+
 async fn get_diffy(
     doc: &fpm::File,
     snapshots: &std::collections::BTreeMap<String, u128>,
@@ -30,7 +88,7 @@ async fn get_diffy(
         let path = fpm::utils::history_path(&doc.get_id(), &doc.get_base_path(), timestamp);
         let content = tokio::fs::read_to_string(&doc.get_full_path()).await?;
 
-        let existing_doc = tokio::fs::read_to_string(&path).await?;
+        let existing_doc = fpm::history_store::read_to_string(&path).await?;
         if content.eq(&existing_doc) {
             return Ok(None);
         }
@@ -68,8 +126,8 @@ async fn get_track_diff(
                 track.other_timestamp.as_ref().unwrap(),
             );
 
-            let now_doc = tokio::fs::read_to_string(&now_path).await?;
-            let then_doc = tokio::fs::read_to_string(&then_path).await?;
+            let now_doc = fpm::history_store::read_to_string(&now_path).await?;
+            let then_doc = fpm::history_store::read_to_string(&then_path).await?;
             if now_doc.eq(&then_doc) {
                 continue;
             }
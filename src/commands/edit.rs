@@ -34,8 +34,8 @@ pub async fn edit(config: &fpm::Config, file: &str, cr: &str) -> fpm::Result<()>
         });
     }
 
-    if file_path.exists() {
-        let content = tokio::fs::read(&file_path).await?;
+    if fpm::history_store::exists(&file_path) {
+        let content = fpm::history_store::read(&file_path).await?;
         fpm::utils::update(&cr_file_path, content.as_slice()).await?;
     } else {
         fpm::utils::update(&cr_file_path, vec![].as_slice()).await?;
@@ -0,0 +1,380 @@
+pub const COMMAND: &str = "export-git";
+
+pub fn command() -> clap::Command {
+    clap::Command::new(COMMAND)
+        .about("Replay the history of this fpm package as git commits, with CRs as branches")
+        .arg(clap::arg!(repo: <REPO> "The git repository to export to, created if it does not exist"))
+        .arg(clap::arg!(--branch <BRANCH> "The git branch for main").default_value("main"))
+        .arg(clap::arg!(--force "Overwrite the existing branches of the git repository"))
+        .hide(true) // hidden since the feature is not being released yet.
+}
+
+pub async fn handle_command(matches: &clap::ArgMatches) -> fpm::Result<()> {
+    use fpm::utils::ValueOf;
+
+    export_git(
+        &fpm::Config::read(None, true, None).await?,
+        camino::Utf8Path::new(matches.value_of_("repo").unwrap()),
+        matches.value_of_("branch").unwrap(),
+        matches.get_flag("force"),
+    )
+    .await
+}
+
+/// Edits synced together, they share the timestamp, author and message, and become one commit.
+/// `cr` is set for the edits of files in a CR, which go to the `cr-<cr number>` branch.
+#[derive(Debug, PartialEq)]
+struct Commit {
+    timestamp: u128,
+    author: Option<String>,
+    message: String,
+    cr: Option<usize>,
+    /// Set for the edits merged from a CR into main, the CR branch becomes a parent
+    src_cr: Option<usize>,
+    changes: Vec<Change>,
+}
+
+#[derive(Debug, PartialEq)]
+enum Change {
+    Modify { path: String, content: Vec<u8> },
+    Delete { path: String },
+}
+
+async fn export_git(
+    config: &fpm::Config,
+    repo: &camino::Utf8Path,
+    main_branch: &str,
+    force: bool,
+) -> fpm::Result<()> {
+    if !config.history_file().exists() {
+        return fpm::usage_error(
+            "Nothing to export, the package has no history, Help: Use `fpm sync`".to_string(),
+        );
+    }
+    let commits = get_commits(config, &config.get_history().await?).await?;
+
+    let new_repo = !repo.join(".git").exists();
+    if new_repo {
+        tokio::fs::create_dir_all(repo).await?;
+        git(repo, &["init", "--quiet"], None)?;
+    }
+    let mut args = vec!["fast-import", "--quiet"];
+    if force {
+        args.push("--force");
+    }
+    git(
+        repo,
+        args.as_slice(),
+        Some(fast_import_stream(commits.as_slice(), main_branch).as_slice()),
+    )?;
+    if new_repo {
+        let head = format!("refs/heads/{}", main_branch);
+        git(repo, &["symbolic-ref", "HEAD", head.as_str()], None)?;
+        git(repo, &["reset", "--hard", "--quiet"], None)?;
+    }
+
+    println!("Exported {} commits to {}", commits.len(), repo.as_str());
+    Ok(())
+}
+
+async fn get_commits(
+    config: &fpm::Config,
+    history: &[fpm::history::FileHistory],
+) -> fpm::Result<Vec<Commit>> {
+    use itertools::Itertools;
+
+    let groups = history
+        .iter()
+        .flat_map(|v| v.file_edit.iter().map(move |e| (v.filename.as_str(), e)))
+        .sorted_by_key(|(filename, e)| (e.timestamp, file_cr(filename), *filename))
+        .group_by(|(filename, e)| {
+            (
+                e.timestamp,
+                e.author.clone(),
+                e.message.clone(),
+                file_cr(filename),
+            )
+        })
+        .into_iter()
+        .map(|(key, edits)| (key, edits.collect_vec()))
+        .collect_vec();
+
+    let mut commits = vec![];
+    for ((timestamp, author, message, cr), edits) in groups {
+        let mut changes = vec![];
+        for (filename, file_edit) in edits.iter() {
+            let path = branch_path(filename, cr);
+            if file_edit.is_deleted() {
+                changes.push(Change::Delete { path });
+                continue;
+            }
+            let content =
+                fpm::history_store::read(&config.history_path(filename, file_edit.version)).await?;
+            if let Some(cr) = cr.filter(|_| path.eq("-/deleted.ftd")) {
+                // files deleted in the CR are removed from the CR branch
+                for cr_deleted in
+                    fpm::cr::resolve_cr_deleted(String::from_utf8(content.clone())?.as_str(), cr)
+                        .await?
+                {
+                    changes.push(Change::Delete {
+                        path: cr_deleted.filename,
+                    });
+                }
+            }
+            changes.push(Change::Modify { path, content });
+        }
+        let message = message.unwrap_or_else(|| {
+            edits
+                .iter()
+                .map(|(filename, e)| format!("{:?} {}", e.operation, branch_path(filename, cr)))
+                .join("\n")
+        });
+        commits.push(Commit {
+            timestamp,
+            author,
+            message,
+            cr,
+            src_cr: edits
+                .iter()
+                .find_map(|(_, e)| e.src_cr)
+                .filter(|_| cr.is_none()),
+            changes,
+        });
+    }
+    Ok(commits)
+}
+
+/// The CR a file of the package belongs to, the CR files and their tracking info are in `-/<cr>/`
+fn file_cr(filename: &str) -> Option<usize> {
+    fpm::cr::get_cr_path_from_url(filename.strip_prefix(".tracks/").unwrap_or(filename))
+}
+
+/// Path of the file in the git branch, `-/<cr>/index.ftd` is `index.ftd` in the CR branch, and
+/// `.tracks/-/<cr>/index.ftd.track` is `.tracks/index.ftd.track`
+fn branch_path(filename: &str, cr: Option<usize>) -> String {
+    let cr = match cr {
+        Some(cr) => cr,
+        None => return filename.to_string(),
+    };
+    let cr_path = format!("{}/", fpm::cr::cr_path(cr));
+    if let Some(path) = filename.strip_prefix(cr_path.as_str()) {
+        return path.to_string();
+    }
+    if let Some(path) = filename
+        .strip_prefix(".tracks/")
+        .and_then(|v| v.strip_prefix(cr_path.as_str()))
+    {
+        return format!(".tracks/{}", path);
+    }
+    filename.to_string()
+}
+
+/// `Name <email>` authors are used as is, others like `github:amitu` get an empty email
+fn git_ident(author: Option<&str>) -> String {
+    match author {
+        Some(author) if author.contains(" <") && author.ends_with('>') => author.to_string(),
+        Some(author) => format!("{} <>", author.replace(|c| c == '<' || c == '>', "")),
+        None => "fpm <>".to_string(),
+    }
+}
+
+/// Input for `git fast-import`, see https://git-scm.com/docs/git-fast-import
+fn fast_import_stream(commits: &[Commit], main_branch: &str) -> Vec<u8> {
+    use std::io::Write;
+
+    let mut stream = vec![];
+    let mut main_head: Option<usize> = None;
+    let mut cr_heads: std::collections::HashMap<usize, usize> = Default::default();
+
+    for (mark, commit) in commits.iter().enumerate().map(|(i, c)| (i + 1, c)) {
+        let (branch, head) = match commit.cr {
+            Some(cr) => (format!("cr-{}", cr), cr_heads.get(&cr).copied()),
+            None => (main_branch.to_string(), main_head),
+        };
+        if head.is_none() {
+            // start afresh, instead of continuing the existing branch of the repository
+            writeln!(stream, "reset refs/heads/{}", branch).unwrap();
+        }
+        let ident = format!(
+            "{} {} +0000",
+            git_ident(commit.author.as_deref()),
+            commit.timestamp / 1_000_000_000
+        );
+        writeln!(stream, "commit refs/heads/{}", branch).unwrap();
+        writeln!(stream, "mark :{}", mark).unwrap();
+        writeln!(stream, "author {}", ident).unwrap();
+        writeln!(stream, "committer {}", ident).unwrap();
+        writeln!(stream, "data {}", commit.message.len()).unwrap();
+        writeln!(stream, "{}", commit.message).unwrap();
+        match (head, commit.cr, main_head) {
+            (Some(head), _, _) => writeln!(stream, "from :{}", head).unwrap(),
+            // CR branches start from main
+            (None, Some(_), Some(main_head)) => writeln!(stream, "from :{}", main_head).unwrap(),
+            _ => {}
+        }
+        if let Some(src_head) = commit.src_cr.and_then(|cr| cr_heads.get(&cr)) {
+            writeln!(stream, "merge :{}", src_head).unwrap();
+        }
+        for change in commit.changes.iter() {
+            match change {
+                Change::Modify { path, content } => {
+                    writeln!(stream, "M 100644 inline {}", path).unwrap();
+                    writeln!(stream, "data {}", content.len()).unwrap();
+                    stream.extend_from_slice(content);
+                    writeln!(stream).unwrap();
+                }
+                Change::Delete { path } => writeln!(stream, "D {}", path).unwrap(),
+            }
+        }
+        writeln!(stream).unwrap();
+
+        match commit.cr {
+            Some(cr) => {
+                cr_heads.insert(cr, mark);
+            }
+            None => main_head = Some(mark),
+        }
+    }
+    stream
+}
+
+/// Runs git in `dir`, and returns the output, `stdin` is written to the input of git
+pub(crate) fn git(
+    dir: &camino::Utf8Path,
+    args: &[&str],
+    stdin: Option<&[u8]>,
+) -> fpm::Result<Vec<u8>> {
+    use std::io::Write;
+
+    let mut child = std::process::Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .stdin(if stdin.is_some() {
+            std::process::Stdio::piped()
+        } else {
+            std::process::Stdio::null()
+        })
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()?;
+    // written from a thread, so git does not block on a full stdout while we write
+    let writer = match (stdin, child.stdin.take()) {
+        (Some(stdin), Some(mut pipe)) => {
+            let stdin = stdin.to_vec();
+            Some(std::thread::spawn(move || pipe.write_all(stdin.as_slice())))
+        }
+        _ => None,
+    };
+    let output = child.wait_with_output()?;
+    if let Some(writer) = writer {
+        writer
+            .join()
+            .map_err(|_| fpm::Error::GenericError("failed to write to git".to_string()))??;
+    }
+    if !output.status.success() {
+        return Err(fpm::Error::GenericError(format!(
+            "`git {}` failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(output.stderr.as_slice()).trim()
+        )));
+    }
+    Ok(output.stdout)
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn branch_path() {
+        assert_eq!(super::branch_path("index.ftd", None), "index.ftd");
+        assert_eq!(super::branch_path("-/2/index.ftd", Some(2)), "index.ftd");
+        assert_eq!(super::branch_path("-/2/-/meta.ftd", Some(2)), "-/meta.ftd");
+        assert_eq!(
+            super::branch_path(".tracks/-/2/index.ftd.track", Some(2)),
+            ".tracks/index.ftd.track"
+        );
+    }
+
+    #[test]
+    fn git_ident() {
+        assert_eq!(super::git_ident(Some("github:amitu")), "github:amitu <>");
+        assert_eq!(
+            super::git_ident(Some("Amit Upadhyay <upadhyay@gmail.com>")),
+            "Amit Upadhyay <upadhyay@gmail.com>"
+        );
+        assert_eq!(super::git_ident(None), "fpm <>");
+    }
+
+    #[test]
+    fn fast_import_stream() {
+        let commits = vec![
+            super::Commit {
+                timestamp: 1_000_000_000,
+                author: Some("github:amitu".to_string()),
+                message: "hello".to_string(),
+                cr: None,
+                src_cr: None,
+                changes: vec![super::Change::Modify {
+                    path: "index.ftd".to_string(),
+                    content: b"-- ftd.text: hello".to_vec(),
+                }],
+            },
+            super::Commit {
+                timestamp: 2_000_000_000,
+                author: None,
+                message: "world".to_string(),
+                cr: Some(1),
+                src_cr: None,
+                changes: vec![super::Change::Delete {
+                    path: "index.ftd".to_string(),
+                }],
+            },
+            super::Commit {
+                timestamp: 3_000_000_000,
+                author: None,
+                message: "merged".to_string(),
+                cr: None,
+                src_cr: Some(1),
+                changes: vec![super::Change::Delete {
+                    path: "index.ftd".to_string(),
+                }],
+            },
+        ];
+        assert_eq!(
+            String::from_utf8(super::fast_import_stream(commits.as_slice(), "main")).unwrap(),
+            indoc::indoc! {"
+                reset refs/heads/main
+                commit refs/heads/main
+                mark :1
+                author github:amitu <> 1 +0000
+                committer github:amitu <> 1 +0000
+                data 5
+                hello
+                M 100644 inline index.ftd
+                data 18
+                -- ftd.text: hello
+
+                reset refs/heads/cr-1
+                commit refs/heads/cr-1
+                mark :2
+                author fpm <> 2 +0000
+                committer fpm <> 2 +0000
+                data 5
+                world
+                from :1
+                D index.ftd
+
+                commit refs/heads/main
+                mark :3
+                author fpm <> 3 +0000
+                committer fpm <> 3 +0000
+                data 6
+                merged
+                from :1
+                merge :2
+                D index.ftd
+
+            "}
+        );
+    }
+}
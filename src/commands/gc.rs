@@ -0,0 +1,96 @@
+pub const COMMAND: &str = "gc";
+
+pub fn command() -> clap::Command {
+    clap::Command::new(COMMAND)
+        .about("Pack old versions of files in history into compressed packfiles")
+        .arg(
+            clap::arg!(--"prune-older-than" <DAYS> "Remove snapshots in .history older than these many days, except the latest and the tracked ones")
+                .value_parser(clap::value_parser!(u64)),
+        )
+        .hide(true) // hidden since the feature is not being released yet.
+}
+
+pub async fn handle_command(matches: &clap::ArgMatches) -> fpm::Result<()> {
+    gc(
+        &fpm::Config::read(None, true, None).await?,
+        matches.get_one::<u64>("prune-older-than").copied(),
+    )
+    .await
+}
+
+async fn gc(config: &fpm::Config, prune_older_than_days: Option<u64>) -> fpm::Result<()> {
+    let prune = match prune_older_than_days {
+        Some(days) => Some(fpm::history_store::Prune {
+            before: fpm::timestamp_nanosecond()
+                .saturating_sub(days as u128 * 24 * 60 * 60 * 1_000_000_000),
+            referenced: tracked_snapshots(config).await?,
+        }),
+        None => None,
+    };
+
+    // The versions in `.remote-state/history/` are named by version number, and all of them are
+    // in `history.ftd`, read by `fpm show`, merges, workspaces and CRs. They are packed, never
+    // pruned.
+    for (history_dir, prune) in [
+        (config.history_dir(), prune.as_ref()),
+        (config.remote_history_dir(), None),
+    ] {
+        if !history_dir.exists() {
+            continue;
+        }
+        let stats = fpm::history_store::gc(&history_dir, prune).await?;
+        println!(
+            "{}: packed {} versions ({} duplicates), pruned {}, {} => {} bytes",
+            config.path_without_root(&history_dir)?,
+            stats.packed,
+            stats.deduplicated,
+            stats.pruned,
+            stats.bytes_before,
+            stats.bytes_after
+        );
+    }
+    Ok(())
+}
+
+/// Snapshots in `.history/`, relative to it, which are still read: the latest snapshot of every
+/// file, and the ones `.tracks` refer to for `fpm diff`, `fpm tracks` and `fpm mark-upto-date`
+async fn tracked_snapshots(config: &fpm::Config) -> fpm::Result<std::collections::HashSet<String>> {
+    let mut referenced: std::collections::HashSet<String> =
+        fpm::snapshot::get_latest_snapshots(&config.root)
+            .await?
+            .iter()
+            .map(|(filename, timestamp)| fpm::utils::snapshot_id(filename, timestamp))
+            .collect();
+
+    let track_dir = config.track_dir();
+    if !track_dir.exists() {
+        return Ok(referenced);
+    }
+    for entry in ignore::WalkBuilder::new(&track_dir).hidden(false).build() {
+        let path = camino::Utf8PathBuf::from_path_buf(entry?.into_path())
+            .map_err(|p| fpm::Error::GenericError(format!("non utf8 path: {:?}", p)))?;
+        let source = match path
+            .strip_prefix(&track_dir)?
+            .as_str()
+            .replace(std::path::MAIN_SEPARATOR, "/")
+            .strip_suffix(".track")
+        {
+            Some(source) => source.to_string(),
+            None => continue,
+        };
+        if !path.is_file() || source.starts_with("-/") {
+            // the tracks of CRs refer to versions in `.remote-state/history/`
+            continue;
+        }
+        for track in fpm::tracker::get_tracks(config.root.as_str(), &path)?.values() {
+            referenced.insert(fpm::utils::snapshot_id(&source, &track.self_timestamp));
+            for timestamp in [track.other_timestamp, track.last_merged_version]
+                .iter()
+                .flatten()
+            {
+                referenced.insert(fpm::utils::snapshot_id(&track.filename, timestamp));
+            }
+        }
+    }
+    Ok(referenced)
+}
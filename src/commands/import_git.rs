@@ -0,0 +1,337 @@
+pub const COMMAND: &str = "import-git";
+
+pub fn command() -> clap::Command {
+    clap::Command::new(COMMAND)
+        .about("Seed the history of this fpm package from the commits of a git repository")
+        .arg(clap::arg!(repo: [REPO] "The git repository to import from (default: the package root)"))
+        .arg(clap::arg!(--branch <BRANCH> "The git branch to import as main").default_value("HEAD"))
+        .hide(true) // hidden since the feature is not being released yet.
+}
+
+pub async fn handle_command(matches: &clap::ArgMatches) -> fpm::Result<()> {
+    use fpm::utils::ValueOf;
+
+    let config = fpm::Config::read(None, true, None).await?;
+    let repo = match matches.value_of_("repo") {
+        Some(repo) => camino::Utf8PathBuf::from(repo),
+        None => config.root.clone(),
+    };
+    import_git(&config, &repo, matches.value_of_("branch").unwrap()).await
+}
+
+/// Replays the first parent commits of `branch`, oldest first. Every added or modified file gets
+/// a snapshot in `.history`, and a version with the author and message of the commit in the
+/// history of the package. The files of the package itself are not changed.
+async fn import_git(
+    config: &fpm::Config,
+    repo: &camino::Utf8Path,
+    branch: &str,
+) -> fpm::Result<()> {
+    use fpm::commands::export_git::git;
+    use itertools::Itertools;
+
+    if config.latest_ftd().exists() || config.history_file().exists() {
+        return fpm::usage_error(
+            "The package already has history, import-git only seeds the history of a new package"
+                .to_string(),
+        );
+    }
+
+    let rev_list = String::from_utf8(git(
+        repo,
+        &[
+            "rev-list",
+            "--reverse",
+            "--first-parent",
+            "--parents",
+            branch,
+        ],
+        None,
+    )?)?;
+
+    let mut file_history: std::collections::BTreeMap<String, fpm::history::FileHistory> =
+        Default::default();
+    let mut snapshots: std::collections::BTreeMap<String, u128> = Default::default();
+    let mut timestamp = 0;
+    let mut commit_count = 0;
+
+    for line in rev_list.lines() {
+        let mut hashes = line.split_whitespace();
+        let commit = match hashes.next() {
+            Some(commit) => commit,
+            None => continue,
+        };
+        let parent = hashes.next();
+        let (author, seconds, message) = commit_info(repo, commit)?;
+        // commits made in the same second still get their own snapshots
+        timestamp = std::cmp::max(seconds * 1_000_000_000, timestamp + 1);
+
+        let diff_tree = match parent {
+            Some(parent) => git(
+                repo,
+                &[
+                    "diff-tree",
+                    "--no-commit-id",
+                    "-r",
+                    "-z",
+                    "--no-renames",
+                    "--name-status",
+                    parent,
+                    commit,
+                ],
+                None,
+            )?,
+            None => git(
+                repo,
+                &[
+                    "diff-tree",
+                    "--no-commit-id",
+                    "-r",
+                    "-z",
+                    "--no-renames",
+                    "--name-status",
+                    "--root",
+                    commit,
+                ],
+                None,
+            )?,
+        };
+        let diff_tree = String::from_utf8(diff_tree)?;
+
+        for (status, path) in diff_tree.split('\0').filter(|v| !v.is_empty()).tuples() {
+            if is_fpm_state(path) {
+                continue;
+            }
+            let version = file_history
+                .get(path)
+                .and_then(|v| v.file_edit.first())
+                .map(|v| v.version + 1)
+                .unwrap_or(1);
+            let operation = if status.starts_with('D') {
+                snapshots.remove(path);
+                fpm::history::FileOperation::Deleted
+            } else {
+                let object = format!("{}:{}", commit, path);
+                let content = git(repo, &["cat-file", "blob", object.as_str()], None)?;
+                fpm::utils::update(
+                    &fpm::utils::history_path(path, config.root.as_str(), &timestamp),
+                    content.as_slice(),
+                )
+                .await?;
+                fpm::utils::update(&config.history_path(path, version), content.as_slice()).await?;
+                snapshots.insert(path.to_string(), timestamp);
+                if version == 1 {
+                    fpm::history::FileOperation::Added
+                } else {
+                    fpm::history::FileOperation::Updated
+                }
+            };
+            file_history
+                .entry(path.to_string())
+                .or_insert_with(|| fpm::history::FileHistory {
+                    filename: path.to_string(),
+                    file_edit: vec![],
+                })
+                .file_edit
+                .insert(
+                    0,
+                    fpm::history::FileEdit {
+                        message: message.clone(),
+                        timestamp,
+                        version,
+                        author: author.clone(),
+                        src_cr: None,
+                        operation,
+                    },
+                );
+        }
+        commit_count += 1;
+    }
+
+    tokio::fs::create_dir_all(config.history_dir()).await?;
+    fpm::snapshot::create_latest_snapshots(
+        config,
+        snapshots
+            .into_iter()
+            .map(|(filename, timestamp)| fpm::Snapshot {
+                filename,
+                timestamp,
+            })
+            .collect_vec()
+            .as_slice(),
+    )
+    .await?;
+    fpm::utils::update(
+        &config.history_file(),
+        fpm::history::FileHistory::to_ftd(file_history.values().collect_vec().as_slice())
+            .as_bytes(),
+    )
+    .await?;
+
+    println!(
+        "Imported {} commits, {} files",
+        commit_count,
+        file_history.len()
+    );
+    Ok(())
+}
+
+/// (author, author time in seconds, message) of the commit, cleaned up for `history.ftd`
+fn commit_info(
+    repo: &camino::Utf8Path,
+    commit: &str,
+) -> fpm::Result<(Option<String>, u128, Option<String>)> {
+    let info = String::from_utf8(fpm::commands::export_git::git(
+        repo,
+        &["show", "-s", "--format=%an%x00%ae%x00%at%x00%B", commit],
+        None,
+    )?)?;
+    let mut parts = info.splitn(4, '\0');
+    let mut next = || parts.next().unwrap_or_default();
+    let (name, email, seconds, message) = (next(), next(), next(), next());
+
+    let author = fpm::history::clean_author(Some(format!("{} <{}>", name, email).as_str()))?;
+    let seconds = seconds.trim().parse::<u128>()?;
+    let message = fpm::history::clean_message(Some(history_message(message).as_str()))?;
+    Ok((author, seconds, message))
+}
+
+/// Lines starting with `--` are escaped, and long messages are cut, they are allowed in git but
+/// not in `history.ftd`
+fn history_message(message: &str) -> String {
+    use itertools::Itertools;
+
+    let mut message = message
+        .trim()
+        .lines()
+        .map(|l| {
+            if l.trim_start().starts_with("--") {
+                format!("\\{}", l.trim_start())
+            } else {
+                l.to_string()
+            }
+        })
+        .join("\n");
+    if message.len() > fpm::history::MAX_MESSAGE_LEN {
+        let mut end = fpm::history::MAX_MESSAGE_LEN;
+        while !message.is_char_boundary(end) {
+            end -= 1;
+        }
+        message.truncate(end);
+    }
+    message
+}
+
+/// Files fpm keeps its own state in, they are not part of the package history
+fn is_fpm_state(path: &str) -> bool {
+    [
+        ".history/",
+        ".remote-state/",
+        ".clone-state/",
+        ".packages/",
+        ".build/",
+    ]
+    .iter()
+    .any(|v| path.starts_with(v))
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn history_message() {
+        assert_eq!(
+            super::history_message("Add intro\n\n-- ftd.text: hello\n"),
+            "Add intro\n\n\\-- ftd.text: hello"
+        );
+        assert_eq!(super::history_message(&"a".repeat(5000)).len(), 4096);
+    }
+
+    #[test]
+    fn is_fpm_state() {
+        assert!(super::is_fpm_state(".history/index.1.ftd"));
+        assert!(!super::is_fpm_state("index.ftd"));
+    }
+
+    #[test]
+    fn import_git() {
+        fpm::test_utils::block_on(async {
+            let package = fpm::test_utils::TempPackage::new("import-git");
+            let root = &package.root;
+            let git = |args: &[&str]| {
+                let mut full_args = vec![
+                    "-c",
+                    "user.name=Amit",
+                    "-c",
+                    "user.email=amit@example.com",
+                    "-c",
+                    "commit.gpgsign=false",
+                ];
+                full_args.extend_from_slice(args);
+                fpm::commands::export_git::git(root, full_args.as_slice(), None).unwrap()
+            };
+            let write = |path: &str, content: &str| package.write(path, content);
+
+            git(&["init", "-q"]);
+            write("index.ftd", "-- ftd.text: hello\n");
+            write("about.ftd", "-- ftd.text: about\n");
+            git(&["add", "-A"]);
+            git(&["commit", "-q", "-m", "First"]);
+            write("index.ftd", "-- ftd.text: hello world\n");
+            std::fs::remove_file(root.join("about.ftd")).unwrap();
+            git(&["add", "-A"]);
+            git(&["commit", "-q", "-m", "Second"]);
+            write("blog.ftd", "-- ftd.text: blog\n");
+            git(&["add", "-A"]);
+            git(&["commit", "-q", "-m", "Third"]);
+
+            let config = package.config().await;
+            super::import_git(&config, root, "HEAD").await.unwrap();
+
+            let history = config.get_history().await.unwrap();
+            let edits = |filename: &str| {
+                history
+                    .iter()
+                    .find(|v| v.filename == filename)
+                    .unwrap()
+                    .file_edit
+                    .iter()
+                    .map(|v| {
+                        (
+                            v.version,
+                            v.message.clone().unwrap_or_default(),
+                            v.author.clone().unwrap_or_default(),
+                            v.is_deleted(),
+                        )
+                    })
+                    .collect::<Vec<_>>()
+            };
+            let edit = |version: i32, message: &str, is_deleted: bool| {
+                (
+                    version,
+                    message.to_string(),
+                    "Amit <amit@example.com>".to_string(),
+                    is_deleted,
+                )
+            };
+            assert_eq!(history.len(), 4);
+            assert_eq!(edits("FPM.ftd"), vec![edit(1, "First", false)]);
+            assert_eq!(
+                edits("index.ftd"),
+                vec![edit(2, "Second", false), edit(1, "First", false)]
+            );
+            assert_eq!(
+                edits("about.ftd"),
+                vec![edit(2, "Second", true), edit(1, "First", false)]
+            );
+            assert_eq!(edits("blog.ftd"), vec![edit(1, "Third", false)]);
+            assert_eq!(
+                std::fs::read_to_string(config.history_path("index.ftd", 1)).unwrap(),
+                "-- ftd.text: hello\n"
+            );
+            assert_eq!(
+                std::fs::read_to_string(config.history_path("index.ftd", 2)).unwrap(),
+                "-- ftd.text: hello world\n"
+            );
+        });
+    }
+}
@@ -0,0 +1,230 @@
+pub const COMMAND: &str = "log";
+
+pub fn command() -> clap::Command {
+    clap::Command::new(COMMAND)
+        .about("Show the history of edits to files in this fpm package, newest first")
+        .arg(clap::arg!(file: [FILE] "The file to see history of (leave empty to see history of entire package)"))
+        .arg(clap::arg!(--author <AUTHOR> "Only show edits by this author"))
+        .arg(clap::arg!(--cr <CR> "Only show edits made in, or merged from, this CR"))
+        .arg(clap::arg!(--since <DATE> "Only show edits made on or after this date (YYYY-MM-DD, UTC)"))
+        .arg(clap::arg!(--until <DATE> "Only show edits made on or before this date (YYYY-MM-DD, UTC)"))
+        .hide(true) // hidden since the feature is not being released yet.
+}
+
+pub async fn handle_command(matches: &clap::ArgMatches) -> fpm::Result<()> {
+    use fpm::utils::ValueOf;
+
+    let cr = match matches.value_of_("cr") {
+        Some(cr) => Some(cr.parse::<usize>()?),
+        None => None,
+    };
+    let since = match matches.value_of_("since") {
+        Some(date) => Some(parse_date(date)?),
+        None => None,
+    };
+    let until = match matches.value_of_("until") {
+        // inclusive, so everything before the start of the next day
+        Some(date) => Some(parse_date(date)? + NANOS_PER_DAY - 1),
+        None => None,
+    };
+
+    log(
+        &fpm::Config::read(None, true, None).await?,
+        matches.value_of_("file"),
+        &Filter {
+            author: matches.value_of_("author").map(ToString::to_string),
+            cr,
+            since,
+            until,
+        },
+    )
+    .await
+}
+
+const NANOS_PER_DAY: u128 = 24 * 60 * 60 * 1_000_000_000;
+
+#[derive(Debug, Default)]
+struct Filter {
+    author: Option<String>,
+    cr: Option<usize>,
+    since: Option<u128>,
+    until: Option<u128>,
+}
+
+impl Filter {
+    fn matches(&self, filename: &str, file_edit: &fpm::history::FileEdit) -> bool {
+        if let Some(ref author) = self.author {
+            if !file_edit
+                .author
+                .as_ref()
+                .map(|v| v.eq(author))
+                .unwrap_or(false)
+            {
+                return false;
+            }
+        }
+        if let Some(cr) = self.cr {
+            if file_edit.src_cr != Some(cr)
+                && !filename.starts_with(format!("{}/", fpm::cr::cr_path(cr)).as_str())
+            {
+                return false;
+            }
+        }
+        if matches!(self.since, Some(since) if file_edit.timestamp < since) {
+            return false;
+        }
+        if matches!(self.until, Some(until) if file_edit.timestamp > until) {
+            return false;
+        }
+        true
+    }
+}
+
+async fn log(config: &fpm::Config, file: Option<&str>, filter: &Filter) -> fpm::Result<()> {
+    let history = config.get_history().await?;
+    if let Some(file) = file {
+        if !history.iter().any(|v| v.filename.eq(file)) {
+            return fpm::usage_error(format!("`{}` has no history, it is not synced yet", file));
+        }
+    }
+
+    let mut edits: Vec<(&str, &fpm::history::FileEdit)> = history
+        .iter()
+        .filter(|v| file.map(|f| v.filename.eq(f)).unwrap_or(true))
+        .flat_map(|v| v.file_edit.iter().map(|e| (v.filename.as_str(), e)))
+        .filter(|(filename, e)| filter.matches(filename, e))
+        .collect();
+    // files synced together share the timestamp, keep them in file name order
+    edits.sort_by(|(f1, e1), (f2, e2)| e2.timestamp.cmp(&e1.timestamp).then(f1.cmp(f2)));
+
+    for (filename, file_edit) in edits {
+        print_file_edit(filename, file_edit);
+    }
+    Ok(())
+}
+
+fn print_file_edit(filename: &str, file_edit: &fpm::history::FileEdit) {
+    use colored::Colorize;
+
+    println!("{}", format!("{}@{}", filename, file_edit.version).yellow());
+    println!("Operation: {:?}", file_edit.operation);
+    if let Some(ref author) = file_edit.author {
+        println!("Author: {}", author);
+    }
+    println!("Date: {}", format_timestamp(file_edit.timestamp));
+    if let Some(cr) = file_edit.src_cr {
+        println!("CR: {}", cr);
+    }
+    if let Some(message) = file_edit.message.as_ref().filter(|v| !v.trim().is_empty()) {
+        println!();
+        for line in message.lines() {
+            println!("    {}", line);
+        }
+    }
+    println!();
+}
+
+/// `2022-10-19` => nanoseconds since unix epoch at the start of the day, in UTC
+fn parse_date(date: &str) -> fpm::Result<u128> {
+    let invalid = || fpm::Error::UsageError {
+        message: format!("invalid date `{}`, expected YYYY-MM-DD", date),
+    };
+    let mut parts = date.trim().splitn(3, '-');
+    let mut next = || -> fpm::Result<i64> {
+        parts
+            .next()
+            .and_then(|v| v.parse::<i64>().ok())
+            .ok_or_else(invalid)
+    };
+    let (year, month, day) = (next()?, next()?, next()?);
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) || year < 1970 {
+        return Err(invalid());
+    }
+    Ok(days_from_civil(year, month, day) as u128 * NANOS_PER_DAY)
+}
+
+/// nanoseconds since unix epoch => `2022-10-19 10:22:11 UTC`
+pub(crate) fn format_timestamp(nanos: u128) -> String {
+    let seconds = (nanos / 1_000_000_000) as i64;
+    let (year, month, day) = civil_from_days(seconds.div_euclid(86400));
+    let seconds = seconds.rem_euclid(86400);
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+        year,
+        month,
+        day,
+        seconds / 3600,
+        seconds % 3600 / 60,
+        seconds % 60
+    )
+}
+
+/// Days since 1970-01-01 of a date in the proleptic gregorian calendar, see
+/// http://howardhinnant.github.io/date_algorithms.html#days_from_civil
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+/// Inverse of `days_from_civil`
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn parse_date() {
+        assert_eq!(super::parse_date("1970-01-01").unwrap(), 0);
+        assert_eq!(
+            super::parse_date("2022-10-19").unwrap(),
+            1666137600 * 1_000_000_000
+        );
+        assert!(super::parse_date("2022-13-01").is_err());
+        assert!(super::parse_date("yesterday").is_err());
+    }
+
+    #[test]
+    fn format_timestamp() {
+        assert_eq!(
+            super::format_timestamp(1666174931 * 1_000_000_000 + 42),
+            "2022-10-19 10:22:11 UTC"
+        );
+        assert_eq!(super::format_timestamp(0), "1970-01-01 00:00:00 UTC");
+    }
+
+    #[test]
+    fn filter_cr() {
+        let file_edit = |src_cr| fpm::history::FileEdit {
+            message: None,
+            timestamp: 0,
+            version: 1,
+            author: None,
+            src_cr,
+            operation: fpm::history::FileOperation::Added,
+        };
+        let filter = super::Filter {
+            cr: Some(1),
+            ..Default::default()
+        };
+        assert!(filter.matches("-/1/index.ftd", &file_edit(None)));
+        assert!(filter.matches("index.ftd", &file_edit(Some(1))));
+        assert!(!filter.matches("-/12/index.ftd", &file_edit(None)));
+        assert!(!filter.matches("-/100/index.ftd", &file_edit(None)));
+        assert!(!filter.matches("index.ftd", &file_edit(Some(12))));
+    }
+}
@@ -0,0 +1,108 @@
+pub const COMMAND: &str = "login";
+
+pub fn command() -> clap::Command {
+    clap::Command::new(COMMAND)
+        .about("Save the API token of an fpm server, it is sent by sync and clone to that server")
+        .arg(clap::arg!(server: <SERVER> "The fpm server, e.g. http://127.0.0.1:8000"))
+        .arg(clap::arg!(--token <TOKEN> "The token from <SERVER>/-/login/token/, asked for if not given"))
+        .hide(true) // hidden since the feature is not being released yet.
+}
+
+pub async fn handle_command(matches: &clap::ArgMatches) -> fpm::Result<()> {
+    use fpm::utils::ValueOf;
+
+    let server = origin(matches.value_of_("server").unwrap())?;
+    let token = match matches.value_of_("token") {
+        Some(token) => token.to_string(),
+        None => {
+            println!(
+                "Open {}/-/login/token/ in the browser, and paste the token here:",
+                server
+            );
+            let mut token = String::new();
+            std::io::stdin().read_line(&mut token)?;
+            token
+        }
+    };
+    let token = token.trim();
+    if token.is_empty() {
+        return fpm::usage_error("The token is empty".to_string());
+    }
+
+    let mut credentials = read_credentials().await?;
+    credentials.insert(server.to_string(), token.to_string());
+    write_credentials(&credentials).await?;
+    println!("Logged in to {}", server);
+    Ok(())
+}
+
+/// Token saved by `fpm login` for the server of `url`
+pub(crate) async fn token_for(url: &str) -> fpm::Result<Option<String>> {
+    let server = match origin(url) {
+        Ok(server) => server,
+        Err(_) => return Ok(None),
+    };
+    Ok(read_credentials().await?.remove(server.as_str()))
+}
+
+/// `scheme://host:port` of the server, tokens are saved per origin so they are never sent to
+/// another server
+fn origin(server: &str) -> fpm::Result<String> {
+    let server = if server.contains("://") {
+        server.to_string()
+    } else {
+        format!("https://{}", server)
+    };
+    let url = url::Url::parse(server.as_str()).map_err(|e| fpm::Error::UsageError {
+        message: format!("invalid server `{}`: {}", server, e),
+    })?;
+    Ok(url.origin().ascii_serialization())
+}
+
+/// `~/.fpm/credentials.json`, server origin => token
+fn credentials_path() -> Option<camino::Utf8PathBuf> {
+    std::env::var("HOME")
+        .or_else(|_| std::env::var("USERPROFILE"))
+        .ok()
+        .map(|home| camino::Utf8PathBuf::from(home).join(".fpm/credentials.json"))
+}
+
+async fn read_credentials() -> fpm::Result<std::collections::BTreeMap<String, String>> {
+    match credentials_path() {
+        Some(path) if path.exists() => Ok(serde_json::from_slice(
+            tokio::fs::read(path).await?.as_slice(),
+        )?),
+        _ => Ok(Default::default()),
+    }
+}
+
+async fn write_credentials(
+    credentials: &std::collections::BTreeMap<String, String>,
+) -> fpm::Result<()> {
+    let path = credentials_path().ok_or_else(|| fpm::Error::UsageError {
+        message: "Can't find the home directory to save the token in".to_string(),
+    })?;
+    fpm::utils::update(&path, serde_json::to_string_pretty(credentials)?.as_bytes()).await?;
+    // only the user should be able to read the tokens
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        tokio::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600)).await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn origin() {
+        assert_eq!(
+            super::origin("http://127.0.0.1:8000/-/sync2/").unwrap(),
+            "http://127.0.0.1:8000"
+        );
+        assert_eq!(
+            super::origin("fpm.example.com").unwrap(),
+            "https://fpm.example.com"
+        );
+    }
+}
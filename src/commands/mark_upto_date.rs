@@ -104,7 +104,7 @@ async fn mark_upto_date_simple(
     Ok(())
 }
 
-async fn write(
+pub(crate) async fn write(
     file_path: &camino::Utf8PathBuf,
     tracks: &std::collections::BTreeMap<String, fpm::Track>,
 ) -> fpm::Result<()> {
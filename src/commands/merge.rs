@@ -3,6 +3,7 @@ pub async fn merge(
     src: Option<&str>,
     dest: &str,
     file: Option<&str>,
+    force: bool,
 ) -> fpm::Result<()> {
     let src = src.unwrap_or("main");
 
@@ -11,7 +12,7 @@ pub async fn merge(
         merge_main_into_cr(config, dest, file).await?;
     } else if dest.eq("main") {
         let src = src.parse::<usize>()?;
-        merge_cr_into_main(config, src, file).await?;
+        merge_cr_into_main(config, src, file, force).await?;
     } else {
         let src = src.parse::<usize>()?;
         let dest = dest.parse::<usize>()?;
@@ -21,13 +22,363 @@ pub async fn merge(
     Ok(())
 }
 
+/// Merges the changes of CR `src` into CR `dest`, for stacked change requests.
+///
+/// Files of `src` not edited in `dest` are copied along with their tracking info, files edited
+/// in both are merged with the main version `src` is tracking as the ancestor, and the
+/// `deleted.ftd` entries of `src` are added to `dest`. Conflicts are written to the working
+/// tree with conflict markers, the `dest` content is kept in `.fpm/conflicted/`, and they are
+/// resolved with `fpm mark-resolved` or `fpm abort-merge`. If everything merged cleanly, and
+/// the whole CR was merged, `src` is closed.
 async fn merge_cr_into_cr(
-    _config: &fpm::Config,
-    _src: usize,
-    _dest: usize,
-    _file: Option<&str>,
+    config: &fpm::Config,
+    src: usize,
+    dest: usize,
+    file: Option<&str>,
 ) -> fpm::Result<()> {
-    unimplemented!()
+    use itertools::Itertools;
+    use sha2::Digest;
+
+    if src == dest {
+        return fpm::usage_error(format!("Can't merge CR#{} into itself", src));
+    }
+    if !fpm::cr::is_open_cr_exists(config, dest).await? {
+        return fpm::usage_error(format!("CR#{} is closed", dest));
+    }
+
+    let src_cr_manifest = config.get_cr_manifest(src).await?;
+    let dest_cr_manifest = config.get_cr_manifest(dest).await?;
+    let src_deleted = get_cr_deleted(config, &src_cr_manifest, src).await?;
+    let mut dest_deleted = get_cr_deleted(config, &dest_cr_manifest, dest).await?;
+
+    let mut new_file_status: std::collections::BTreeMap<String, fpm::sync_utils::FileStatus> =
+        Default::default();
+    let mut conflicted_file_status = vec![];
+    // (workspace entry, `dest` content to keep in `.fpm/conflicted`, content for working tree)
+    let mut conflicts: Vec<(fpm::snapshot::Workspace, Option<Vec<u8>>, Option<Vec<u8>>)> = vec![];
+
+    for (filename, src_cr_file, src_file_edit) in cr_files(&src_cr_manifest, src, file) {
+        let src_content =
+            fpm::history_store::read(&config.history_path(src_cr_file, src_file_edit.version))
+                .await?;
+        let src_tracking_info =
+            get_cr_tracking_info(config, &src_cr_manifest, src, filename).await?;
+        let dest_cr_file = format!("{}/{}", fpm::cr::cr_path(dest), filename);
+
+        let dest_file_edit = match dest_cr_manifest.get(&dest_cr_file) {
+            Some(file_edit) if !file_edit.is_deleted() => file_edit,
+            _ => {
+                if let Some(dest_delete) = dest_deleted.get(filename) {
+                    // Edited in `src`, deleted in `dest`
+                    conflicted_file_status.push(fpm::sync_utils::FileStatus::Delete {
+                        path: dest_cr_file.to_string(),
+                        version: dest_delete.version,
+                        status: fpm::sync_utils::Status::CloneDeletedRemoteEdited(
+                            src_file_edit.version,
+                        ),
+                    });
+                    conflicts.push((
+                        fpm::snapshot::Workspace {
+                            filename: dest_cr_file,
+                            base: dest_delete.version as u128,
+                            conflicted: src_file_edit.version as u128,
+                            workspace: fpm::snapshot::WorkspaceType::CloneDeletedRemoteEdited,
+                        },
+                        None,
+                        Some(src_content),
+                    ));
+                    continue;
+                }
+
+                // Not edited in `dest`, take the `src` version along with what it tracks
+                if let Some(tracking_info) = src_tracking_info {
+                    let dest_track_file = config.path_without_root(
+                        &config.track_path(&config.root.join(dest_cr_file.as_str())),
+                    )?;
+                    new_file_status.insert(
+                        dest_track_file.to_string(),
+                        fpm::sync_utils::FileStatus::Add {
+                            path: dest_track_file,
+                            content: fpm::track::generate_tracking_info_content(&[tracking_info])
+                                .into_bytes(),
+                            status: fpm::sync_utils::Status::NoConflict,
+                        },
+                    );
+                }
+                new_file_status.insert(
+                    dest_cr_file.to_string(),
+                    fpm::sync_utils::FileStatus::Add {
+                        path: dest_cr_file,
+                        content: src_content,
+                        status: fpm::sync_utils::Status::NoConflict,
+                    },
+                );
+                continue;
+            }
+        };
+
+        let dest_content =
+            fpm::history_store::read(&config.history_path(&dest_cr_file, dest_file_edit.version))
+                .await?;
+        if sha2::Sha256::digest(&dest_content).eq(&sha2::Sha256::digest(&src_content)) {
+            continue;
+        }
+
+        let tracking_info = match src_tracking_info {
+            Some(tracking_info) => tracking_info,
+            None => {
+                // Added in both the CRs
+                conflicted_file_status.push(fpm::sync_utils::FileStatus::Add {
+                    path: dest_cr_file.to_string(),
+                    content: dest_content.clone(),
+                    status: fpm::sync_utils::Status::CloneAddedRemoteAdded(src_file_edit.version),
+                });
+                conflicts.push((
+                    fpm::snapshot::Workspace {
+                        filename: dest_cr_file,
+                        base: dest_file_edit.version as u128,
+                        conflicted: src_file_edit.version as u128,
+                        workspace: fpm::snapshot::WorkspaceType::Conflicted,
+                    },
+                    Some(dest_content),
+                    None,
+                ));
+                continue;
+            }
+        };
+
+        // If `dest` tracks a newer main version than `src`, the main changes in between show up
+        // as `dest` changes, which is what they are wrt `src`
+        let marker = match merge_tracked(
+            config,
+            filename,
+            &tracking_info,
+            &dest_content,
+            &src_content,
+        )
+        .await?
+        {
+            fpm::sync_utils::MergeResult::Merged(data) => {
+                new_file_status.insert(
+                    dest_cr_file.to_string(),
+                    fpm::sync_utils::FileStatus::Update {
+                        path: dest_cr_file,
+                        content: data,
+                        version: dest_file_edit.version,
+                        status: fpm::sync_utils::Status::NoConflict,
+                    },
+                );
+                continue;
+            }
+            fpm::sync_utils::MergeResult::Conflicted(data) => Some(data),
+            // binary file like images, can't merge
+            fpm::sync_utils::MergeResult::Binary => None,
+        };
+
+        conflicted_file_status.push(fpm::sync_utils::FileStatus::Update {
+            path: dest_cr_file.to_string(),
+            content: dest_content.clone(),
+            version: dest_file_edit.version,
+            status: fpm::sync_utils::Status::Conflict(src_file_edit.version),
+        });
+        conflicts.push((
+            fpm::snapshot::Workspace {
+                filename: dest_cr_file,
+                base: dest_file_edit.version as u128,
+                conflicted: src_file_edit.version as u128,
+                workspace: fpm::snapshot::WorkspaceType::Conflicted,
+            },
+            Some(dest_content),
+            marker,
+        ));
+    }
+
+    let mut dest_deleted_changed = false;
+    for (filename, src_delete) in src_deleted.into_iter() {
+        if matches!(file, Some(file) if file.ne(&filename)) || dest_deleted.contains_key(&filename)
+        {
+            continue;
+        }
+        let dest_cr_file = format!("{}/{}", fpm::cr::cr_path(dest), filename);
+        match dest_cr_manifest.get(&dest_cr_file) {
+            Some(dest_file_edit) if !dest_file_edit.is_deleted() => {
+                // Deleted in `src`, edited in `dest`
+                let dest_content = fpm::history_store::read(
+                    &config.history_path(&dest_cr_file, dest_file_edit.version),
+                )
+                .await?;
+                conflicted_file_status.push(fpm::sync_utils::FileStatus::Update {
+                    path: dest_cr_file.to_string(),
+                    content: dest_content.clone(),
+                    version: dest_file_edit.version,
+                    status: fpm::sync_utils::Status::CloneEditedRemoteDeleted(src_delete.version),
+                });
+                conflicts.push((
+                    fpm::snapshot::Workspace {
+                        filename: dest_cr_file,
+                        base: dest_file_edit.version as u128,
+                        conflicted: src_delete.version as u128,
+                        workspace: fpm::snapshot::WorkspaceType::CloneEditedRemoteDeleted,
+                    },
+                    Some(dest_content),
+                    None,
+                ));
+            }
+            _ => {
+                dest_deleted.insert(filename, src_delete);
+                dest_deleted_changed = true;
+            }
+        }
+    }
+
+    if dest_deleted_changed {
+        let dest_deleted_file = config.path_without_root(&config.cr_deleted_file_path(dest))?;
+        let content = fpm::cr::generate_deleted_files_content(
+            dest_deleted.into_values().collect_vec().as_slice(),
+        )
+        .into_bytes();
+        let status = match dest_cr_manifest.get(&dest_deleted_file) {
+            Some(file_edit) if !file_edit.is_deleted() => fpm::sync_utils::FileStatus::Update {
+                path: dest_deleted_file.to_string(),
+                content,
+                version: file_edit.version,
+                status: fpm::sync_utils::Status::NoConflict,
+            },
+            _ => fpm::sync_utils::FileStatus::Add {
+                path: dest_deleted_file.to_string(),
+                content,
+                status: fpm::sync_utils::Status::NoConflict,
+            },
+        };
+        new_file_status.insert(dest_deleted_file, status);
+    }
+
+    conflicted_file_status
+        .iter()
+        .chain(new_file_status.values())
+        .map(|v| fpm::commands::sync_status::print_status(v, false))
+        .collect_vec();
+
+    let mut sync_request_files = new_file_status
+        .into_values()
+        .filter_map(|v| v.sync_request(Some(src)))
+        .collect_vec();
+    if conflicts.is_empty() && file.is_none() {
+        let src_cr_manifest = src_cr_manifest.into_iter().collect();
+        let about_status = add_close_cr_status(config, src, &src_cr_manifest).await?;
+        if let Some(sync_req) = about_status.sync_request(None) {
+            sync_request_files.push(sync_req);
+        }
+    }
+    if !sync_request_files.is_empty() {
+        fpm::apis::sync2::do_sync(
+            config,
+            sync_request_files.as_slice(),
+            Some(format!("Merged CR {} into CR {}", src, dest).as_str()),
+            config.get_author().await?.as_deref(),
+        )
+        .await?;
+    }
+
+    if !conflicts.is_empty() {
+        let mut workspace = fpm::snapshot::get_workspace(config).await?;
+        for (entry, ours, working) in conflicts {
+            if let Some(ours) = ours {
+                fpm::utils::update1(&config.conflicted_dir(), entry.filename.as_str(), &ours)
+                    .await?;
+            }
+            if let Some(working) = working {
+                fpm::utils::update1(&config.root, entry.filename.as_str(), &working).await?;
+            }
+            workspace.insert(entry.filename.to_string(), entry);
+        }
+        fpm::snapshot::create_workspace(config, workspace.into_values().collect_vec().as_slice())
+            .await?;
+        println!(
+            "Merge of CR#{} into CR#{} has conflicts, resolve them and use `fpm mark-resolved <path>` or `fpm abort-merge <path>`",
+            src, dest
+        );
+    }
+
+    Ok(())
+}
+
+/// Entries of `-/<cr>/-/deleted.ftd` as of the latest synced version, keyed by file name
+async fn get_cr_deleted(
+    config: &fpm::Config,
+    cr_manifest: &std::collections::BTreeMap<String, fpm::history::FileEdit>,
+    cr: usize,
+) -> fpm::Result<std::collections::BTreeMap<String, fpm::cr::CRDeleted>> {
+    let deleted_file = config.path_without_root(&config.cr_deleted_file_path(cr))?;
+    let file_edit = match cr_manifest.get(&deleted_file) {
+        Some(file_edit) if !file_edit.is_deleted() => file_edit,
+        _ => return Ok(Default::default()),
+    };
+    let content =
+        fpm::history_store::read_to_string(&config.history_path(&deleted_file, file_edit.version))
+            .await?;
+    Ok(fpm::cr::resolve_cr_deleted(content.as_str(), cr)
+        .await?
+        .into_iter()
+        .map(|v| (v.filename.to_string(), v))
+        .collect())
+}
+
+/// The tracking info of `filename` in CR `cr`, i.e. the main version the CR copy started from.
+/// `None` if the file was added in the CR.
+async fn get_cr_tracking_info(
+    config: &fpm::Config,
+    cr_manifest: &std::collections::BTreeMap<String, fpm::history::FileEdit>,
+    cr: usize,
+    filename: &str,
+) -> fpm::Result<Option<fpm::track::TrackingInfo>> {
+    let track_file =
+        config.path_without_root(&config.track_path(&config.cr_path(cr).join(filename)))?;
+    let file_edit = match cr_manifest.get(&track_file) {
+        Some(file_edit) if !file_edit.is_deleted() => file_edit,
+        _ => return Ok(None),
+    };
+    Ok(
+        fpm::track::get_tracking_info_(&config.history_path(&track_file, file_edit.version))
+            .await?
+            .into_iter()
+            .find(|v| v.filename.eq(filename)),
+    )
+}
+
+/// The files of CR `cr` to merge, (file name, path in the CR, edit), only `file` if given. The
+/// about, meta and deleted files of the CR itself are in `-/<cr>/-/`, they are not merged.
+fn cr_files<'a>(
+    cr_manifest: &'a std::collections::BTreeMap<String, fpm::history::FileEdit>,
+    cr: usize,
+    file: Option<&'a str>,
+) -> impl Iterator<Item = (&'a str, &'a str, &'a fpm::history::FileEdit)> {
+    let prefix = format!("{}/", fpm::cr::cr_path(cr));
+    cr_manifest.iter().filter_map(move |(cr_file, file_edit)| {
+        let filename = cr_file.strip_prefix(prefix.as_str())?;
+        if filename.starts_with("-/")
+            || file_edit.is_deleted()
+            || matches!(file, Some(file) if file.ne(filename))
+        {
+            return None;
+        }
+        Some((filename, cr_file.as_str(), file_edit))
+    })
+}
+
+/// Three way merge of `ours` and `theirs`, two copies of `filename` edited since
+/// `tracking_info`, the main version a CR copy started from, which is the ancestor
+async fn merge_tracked(
+    config: &fpm::Config,
+    filename: &str,
+    tracking_info: &fpm::track::TrackingInfo,
+    ours: &[u8],
+    theirs: &[u8],
+) -> fpm::Result<fpm::sync_utils::MergeResult> {
+    let ancestor =
+        fpm::sync_utils::read_ancestor(&config.history_path(filename, tracking_info.version))
+            .await?;
+    Ok(fpm::sync_utils::merge(ancestor.as_deref(), ours, theirs))
 }
 
 async fn merge_main_into_cr(
@@ -73,8 +424,8 @@ async fn merge_main_into_cr(
             break;
         }
         if cr_file_path.eq(&deleted_file_str) {
-            let cr_deleted_files = tokio::fs::read_to_string(
-                config.history_path(cr_file_path.as_str(), cr_file_edit.version),
+            let cr_deleted_files = fpm::history_store::read_to_string(
+                &config.history_path(cr_file_path.as_str(), cr_file_edit.version),
             )
             .await?;
             let mut cr_deleted_list = fpm::cr::resolve_cr_deleted(cr_deleted_files.as_str(), dest)
@@ -149,9 +500,10 @@ async fn merge_main_into_cr(
             continue;
         };
 
-        let ours_content_bytes =
-            tokio::fs::read(config.history_path(cr_file_path.as_str(), cr_file_edit.version))
-                .await?;
+        let ours_content_bytes = fpm::history_store::read(
+            &config.history_path(cr_file_path.as_str(), cr_file_edit.version),
+        )
+        .await?;
 
         // get corresponding track file
         let track_file_path_str =
@@ -163,9 +515,10 @@ async fn merge_main_into_cr(
                 if file_edit.is_deleted() {
                     continue;
                 }
-                let theirs_content_bytes =
-                    tokio::fs::read(config.history_path(filename.as_str(), file_edit.version))
-                        .await?;
+                let theirs_content_bytes = fpm::history_store::read(
+                    &config.history_path(filename.as_str(), file_edit.version),
+                )
+                .await?;
                 if sha2::Sha256::digest(&ours_content_bytes)
                     .eq(&sha2::Sha256::digest(theirs_content_bytes))
                 {
@@ -203,8 +556,10 @@ async fn merge_main_into_cr(
             if file_edit.is_deleted() {
                 continue;
             }
-            let theirs_content_bytes =
-                tokio::fs::read(config.history_path(filename.as_str(), file_edit.version)).await?;
+            let theirs_content_bytes = fpm::history_store::read(
+                &config.history_path(filename.as_str(), file_edit.version),
+            )
+            .await?;
             if sha2::Sha256::digest(&ours_content_bytes)
                 .eq(&sha2::Sha256::digest(theirs_content_bytes))
             {
@@ -244,38 +599,25 @@ async fn merge_main_into_cr(
         }
 
         // try to merge
-        let ancestor_content = if let Ok(content) =
-            tokio::fs::read_to_string(config.history_path(filename.as_str(), track_info.version))
-                .await
-        {
-            content
-        } else {
-            // binary file like images, can't resolve conflict
-            conflicted_file_status.push(fpm::sync_utils::FileStatus::Update {
-                path: filename.to_string(),
-                content: ours_content_bytes.clone(),
-                version: track_info.version,
-                status: fpm::sync_utils::Status::Conflict(file_edit.version),
-            });
-            continue;
-        };
-
+        let ancestor_content = fpm::sync_utils::read_ancestor(
+            &config.history_path(filename.as_str(), track_info.version),
+        )
+        .await?;
         let theirs_content =
-            tokio::fs::read_to_string(config.history_path(filename.as_str(), file_edit.version))
+            fpm::history_store::read(&config.history_path(filename.as_str(), file_edit.version))
                 .await?;
 
-        let ours_content = String::from_utf8(ours_content_bytes.clone())?;
-
-        match diffy::MergeOptions::new()
-            .set_conflict_style(diffy::ConflictStyle::Merge)
-            .merge(&ancestor_content, &ours_content, &theirs_content)
-        {
-            Ok(data) => {
+        match fpm::sync_utils::merge(
+            ancestor_content.as_deref(),
+            &ours_content_bytes,
+            &theirs_content,
+        ) {
+            fpm::sync_utils::MergeResult::Merged(data) => {
                 new_file_status.insert(
                     cr_file_path.to_string(),
                     fpm::sync_utils::FileStatus::Update {
                         path: cr_file_path.to_string(),
-                        content: data.into_bytes(),
+                        content: data,
                         version: cr_file_edit.version,
                         status: fpm::sync_utils::Status::NoConflict,
                     },
@@ -294,8 +636,19 @@ async fn merge_main_into_cr(
                     },
                 );
             }
-            Err(_) => {
-                // Can't resolve conflict
+            fpm::sync_utils::MergeResult::Conflicted(data) => {
+                // Overlapping hunks are marked in the CR copy, for `fpm resolve-conflict`
+                fpm::utils::update(&config.root.join(cr_file_path), &data).await?;
+                conflicted_file_status.push(fpm::sync_utils::FileStatus::Update {
+                    path: filename.to_string(),
+                    content: ours_content_bytes.clone(),
+                    version: track_info.version,
+                    status: fpm::sync_utils::Status::Conflict(file_edit.version),
+                });
+                continue;
+            }
+            fpm::sync_utils::MergeResult::Binary => {
+                // binary file like images, can't resolve conflict
                 conflicted_file_status.push(fpm::sync_utils::FileStatus::Update {
                     path: filename.to_string(),
                     content: ours_content_bytes.clone(),
@@ -316,20 +669,52 @@ async fn merge_main_into_cr(
             .into_values()
             .filter_map(|v| v.sync_request(None))
             .collect_vec();
-        fpm::apis::sync2::do_sync(config, changed_files.as_slice()).await?;
+        fpm::apis::sync2::do_sync(
+            config,
+            changed_files.as_slice(),
+            Some(format!("Merged main into CR {}", dest).as_str()),
+            config.get_author().await?.as_deref(),
+        )
+        .await?;
     }
     Ok(())
 }
 
+/// Merges CR `src` into main, only once it is approved by someone other than its author, unless
+/// `force`d.
 async fn merge_cr_into_main(
     config: &fpm::Config,
     src: usize,
     file: Option<&str>,
+    force: bool,
 ) -> fpm::Result<()> {
     use itertools::Itertools;
     use sha2::Digest;
 
     //TODO: check if cr is closed
+    let cr_author = fpm::cr::get_cr_author(config, src).await?;
+    match fpm::cr::get_cr_reviews(config, src)
+        .await?
+        .merge_status(cr_author.as_deref())
+    {
+        Some(fpm::cr::ReviewStatus::Approved) => {}
+        _ if force => {
+            fpm::warning!("CR#{} is merged without an approval", src);
+        }
+        Some(fpm::cr::ReviewStatus::ChangesRequested) => {
+            return fpm::usage_error(format!(
+                "Changes are requested in CR#{}, Help: Use `fpm cr show {}`, or `--force`",
+                src, src
+            ));
+        }
+        None => {
+            return fpm::usage_error(format!(
+                "CR#{} is not approved, Help: Use `fpm cr approve {}` as a reviewer, or `--force`",
+                src, src
+            ));
+        }
+    }
+
     let remote_manifest: std::collections::BTreeMap<String, fpm::history::FileEdit> = config
         .get_remote_manifest(true)
         .await?
@@ -337,7 +722,7 @@ async fn merge_cr_into_main(
         .filter(|(k, _)| !(k.starts_with("-/") || k.starts_with(".tracks/")))
         .collect();
     let cr_manifest = config.get_cr_manifest(src).await?;
-    let (cr_track_manifest, cr_file_manifest) = cr_manifest.into_iter().fold(
+    let (cr_track_manifest, cr_file_manifest) = cr_manifest.clone().into_iter().fold(
         (
             std::collections::HashMap::new(),
             std::collections::HashMap::new(),
@@ -412,217 +797,125 @@ async fn merge_cr_into_main(
     let mut new_file_status: std::collections::BTreeMap<String, fpm::sync_utils::FileStatus> =
         Default::default();
     let mut conflicted_file_status = vec![];
-    let deleted_files_path = config.cr_deleted_file_path(src);
-    let deleted_files = config.path_without_root(&deleted_files_path)?;
-    let mut file_processed = false;
-    for (cr_file_name, cr_file_edit) in cr_file_manifest.iter() {
-        if file_processed {
-            break;
+    for (filename, cr_delete) in get_cr_deleted(config, &cr_manifest, src).await? {
+        if matches!(file, Some(file) if file.ne(&filename)) {
+            continue;
         }
-        if cr_file_name.eq(&deleted_files) {
-            // status for deleted files
-            let cr_deleted_files = tokio::fs::read_to_string(
-                config.history_path(cr_file_name.as_str(), cr_file_edit.version),
-            )
-            .await?;
-            let cr_deleted_list =
-                fpm::cr::resolve_cr_deleted(cr_deleted_files.as_str(), src).await?;
-
-            for cr_delete in cr_deleted_list {
-                if file_processed {
-                    break;
-                }
-                if let Some(file) = file {
-                    if cr_delete.filename.ne(file) {
-                        continue;
-                    } else {
-                        file_processed = true;
-                    }
-                }
-                let file_edit =
-                    if let Some(file_edit) = remote_manifest.get(cr_delete.filename.as_str()) {
-                        file_edit
-                    } else {
-                        return fpm::usage_error(format!(
-                            "Can't find history of `{}` which is marked to be deleted in CR#{}",
-                            cr_delete.filename, src
-                        ));
-                    };
-                if file_edit.is_deleted() {
-                    continue;
-                }
-                if !file_edit.version.eq(&cr_delete.version) {
-                    conflicted_file_status.push(fpm::sync_utils::FileStatus::Delete {
-                        path: cr_delete.filename.to_string(),
-                        version: cr_delete.version,
-                        status: fpm::sync_utils::Status::CloneDeletedRemoteEdited(
-                            file_edit.version,
-                        ),
-                    });
-                    continue;
-                }
-                new_file_status
-                    .insert(cr_delete.filename.to_string(), cr_delete.into_file_status());
+        let file_edit = match remote_manifest.get(filename.as_str()) {
+            Some(file_edit) => file_edit,
+            None => {
+                return fpm::usage_error(format!(
+                    "Can't find history of `{}` which is marked to be deleted in CR#{}",
+                    filename, src
+                ))
             }
+        };
+        if file_edit.is_deleted() {
             continue;
         }
-
-        let cr_file_path = config.history_path(cr_file_name.as_str(), cr_file_edit.version);
-        let filename = fpm::cr::cr_path_to_file_name(src, cr_file_path.as_str())?;
-        if let Some(file) = file {
-            if filename.ne(file) {
-                continue;
-            } else {
-                file_processed = true;
-            }
+        if !file_edit.version.eq(&cr_delete.version) {
+            conflicted_file_status.push(fpm::sync_utils::FileStatus::Delete {
+                path: filename,
+                version: cr_delete.version,
+                status: fpm::sync_utils::Status::CloneDeletedRemoteEdited(file_edit.version),
+            });
+            continue;
         }
+        new_file_status.insert(filename, cr_delete.into_file_status());
+    }
 
-        let cr_file_content = tokio::fs::read(&cr_file_path).await?;
-        let file_edit = if let Some(file_edit) = remote_manifest.get(filename.as_str()) {
-            file_edit
-        } else {
-            // Added file in CR
-            new_file_status.insert(
-                filename.to_string(),
-                fpm::sync_utils::FileStatus::Add {
-                    path: cr_file_name.to_string(),
-                    content: cr_file_content.clone(),
-                    status: fpm::sync_utils::Status::NoConflict,
-                },
-            );
-            continue;
+    for (filename, cr_file_name, cr_file_edit) in cr_files(&cr_manifest, src, file) {
+        let cr_content =
+            fpm::history_store::read(&config.history_path(cr_file_name, cr_file_edit.version))
+                .await?;
+        let added = fpm::sync_utils::FileStatus::Add {
+            path: filename.to_string(),
+            content: cr_content.clone(),
+            status: fpm::sync_utils::Status::NoConflict,
         };
-
-        let track_file_path_str =
-            config.path_without_root(&config.track_dir().join(cr_file_name))?;
-        let track_file_edit = match cr_track_manifest.get(&track_file_path_str) {
-            Some(file_edit) if !file_edit.is_deleted() => file_edit,
-            _ => {
+        let file_edit = match remote_manifest.get(filename) {
+            Some(file_edit) => file_edit,
+            None => {
                 // Added file in CR
-                new_file_status.insert(
-                    filename.to_string(),
-                    fpm::sync_utils::FileStatus::Add {
-                        path: cr_file_name.to_string(),
-                        content: cr_file_content.clone(),
-                        status: fpm::sync_utils::Status::NoConflict,
-                    },
-                );
+                new_file_status.insert(filename.to_string(), added);
                 continue;
             }
         };
 
-        let ours_content_bytes =
-            tokio::fs::read(config.history_path(cr_file_path.as_str(), cr_file_edit.version))
-                .await?;
-
-        let track_file_path =
-            config.history_path(track_file_path_str.as_str(), track_file_edit.version);
-
-        let mut tracking_infos = fpm::track::get_tracking_info_(&track_file_path)
-            .await?
-            .into_iter()
-            .map(|v| (v.filename.to_string(), v))
-            .collect::<std::collections::HashMap<String, fpm::track::TrackingInfo>>();
-        let track_info = if let Some(track_info) = tracking_infos.get_mut(&filename) {
-            track_info
-        } else {
-            // Added file in CR
-            if file_edit.is_deleted() {
-                new_file_status.insert(
-                    filename.to_string(),
-                    fpm::sync_utils::FileStatus::Add {
-                        path: cr_file_name.to_string(),
-                        content: cr_file_content.clone(),
-                        status: fpm::sync_utils::Status::NoConflict,
-                    },
-                );
-            }
-            let theirs_content_bytes =
-                tokio::fs::read(config.history_path(filename.as_str(), file_edit.version)).await?;
-            if !sha2::Sha256::digest(&ours_content_bytes)
-                .eq(&sha2::Sha256::digest(theirs_content_bytes))
-            {
-                conflicted_file_status.push(fpm::sync_utils::FileStatus::Add {
-                    path: filename.to_string(),
-                    content: ours_content_bytes.clone(),
-                    status: fpm::sync_utils::Status::CloneAddedRemoteAdded(file_edit.version),
-                });
+        let tracking_info = match get_cr_tracking_info(config, &cr_manifest, src, filename).await? {
+            Some(tracking_info) => tracking_info,
+            None => {
+                // Added file in CR, main has it too unless it's deleted there
+                if file_edit.is_deleted() {
+                    new_file_status.insert(filename.to_string(), added);
+                    continue;
+                }
+                let main_content =
+                    fpm::history_store::read(&config.history_path(filename, file_edit.version))
+                        .await?;
+                if !sha2::Sha256::digest(&cr_content).eq(&sha2::Sha256::digest(main_content)) {
+                    conflicted_file_status.push(fpm::sync_utils::FileStatus::Add {
+                        path: filename.to_string(),
+                        content: cr_content,
+                        status: fpm::sync_utils::Status::CloneAddedRemoteAdded(file_edit.version),
+                    });
+                }
+                continue;
             }
-            continue;
         };
 
         if file_edit.is_deleted() {
-            if !track_info.version.eq(&file_edit.version) {
+            if !tracking_info.version.eq(&file_edit.version) {
                 conflicted_file_status.push(fpm::sync_utils::FileStatus::Update {
                     path: filename.to_string(),
-                    content: ours_content_bytes.clone(),
-                    version: track_info.version,
+                    content: cr_content,
+                    version: tracking_info.version,
                     status: fpm::sync_utils::Status::CloneEditedRemoteDeleted(file_edit.version),
                 });
             }
             continue;
         }
 
-        if track_info.version.eq(&file_edit.version) {
+        if tracking_info.version.eq(&file_edit.version) {
             // Edited on cr wrt remote's latest version
             new_file_status.insert(
                 filename.to_string(),
                 fpm::sync_utils::FileStatus::Update {
-                    path: filename,
-                    content: cr_file_content,
-                    version: track_info.version,
+                    path: filename.to_string(),
+                    content: cr_content,
+                    version: file_edit.version,
                     status: fpm::sync_utils::Status::NoConflict,
                 },
             );
             continue;
         }
 
-        let ancestor_content = if let Ok(content) =
-            tokio::fs::read_to_string(config.history_path(filename.as_str(), track_info.version))
-                .await
-        {
-            content
-        } else {
-            // binary file like images, can't resolve conflict
-            conflicted_file_status.push(fpm::sync_utils::FileStatus::Update {
-                path: filename.to_string(),
-                content: ours_content_bytes.clone(),
-                version: track_info.version,
-                status: fpm::sync_utils::Status::Conflict(file_edit.version),
-            });
-            continue;
-        };
-
-        let theirs_content =
-            tokio::fs::read_to_string(config.history_path(filename.as_str(), file_edit.version))
-                .await?;
-
-        let ours_content = String::from_utf8(ours_content_bytes.clone())?;
-
-        match diffy::MergeOptions::new()
-            .set_conflict_style(diffy::ConflictStyle::Merge)
-            .merge(&ancestor_content, &ours_content, &theirs_content)
-        {
-            Ok(data) => {
+        let main_content =
+            fpm::history_store::read(&config.history_path(filename, file_edit.version)).await?;
+        match merge_tracked(config, filename, &tracking_info, &cr_content, &main_content).await? {
+            fpm::sync_utils::MergeResult::Merged(data) => {
                 new_file_status.insert(
                     filename.to_string(),
                     fpm::sync_utils::FileStatus::Update {
                         path: filename.to_string(),
-                        content: data.into_bytes(),
-                        version: cr_file_edit.version,
+                        content: data,
+                        version: file_edit.version,
                         status: fpm::sync_utils::Status::NoConflict,
                     },
                 );
             }
-            Err(_) => {
-                // Can't resolve conflict
+            merge_result => {
+                // Overlapping hunks are marked in the main copy, for `fpm resolve-conflict`,
+                // binary files like images can't be merged
+                if let fpm::sync_utils::MergeResult::Conflicted(data) = merge_result {
+                    fpm::utils::update(&config.root.join(filename), &data).await?;
+                }
                 conflicted_file_status.push(fpm::sync_utils::FileStatus::Update {
                     path: filename.to_string(),
-                    content: ours_content_bytes.clone(),
-                    version: track_info.version,
+                    content: cr_content,
+                    version: tracking_info.version,
                     status: fpm::sync_utils::Status::Conflict(file_edit.version),
                 });
-                continue;
             }
         }
     }
@@ -644,7 +937,13 @@ async fn merge_cr_into_main(
                 sync_request_files.push(sync_req);
             }
         }
-        fpm::apis::sync2::do_sync(config, sync_request_files.as_slice()).await?;
+        fpm::apis::sync2::do_sync(
+            config,
+            sync_request_files.as_slice(),
+            Some(format!("Merged CR {} into main", src).as_str()),
+            config.get_author().await?.as_deref(),
+        )
+        .await?;
     }
 
     Ok(())
@@ -669,7 +968,7 @@ async fn add_close_cr_status(
         });
     }
     let cr_about_path = config.history_path(cr_about_path_str.as_str(), cr_about_file_edit.version);
-    let cr_meta_content = tokio::fs::read_to_string(cr_about_path).await?;
+    let cr_meta_content = fpm::history_store::read_to_string(&cr_about_path).await?;
     let mut cr_about = fpm::cr::resolve_cr_meta(cr_meta_content.as_str(), cr).await?;
     cr_about.open = false;
     let cr_close_content = fpm::cr::generate_cr_meta_content(&cr_about);
@@ -804,3 +1103,77 @@ async fn add_close_cr_status(
     fpm::commands::sync2::sync(config, changed_files).await?;
     Ok(())
 }*/
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn merge_cr_into_cr_edited_deleted() {
+        fpm::test_utils::block_on(async {
+            let package = fpm::test_utils::TempPackage::new("merge");
+            package.write(".remote-state/history.ftd", "-- import: fpm\n");
+            let config = package.config().await;
+            let add = |path: &str, content: String| fpm::apis::sync2::SyncRequestFile::Add {
+                path: path.to_string(),
+                content: content.into_bytes(),
+                src_cr: None,
+            };
+            let meta = |cr_number| {
+                fpm::cr::generate_cr_meta_content(&fpm::cr::CRMeta {
+                    title: format!("CR {}", cr_number),
+                    cr_number,
+                    open: true,
+                })
+            };
+
+            fpm::apis::sync2::do_sync(
+                &config,
+                &[add("index.ftd", "-- ftd.text: hello\n".to_string())],
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+            let version = config.get_remote_manifest(true).await.unwrap()["index.ftd"].version;
+            // CR 1 deletes `index.ftd`, CR 2 edits it
+            fpm::apis::sync2::do_sync(
+                &config,
+                &[
+                    add("-/1/-/meta.ftd", meta(1)),
+                    add(
+                        "-/1/-/deleted.ftd",
+                        fpm::cr::generate_deleted_files_content(&[fpm::cr::CRDeleted::new(
+                            "index.ftd",
+                            version,
+                        )]),
+                    ),
+                    add("-/2/-/meta.ftd", meta(2)),
+                    add("-/2/index.ftd", "-- ftd.text: hello world\n".to_string()),
+                    add(
+                        ".tracks/-/2/index.ftd.track",
+                        fpm::track::generate_tracking_info_content(&[
+                            fpm::track::TrackingInfo::new("index.ftd", version, None),
+                        ]),
+                    ),
+                ],
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+
+            super::merge_cr_into_cr(&config, 1, 2, None).await.unwrap();
+            let workspace = fpm::snapshot::get_workspace(&config).await.unwrap();
+            assert_eq!(
+                workspace["-/2/index.ftd"].workspace,
+                fpm::snapshot::WorkspaceType::CloneEditedRemoteDeleted
+            );
+            assert_eq!(
+                std::fs::read_to_string(config.conflicted_dir().join("-/2/index.ftd")).unwrap(),
+                "-- ftd.text: hello world\n"
+            );
+            // nothing is merged, CR 1 is left open
+            assert!(fpm::cr::is_open_cr_exists(&config, 1).await.unwrap());
+            assert!(!config.root.join("-/2/-/deleted.ftd").exists());
+        });
+    }
+}
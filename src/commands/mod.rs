@@ -1,12 +1,19 @@
 pub mod abort_merge;
 pub mod add;
 pub mod build;
+pub mod check_sitemap;
 pub mod clone;
 pub mod close_cr;
+pub mod cr;
 pub mod create_cr;
 pub mod create_package;
 pub mod diff;
 pub mod edit;
+pub mod export_git;
+pub mod gc;
+pub mod import_git;
+pub mod log;
+pub mod login;
 pub mod mark_resolved;
 pub mod mark_upto_date;
 pub mod merge;
@@ -14,11 +21,16 @@ pub mod resolve_conflict;
 pub mod revert;
 pub mod rm;
 pub mod serve;
+pub mod show;
+pub mod sitemap;
 pub mod start_tracking;
 pub mod status;
 pub mod stop_tracking;
 pub mod sync;
 pub mod sync2;
 pub mod sync_status;
+pub mod tracks;
+pub mod translate;
+pub mod translation;
 pub mod translation_status;
 pub mod update;
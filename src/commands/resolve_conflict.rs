@@ -96,6 +96,12 @@ pub async fn resolve_conflict(
         let edited = edit::edit(content).map_err(|e| fpm::Error::UsageError {
             message: format!("{}, Help: Use `fpm resolve-conflict --print {}`", e, path,),
         })?;
+        if fpm::sync_utils::has_conflict_markers(edited.as_str()) {
+            return fpm::usage_error(format!(
+                "`{}` still has conflict markers, resolve all the conflicting hunks",
+                path
+            ));
+        }
         fpm::utils::update(&config.root.join(path), edited.as_bytes()).await?;
     }
 
@@ -163,6 +169,26 @@ struct ConflictData {
     marker: Option<String>,
 }
 
+/// Sync already wrote the merge with conflict markers to the working file. Both sides are
+/// recovered from the markers: `--use-ours` and `--use-theirs` pick a side only for the
+/// conflicting hunks, the hunks merged cleanly are kept from both, unlike a file without
+/// markers where they take the whole file of that side.
+fn from_markers(content: &[u8]) -> Option<ConflictData> {
+    let marked = std::str::from_utf8(content).ok()?;
+    if !fpm::sync_utils::has_conflict_markers(marked) {
+        return None;
+    }
+    Some(ConflictData {
+        ours: Content::Content(
+            fpm::sync_utils::resolve_conflict_markers(marked, true).into_bytes(),
+        ),
+        theirs: Content::Content(
+            fpm::sync_utils::resolve_conflict_markers(marked, false).into_bytes(),
+        ),
+        marker: Some(marked.to_string()),
+    })
+}
+
 async fn get_conflict_data(
     config: &fpm::Config,
     file_status: &fpm::sync_utils::FileStatus,
@@ -179,7 +205,7 @@ async fn get_conflict_data(
                 return fpm::usage_error(format!("`{}` is not in conflict state", path));
             };
             let history_path = config.history_path(path, remote_version);
-            let history_content = tokio::fs::read(history_path).await?;
+            let history_content = fpm::history_store::read(&history_path).await?;
             /* if let Ok(theirs_string) = String::from_utf8(history_content.to_vec()) {
                 let ours_string = String::from_utf8(content.to_vec())?;
                 let patch = diffy::create_patch(ours_string.as_str(), theirs_string.as_str());
@@ -227,35 +253,30 @@ async fn get_conflict_data(
                     status
                 ));
             }
+            if let Some(conflict_data) = from_markers(content) {
+                return Ok(conflict_data);
+            }
             let theirs_path = config.history_path(path, remote_version);
-            let theirs_content = tokio::fs::read(theirs_path).await?;
-            if let Ok(theirs_string) = String::from_utf8(theirs_content.to_vec()) {
-                let ours_string = String::from_utf8(content.to_vec())?;
-                let ancestor_path = config.history_path(path, *version);
-                let ancestor_content = tokio::fs::read(ancestor_path).await?;
-                let ancestor_string = String::from_utf8(ancestor_content)?;
-                match diffy::MergeOptions::new()
-                    .set_conflict_style(diffy::ConflictStyle::Merge)
-                    .merge(&ancestor_string, &ours_string, &theirs_string)
+            let theirs_content = fpm::history_store::read(&theirs_path).await?;
+            let ancestor_path = config.history_path(path, *version);
+            let ancestor_content = fpm::sync_utils::read_ancestor(&ancestor_path).await?;
+            let marker =
+                match fpm::sync_utils::merge(ancestor_content.as_deref(), content, &theirs_content)
                 {
-                    Ok(data) => {
+                    fpm::sync_utils::MergeResult::Merged(data) => {
                         // Not possible to reach here
                         tokio::fs::write(path, &data).await?;
                         return fpm::usage_error(format!("`{}` already resolved", path));
                     }
-                    Err(data) => {
-                        return Ok(ConflictData {
-                            ours: Content::Content(content.to_vec()),
-                            theirs: Content::Content(theirs_content),
-                            marker: Some(data),
-                        });
+                    fpm::sync_utils::MergeResult::Conflicted(data) => {
+                        Some(String::from_utf8(data)?)
                     }
-                }
-            }
+                    fpm::sync_utils::MergeResult::Binary => None,
+                };
             Ok(ConflictData {
                 ours: Content::Content(content.to_vec()),
                 theirs: Content::Content(theirs_content),
-                marker: None,
+                marker,
             })
         }
         fpm::sync_utils::FileStatus::Delete { path, status, .. } => {
@@ -269,7 +290,7 @@ async fn get_conflict_data(
                 ));
             };
             let theirs_path = config.history_path(path, *version);
-            let theirs_content = tokio::fs::read(theirs_path).await?;
+            let theirs_content = fpm::history_store::read(&theirs_path).await?;
             Ok(ConflictData {
                 theirs: Content::Content(theirs_content),
                 ours: Content::Deleted,
@@ -282,3 +303,32 @@ async fn get_conflict_data(
         )),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn from_markers() {
+        let marked = indoc::indoc! {"
+            A
+            b
+            <<<<<<< ours
+            ours
+            =======
+            theirs
+            >>>>>>> theirs
+            E
+        "};
+        let conflict_data = super::from_markers(marked.as_bytes()).unwrap();
+        // `A` and `E` were merged cleanly, only the conflicting hunk differs between the sides
+        assert_eq!(
+            conflict_data.ours.get_content(),
+            Some("A\nb\nours\nE\n".as_bytes())
+        );
+        assert_eq!(
+            conflict_data.theirs.get_content(),
+            Some("A\nb\ntheirs\nE\n".as_bytes())
+        );
+        assert_eq!(conflict_data.marker.as_deref(), Some(marked));
+        assert!(super::from_markers("A\nb\n".as_bytes()).is_none());
+    }
+}
@@ -111,6 +111,18 @@ async fn serve_cr_file(
     }
 }
 
+/// Redirects declared in `fpm.redirects` of the package
+fn redirect(config: &fpm::Config, path: &str) -> Option<fpm::http::Response> {
+    let (to, status) = fpm::package::redirects::find(&config.package.redirects, path)?;
+    let mut resp =
+        actix_web::HttpResponse::new(actix_web::http::StatusCode::from_u16(status).ok()?);
+    resp.headers_mut().insert(
+        actix_web::http::header::LOCATION,
+        actix_web::http::header::HeaderValue::from_str(to.as_str()).ok()?,
+    );
+    Some(resp)
+}
+
 fn guess_mime_type(path: &str) -> mime_guess::Mime {
     mime_guess::from_path(path).first_or_octet_stream()
 }
@@ -167,13 +179,21 @@ pub async fn serve(
                 .add_edition(edition)?)
             .set_request(req);
 
-        serve_file(&mut config, &path.join("/")).await
+        if let Some(response) = redirect(&config, path.as_str()) {
+            return t.it(Ok(response));
+        }
+        if let Some(response) = fpm::package::languages::negotiate(&config, path.as_str()) {
+            return t.it(Ok(response));
+        }
+        let response = serve_file(&mut config, &path.join("/")).await;
+        fpm::error_page::handle(&mut config, path.as_str(), response).await
     } else if let Some(cr_number) = fpm::cr::get_cr_path_from_url(path.as_str()) {
         let mut config = fpm::time("Config::read()").it(fpm::Config::read(None, false, Some(&req))
             .await
             .unwrap()
             .add_edition(edition)?);
-        serve_cr_file(&req, &mut config, &path, cr_number).await
+        let response = serve_cr_file(&req, &mut config, &path, cr_number).await;
+        fpm::error_page::handle(&mut config, path.as_str(), response).await
     } else {
         // url is present in config or not
         // If not present than proxy pass it
@@ -186,6 +206,13 @@ pub async fn serve(
             .add_edition(edition)?
             .set_request(req));
 
+        if let Some(response) = redirect(&config, path.as_str()) {
+            return t.it(Ok(response));
+        }
+        if let Some(response) = fpm::package::languages::negotiate(&config, path.as_str()) {
+            return t.it(Ok(response));
+        }
+
         // if start with -/ and mount-point exists so send redirect to mount-point
         // We have to do -/<package-name>/remaining-url/ ==> (<package-name>, remaining-url) ==> (/config.package-name.mount-point/remaining-url/)
         // Get all the dependencies with mount-point if path_start with any package-name so send redirect to mount-point
@@ -230,6 +257,10 @@ pub async fn serve(
             }
         }
 
+        if let Some(response) = fpm::assets::serve(&config, path.as_str()).await {
+            return t.it(Ok(response));
+        }
+
         // if request goes with mount-point /todos/api/add-todo/
         // so it should say not found and pass it to proxy
         let file_response = serve_file(&mut config, path.as_path()).await;
@@ -243,9 +274,20 @@ pub async fn serve(
         if file_response.status() == actix_web::http::StatusCode::NOT_FOUND {
             // TODO: Check if path exists in dynamic urls also, otherwise pass to endpoint
             // Already checked in the above method serve_file
-            println!("executing proxy: {}", &path);
             let (package_name, url, conf) =
-                fpm::config::utils::get_clean_url(&config, path.as_str())?;
+                match fpm::config::utils::get_clean_url(&config, path.as_str()) {
+                    Ok(v) => v,
+                    // no endpoint to proxy to, so it really is a missing page
+                    Err(_) => {
+                        return t.it(Ok(fpm::error_page::handle(
+                            &mut config,
+                            path.as_str(),
+                            file_response,
+                        )
+                        .await));
+                    }
+                };
+            println!("executing proxy: {}", &path);
             let package_name = package_name.unwrap_or_else(|| config.package.name.to_string());
 
             let host = if let Some(port) = url.port() {
@@ -290,7 +332,7 @@ pub async fn serve(
         //     fpm::wasm::handle_wasm(req, wasm_module, config.package.backend_headers).await
         // }
 
-        file_response
+        fpm::error_page::handle(&mut config, path.as_str(), file_response).await
     };
     t.it(Ok(response))
 }
@@ -367,9 +409,21 @@ async fn sync(req: fpm::http::Request) -> fpm::Result<fpm::http::Response> {
 
 async fn sync2(req: fpm::http::Request) -> fpm::Result<fpm::http::Response> {
     let _lock = LOCK.write().await;
+    if req
+        .content_type()
+        .map(|v| v.essence_str() == fpm::apis::sync2_binary::CONTENT_TYPE)
+        .unwrap_or(false)
+    {
+        return fpm::apis::sync2_binary::sync2(&req).await;
+    }
     fpm::apis::sync2(&req, req.json()?).await
 }
 
+async fn sync2_negotiate(req: fpm::http::Request) -> fpm::Result<fpm::http::Response> {
+    let _lock = LOCK.read().await;
+    fpm::apis::sync2_binary::negotiate(&req, req.json()?).await
+}
+
 pub async fn clone(req: fpm::http::Request) -> fpm::Result<fpm::http::Response> {
     let _lock = LOCK.read().await;
     fpm::apis::clone(req).await
@@ -380,6 +434,12 @@ pub(crate) async fn view_source(req: fpm::http::Request) -> fpm::Result<fpm::htt
     Ok(fpm::apis::view_source(&req).await)
 }
 
+pub(crate) async fn image(req: fpm::http::Request) -> fpm::Result<fpm::http::Response> {
+    let _lock = LOCK.read().await;
+    let config = fpm::Config::read(None, false, Some(&req)).await?;
+    Ok(fpm::responsive_image::serve(&config, req.path()).await)
+}
+
 pub async fn edit(req: fpm::http::Request) -> fpm::Result<fpm::http::Response> {
     let _lock = LOCK.write().await;
     fpm::apis::edit(&req, req.json()?).await
@@ -405,6 +465,21 @@ pub async fn create_cr_page(req: fpm::http::Request) -> fpm::Result<fpm::http::R
     fpm::apis::cr::create_cr_page(req).await
 }
 
+pub async fn cr_review(req: fpm::http::Request) -> fpm::Result<fpm::http::Response> {
+    let _lock = LOCK.write().await;
+    fpm::apis::cr::cr_review(&req, req.json()?).await
+}
+
+pub async fn cr_comment(req: fpm::http::Request) -> fpm::Result<fpm::http::Response> {
+    let _lock = LOCK.write().await;
+    fpm::apis::cr::cr_comment(&req, req.json()?).await
+}
+
+pub async fn cr_review_page(req: fpm::http::Request) -> fpm::Result<fpm::http::Response> {
+    let _lock = LOCK.read().await;
+    fpm::apis::cr::cr_review_page(req).await
+}
+
 struct AppData {
     edition: Option<String>,
 }
@@ -418,18 +493,31 @@ async fn route(
         return fpm::auth::routes::handle_auth(req, app_data.edition.clone()).await;
     }
     //dbg!(req.cookies());
-    let req = fpm::http::Request::from_actix(req, body);
+    let req = match fpm::auth::token::authenticate(fpm::http::Request::from_actix(req, body)) {
+        Ok(req) => req,
+        Err(err) => return fpm::http::api_error(err.to_string()),
+    };
     dbg!(req.method(), req.path());
     match (req.method().to_lowercase().as_str(), req.path()) {
+        ("get", "/-/login/token/") if cfg!(feature = "remote") => {
+            fpm::auth::token::token_page(&req).await
+        }
         ("post", "/-/sync/") if cfg!(feature = "remote") => sync(req).await,
+        ("post", "/-/sync2/negotiate/") if cfg!(feature = "remote") => sync2_negotiate(req).await,
         ("post", "/-/sync2/") if cfg!(feature = "remote") => sync2(req).await,
         ("get", "/-/clone/") if cfg!(feature = "remote") => clone(req).await,
         ("get", t) if t.starts_with("/-/view-src/") => view_source(req).await,
+        ("get", t) if t.starts_with("/-/images/") => image(req).await,
         ("post", "/-/edit/") => edit(req).await,
         ("post", "/-/revert/") => revert(req).await,
         ("get", "/-/editor-sync/") => editor_sync(req).await,
         ("post", "/-/create-cr/") => create_cr(req).await,
         ("get", "/-/create-cr-page/") => create_cr_page(req).await,
+        ("post", "/-/cr-review/") => cr_review(req).await,
+        ("post", "/-/cr-comment/") => cr_comment(req).await,
+        ("get", t) if fpm::cr::get_cr_number_from_review_url(t).is_some() => {
+            cr_review_page(req).await
+        }
         ("get", "/-/clear-cache/") => clear_cache(req).await,
         (_, _) => serve(req, app_data.edition.clone()).await,
     }
@@ -440,6 +528,7 @@ pub async fn listen(
     port: Option<u16>,
     package_download_base_url: Option<String>,
     edition: Option<String>,
+    tls: Option<fpm::tls::TlsOptions>,
 ) -> fpm::Result<()> {
     use colored::Colorize;
     dotenv::dotenv().ok();
@@ -481,25 +570,72 @@ You can try without providing port, it will automatically pick unused port."#,
         }
     };
 
+    let tls_config = match tls {
+        Some(ref tls) => Some(fpm::tls::server_config(tls, bind_address).await?),
+        None => None,
+    };
+    let hsts = tls.as_ref().and_then(|v| v.hsts_header());
+
     let app = move || {
         actix_web::App::new()
             .app_data(actix_web::web::Data::new(AppData {
                 edition: edition.clone(),
             }))
+            .wrap(actix_web::middleware::Condition::new(
+                hsts.is_some(),
+                actix_web::middleware::DefaultHeaders::new().add((
+                    actix_web::http::header::STRICT_TRANSPORT_SECURITY,
+                    hsts.clone().unwrap_or_default(),
+                )),
+            ))
             .route("/{path:.*}", actix_web::web::route().to(route))
     };
 
+    let port = tcp_listener.local_addr()?.port();
     println!("### Server Started ###");
     println!(
-        "Go to: http://{}:{}",
+        "Go to: {}://{}:{}",
+        if tls_config.is_some() {
+            "https"
+        } else {
+            "http"
+        },
         bind_address,
-        tcp_listener.local_addr()?.port()
+        port
     );
 
-    actix_web::HttpServer::new(app)
-        .listen(tcp_listener)?
-        .run()
-        .await?;
+    let server = match tls_config {
+        Some(tls_config) => {
+            actix_web::HttpServer::new(app).listen_rustls(tcp_listener, tls_config)?
+        }
+        None => actix_web::HttpServer::new(app).listen(tcp_listener)?,
+    };
+
+    match tls.and_then(|v| v.http_redirect_port) {
+        Some(http_port) => {
+            let http_listener = match fpm::http::get_available_port(Some(http_port), bind_address) {
+                Some(listener) => listener,
+                None => {
+                    eprintln!(
+                        "Provided http redirect port {} is not available.",
+                        http_port.to_string().red()
+                    );
+                    std::process::exit(2);
+                }
+            };
+            let redirect = actix_web::HttpServer::new(move || {
+                actix_web::App::new().default_service(actix_web::web::to(
+                    move |req: actix_web::HttpRequest| async move {
+                        fpm::tls::redirect_to_https(&req, port)
+                    },
+                ))
+            })
+            .listen(http_listener)?;
+            println!("Redirecting http://{}:{} to https", bind_address, http_port);
+            futures::try_join!(server.run(), redirect.run())?;
+        }
+        None => server.run().await?,
+    }
     Ok(())
 }
 
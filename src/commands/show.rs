@@ -0,0 +1,108 @@
+pub const COMMAND: &str = "show";
+
+pub fn command() -> clap::Command {
+    clap::Command::new(COMMAND)
+        .about("Print a version of a file from history")
+        .arg(clap::arg!(file: <FILE> "The file to print, `<file>@<version>` for a given version, else the latest synced version"))
+        .hide(true) // hidden since the feature is not being released yet.
+}
+
+pub async fn handle_command(matches: &clap::ArgMatches) -> fpm::Result<()> {
+    use fpm::utils::ValueOf;
+
+    show(
+        &fpm::Config::read(None, true, None).await?,
+        matches.value_of_("file").unwrap(),
+    )
+    .await
+}
+
+async fn show(config: &fpm::Config, file: &str) -> fpm::Result<()> {
+    use std::io::Write;
+
+    let (file, version) = parse_file_version(file)?;
+    let file_edit = get_file_edit(config, file, version).await?;
+    let content = fpm::history_store::read(&config.history_path(file, file_edit.version)).await?;
+    std::io::stdout().write_all(content.as_slice())?;
+    Ok(())
+}
+
+/// `foo.ftd@3` => (`foo.ftd`, Some(3)), `foo.ftd` => (`foo.ftd`, None). Only a numeric suffix is
+/// a version, `logo@2x.png` is a file.
+pub(crate) fn parse_file_version(file: &str) -> fpm::Result<(&str, Option<i32>)> {
+    match file.rsplit_once('@') {
+        Some((name, version))
+            if !name.is_empty()
+                && !version.is_empty()
+                && version.chars().all(|c| c.is_ascii_digit()) =>
+        {
+            Ok((
+                name,
+                Some(version.parse::<i32>().map_err(|_| fpm::Error::UsageError {
+                    message: format!("invalid version `{}` of `{}`", version, name),
+                })?),
+            ))
+        }
+        _ => Ok((file, None)),
+    }
+}
+
+/// The edit that created `version` of `file`, latest synced edit if `version` is None
+pub(crate) async fn get_file_edit(
+    config: &fpm::Config,
+    file: &str,
+    version: Option<i32>,
+) -> fpm::Result<fpm::history::FileEdit> {
+    let file_edit = match version {
+        Some(version) => config
+            .get_history()
+            .await?
+            .into_iter()
+            .find(|v| v.filename.eq(file))
+            .and_then(|v| v.file_edit.into_iter().find(|e| e.version.eq(&version))),
+        None => config.get_remote_manifest(true).await?.remove(file),
+    };
+    let file_edit = match file_edit {
+        Some(file_edit) => file_edit,
+        None => {
+            return fpm::usage_error(format!(
+                "`{}` not found in history, Help: Use `fpm log {}`",
+                version
+                    .map(|v| format!("{}@{}", file, v))
+                    .unwrap_or_else(|| file.to_string()),
+                file
+            ))
+        }
+    };
+    if file_edit.is_deleted() {
+        return fpm::usage_error(format!(
+            "`{}` is deleted in version {}",
+            file, file_edit.version
+        ));
+    }
+    Ok(file_edit)
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn parse_file_version() {
+        assert_eq!(
+            super::parse_file_version("foo.ftd@3").unwrap(),
+            ("foo.ftd", Some(3))
+        );
+        assert_eq!(
+            super::parse_file_version("foo.ftd").unwrap(),
+            ("foo.ftd", None)
+        );
+        assert_eq!(
+            super::parse_file_version("static/logo@2x.png").unwrap(),
+            ("static/logo@2x.png", None)
+        );
+        assert_eq!(
+            super::parse_file_version("static/logo@2x.png@12").unwrap(),
+            ("static/logo@2x.png", Some(12))
+        );
+        assert!(super::parse_file_version("foo.ftd@99999999999").is_err());
+    }
+}
@@ -0,0 +1,41 @@
+pub const COMMAND: &str = "sitemap";
+
+pub fn command() -> clap::Command {
+    clap::Command::new(COMMAND)
+        .about("Work with the sitemap of the package")
+        .subcommand_required(true)
+        .subcommand(
+            clap::Command::new("generate")
+                .about("Generate an `fpm.sitemap` from the directory tree of the package, to edit and put in FPM.ftd")
+                .arg(clap::arg!(-o --output <FILE> "Write the sitemap to this file instead of stdout")),
+        )
+        .hide(true) // hidden since the feature is not being released yet.
+}
+
+pub async fn handle_command(matches: &clap::ArgMatches) -> fpm::Result<()> {
+    use fpm::utils::ValueOf;
+
+    match matches.subcommand() {
+        Some(("generate", matches)) => {
+            let config = fpm::Config::read(None, false, None).await?;
+            generate(&config, matches.value_of_("output")).await
+        }
+        Some((command, _)) => unreachable!("unknown sitemap subcommand: {}", command),
+        None => unreachable!("subcommand is required"),
+    }
+}
+
+async fn generate(config: &fpm::Config, output: Option<&str>) -> fpm::Result<()> {
+    let generated = format!(
+        "-- fpm.sitemap:\n\n{}",
+        fpm::sitemap::auto::body(config).await?
+    );
+    match output {
+        Some(output) => {
+            tokio::fs::write(output, generated).await?;
+            println!("Generated the sitemap in {}", output);
+        }
+        None => print!("{}", generated),
+    }
+    Ok(())
+}
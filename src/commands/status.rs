@@ -128,7 +128,7 @@ pub(crate) async fn get_file_status(
         let path = fpm::utils::history_path(&doc.get_id(), &doc.get_base_path(), timestamp);
 
         let content = tokio::fs::read(&doc.get_full_path()).await?;
-        let existing_doc = tokio::fs::read(&path).await?;
+        let existing_doc = fpm::history_store::read(&path).await?;
         if sha2::Sha256::digest(content).eq(&sha2::Sha256::digest(existing_doc)) {
             return Ok(FileStatus::Uptodate);
         }
@@ -110,7 +110,7 @@ async fn get_changed_files(
         if let Some(timestamp) = snapshots.get(&document.get_id()) {
             let snapshot_file_path =
                 fpm::utils::history_path(&document.get_id(), &document.get_base_path(), timestamp);
-            let snapshot_file_content = tokio::fs::read(&snapshot_file_path).await?;
+            let snapshot_file_content = fpm::history_store::read(&snapshot_file_path).await?;
             // Update
             let current_file_content = document.get_content();
             if sha2::Sha256::digest(&snapshot_file_content)
@@ -166,7 +166,7 @@ async fn write(
     if let Some(timestamp) = snapshots.get(&doc.get_id()) {
         let path = fpm::utils::history_path(&doc.get_id(), &doc.get_base_path(), timestamp);
         if let Ok(current_doc) = tokio::fs::read(&doc.get_full_path()).await {
-            let existing_doc = tokio::fs::read(&path).await?;
+            let existing_doc = fpm::history_store::read(&path).await?;
 
             if sha2::Sha256::digest(current_doc).eq(&sha2::Sha256::digest(existing_doc)) {
                 return Ok((
@@ -228,7 +228,14 @@ async fn update_current_directory(
 ) -> fpm::Result<()> {
     for file in files {
         match file {
-            fpm::apis::sync::SyncResponseFile::Add { path, content, .. } => {
+            fpm::apis::sync::SyncResponseFile::Add {
+                path,
+                content,
+                status,
+            } => {
+                if fpm::apis::sync::SyncStatus::PermissionDenied.eq(status) {
+                    println!("PermissionDenied: {}", path);
+                }
                 fpm::utils::update1(&config.root, path, content).await?;
             }
             fpm::apis::sync::SyncResponseFile::Update {
@@ -245,6 +252,9 @@ async fn update_current_directory(
                 if fpm::apis::sync::SyncStatus::Conflict.eq(status) {
                     println!("Conflict: {}", path);
                 }
+                if fpm::apis::sync::SyncStatus::PermissionDenied.eq(status) {
+                    println!("PermissionDenied: {}", path);
+                }
                 fpm::utils::update1(&config.root, path, content).await?;
             }
             fpm::apis::sync::SyncResponseFile::Delete { path, .. } => {
@@ -1,9 +1,10 @@
 pub async fn sync2(
     config: &fpm::Config,
     files: Option<Vec<String>>,
+    message: Option<&str>,
     // cr_number: Option<&str>,
 ) -> fpm::Result<()> {
-    simple_sync(config, files).await
+    simple_sync(config, files, message).await
     /*if let Some(cr_number) = cr_number {
         let cr_number = cr_number.parse::<usize>()?;
         cr_sync(config, file, cr_number).await
@@ -35,7 +36,11 @@ pub async fn sync2(
     };
 }*/
 
-async fn simple_sync(config: &fpm::Config, files: Option<Vec<String>>) -> fpm::Result<()> {
+async fn simple_sync(
+    config: &fpm::Config,
+    files: Option<Vec<String>>,
+    message: Option<&str>,
+) -> fpm::Result<()> {
     use itertools::Itertools;
 
     let mut workspace = config.get_clone_workspace().await?;
@@ -53,7 +58,7 @@ async fn simple_sync(config: &fpm::Config, files: Option<Vec<String>>) -> fpm::R
         .filter_map(|v| v.sync_request(None))
         .collect_vec();
 
-    sync_(config, changed_files, &mut workspace).await?;
+    sync_(config, changed_files, &mut workspace, message).await?;
     config
         .update_workspace(workspace.into_values().collect_vec())
         .await
@@ -63,12 +68,16 @@ pub(crate) async fn sync_(
     config: &fpm::Config,
     request_files: Vec<fpm::apis::sync2::SyncRequestFile>,
     workspace: &mut std::collections::BTreeMap<String, fpm::workspace::WorkspaceEntry>,
+    message: Option<&str>,
 ) -> fpm::Result<()> {
     let history = tokio::fs::read_to_string(config.history_file()).await?;
     let sync_request = fpm::apis::sync2::SyncRequest {
         package_name: config.package.name.to_string(),
         files: request_files,
         history,
+        // validated here too, so a bad message fails before anything is sent
+        message: fpm::history::clean_message(message)?,
+        author: config.get_author().await?,
     };
     let response = send_to_fpm_serve(&sync_request).await?;
     update_current_directory(config, &response).await?;
@@ -144,6 +153,8 @@ async fn update_current_directory(
             } => {
                 if status.add_add_conflict() {
                     println!("CloneAddedRemoteAdded: {}", path);
+                } else if status.permission_denied() {
+                    println!("PermissionDenied: {}", path);
                 } else {
                     fpm::utils::update(&config.root.join(path), content).await?;
                 }
@@ -153,18 +164,30 @@ async fn update_current_directory(
                 content,
                 status,
             } => {
-                if status.edit_delete_conflict() {
+                if status.permission_denied() {
+                    println!("PermissionDenied: {}", path);
+                } else if status.edit_delete_conflict() {
                     println!("CloneDeletedRemoteEdit: {}", path);
                 } else if status.delete_edit_conflict() {
                     println!("CloneEditedRemoteDeleted: {}", path);
                 } else if status.edit_edit_conflict() {
+                    // content has the hunks both sides edited marked, resolve them using
+                    // `fpm resolve-conflict`
+                    if std::str::from_utf8(content)
+                        .map(fpm::sync_utils::has_conflict_markers)
+                        .unwrap_or(false)
+                    {
+                        fpm::utils::update(&config.root.join(path), content).await?;
+                    }
                     println!("Conflict: {}", path);
                 } else {
                     fpm::utils::update(&config.root.join(path), content).await?;
                 }
             }
-            fpm::apis::sync2::SyncResponseFile::Delete { path, .. } => {
-                if config.root.join(path).exists() {
+            fpm::apis::sync2::SyncResponseFile::Delete { path, status, .. } => {
+                if status.permission_denied() {
+                    println!("PermissionDenied: {}", path);
+                } else if config.root.join(path).exists() {
                     tokio::fs::remove_file(config.root.join(path)).await?;
                 }
             }
@@ -173,34 +196,70 @@ async fn update_current_directory(
     Ok(())
 }
 
+#[derive(serde::Deserialize, std::fmt::Debug)]
+struct ApiResponse<T> {
+    message: Option<String>,
+    data: Option<T>,
+    success: bool,
+}
+
+impl<T> ApiResponse<T> {
+    fn into_result(self) -> fpm::Result<T> {
+        if !self.success {
+            return Err(fpm::Error::APIResponseError(
+                self.message
+                    .unwrap_or_else(|| "Some Error occurred".to_string()),
+            ));
+        }
+
+        match self.data {
+            Some(data) => Ok(data),
+            None => Err(fpm::Error::APIResponseError(
+                "Unexpected API behaviour".to_string(),
+            )),
+        }
+    }
+}
+
+/// Sends only the contents the server does not have, with the binary encoding. Servers which
+/// don't support it get the JSON request.
 async fn send_to_fpm_serve(
     data: &fpm::apis::sync2::SyncRequest,
 ) -> fpm::Result<fpm::apis::sync2::SyncResponse> {
-    #[derive(serde::Deserialize, std::fmt::Debug)]
-    struct ApiResponse {
-        message: Option<String>,
-        data: Option<fpm::apis::sync2::SyncResponse>,
-        success: bool,
-    }
+    let negotiate: fpm::Result<ApiResponse<fpm::apis::sync2_binary::NegotiateResponse>> =
+        crate::http::post_json(
+            "http://127.0.0.1:8000/-/sync2/negotiate/",
+            serde_json::to_string(&fpm::apis::sync2_binary::NegotiateRequest {
+                package_name: data.package_name.to_string(),
+                hashes: fpm::apis::sync2_binary::request_hashes(data),
+            })?,
+        )
+        .await;
+    let missing = match negotiate.and_then(|v| v.into_result()) {
+        Ok(negotiate) => negotiate.missing.into_iter().collect(),
+        Err(_) => return send_json_to_fpm_serve(data).await,
+    };
 
-    let response: ApiResponse = crate::http::post_json(
+    let (content_type, body) = crate::http::post_bytes(
         "http://127.0.0.1:8000/-/sync2/",
-        serde_json::to_string(&data)?,
+        fpm::apis::sync2_binary::CONTENT_TYPE,
+        fpm::apis::sync2_binary::encode_request(data, &missing)?,
     )
     .await?;
-
-    if !response.success {
-        return Err(fpm::Error::APIResponseError(
-            response
-                .message
-                .unwrap_or_else(|| "Some Error occurred".to_string()),
-        ));
+    if content_type.as_deref() == Some(fpm::apis::sync2_binary::CONTENT_TYPE) {
+        return fpm::apis::sync2_binary::decode_response(body.as_slice());
     }
+    serde_json::from_slice::<ApiResponse<fpm::apis::sync2::SyncResponse>>(body.as_slice())?
+        .into_result()
+}
 
-    match response.data {
-        Some(data) => Ok(data),
-        None => Err(fpm::Error::APIResponseError(
-            "Unexpected API behaviour".to_string(),
-        )),
-    }
+async fn send_json_to_fpm_serve(
+    data: &fpm::apis::sync2::SyncRequest,
+) -> fpm::Result<fpm::apis::sync2::SyncResponse> {
+    let response: ApiResponse<fpm::apis::sync2::SyncResponse> = crate::http::post_json(
+        "http://127.0.0.1:8000/-/sync2/",
+        serde_json::to_string(&data)?,
+    )
+    .await?;
+    response.into_result()
 }
@@ -0,0 +1,122 @@
+pub const COMMAND: &str = "tracks";
+
+pub fn command() -> clap::Command {
+    clap::Command::new(COMMAND)
+        .about("Show the files tracked with `fpm start-tracking` and whether they are up to date")
+        .subcommand_required(true)
+        .subcommand(
+            clap::Command::new("list")
+                .about("List every source -> target tracking with its state")
+                .arg(clap::arg!(--json "Print the list as JSON")),
+        )
+        .subcommand(
+            clap::Command::new("diff")
+                .about(
+                    "Show what changed in the targets of the source since last marked up to date",
+                )
+                .arg(clap::arg!(source: <SOURCE> "The file tracking the targets"))
+                .arg(clap::arg!(--target <TARGET> "Only show the diff of this target")),
+        )
+        .hide(true) // hidden since the feature is not being released yet.
+}
+
+pub async fn handle_command(matches: &clap::ArgMatches) -> fpm::Result<()> {
+    use fpm::utils::ValueOf;
+
+    let config = fpm::Config::read(None, true, None).await?;
+    match matches.subcommand() {
+        Some(("list", matches)) => list(&config, matches.get_flag("json")).await,
+        Some(("diff", matches)) => {
+            diff(
+                &config,
+                matches.value_of_("source").unwrap(),
+                matches.value_of_("target"),
+            )
+            .await
+        }
+        Some((command, _)) => unreachable!("unknown tracks subcommand: {}", command),
+        None => unreachable!("subcommand is required"),
+    }
+}
+
+async fn list(config: &fpm::Config, json: bool) -> fpm::Result<()> {
+    use colored::Colorize;
+
+    let relations = fpm::tracker::get_track_relations(config).await?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&relations)?);
+        return Ok(());
+    }
+
+    if relations.is_empty() {
+        println!("No file is tracked, Help: Use `fpm start-tracking <source> --target <target>`");
+        return Ok(());
+    }
+    for relation in relations {
+        let status = match relation.outdated_seconds {
+            Some(seconds) => format!(
+                "{} by {}",
+                relation.status.as_str(),
+                fpm::utils::seconds_to_duration(seconds)
+            ),
+            None => relation.status.as_str().to_string(),
+        };
+        let status = match relation.status {
+            fpm::tracker::TrackState::UpToDate => status.green(),
+            fpm::tracker::TrackState::Outdated => status.red(),
+            fpm::tracker::TrackState::NeverMarked => status.yellow(),
+        };
+        println!("{} -> {}: {}", relation.source, relation.target, status);
+    }
+    Ok(())
+}
+
+async fn diff(config: &fpm::Config, source: &str, target: Option<&str>) -> fpm::Result<()> {
+    let snapshots = fpm::snapshot::get_latest_snapshots(&config.root).await?;
+    let relations = fpm::tracker::get_source_relations(config.root.as_str(), source, &snapshots)?
+        .into_iter()
+        .filter(|v| target.map(|t| t.eq(v.target.as_str())).unwrap_or(true))
+        .collect::<Vec<_>>();
+    if relations.is_empty() {
+        return fpm::usage_error(match target {
+            Some(target) => format!("{} is not tracking {}", source, target),
+            None => format!("{} is not tracking any file", source),
+        });
+    }
+
+    for relation in relations {
+        let marked_timestamp = match relation.marked_timestamp {
+            Some(marked_timestamp) => marked_timestamp,
+            None => {
+                println!(
+                    "{} -> {}: never marked up to date, Help: Use `fpm mark-upto-date {} --target {}`",
+                    relation.source, relation.target, relation.source, relation.target
+                );
+                continue;
+            }
+        };
+        if relation.status == fpm::tracker::TrackState::UpToDate {
+            println!("{} -> {}: up to date", relation.source, relation.target);
+            continue;
+        }
+        let then = fpm::history_store::read_to_string(&fpm::utils::history_path(
+            relation.target.as_str(),
+            config.root.as_str(),
+            &marked_timestamp,
+        ))
+        .await?;
+        let now = fpm::history_store::read_to_string(&fpm::utils::history_path(
+            relation.target.as_str(),
+            config.root.as_str(),
+            &relation.target_timestamp,
+        ))
+        .await?;
+        let patch = diffy::create_patch(&then, &now);
+        println!("diff {} -> {}", relation.source, relation.target);
+        println!(
+            "{}",
+            diffy::PatchFormatter::new().with_color().fmt_patch(&patch)
+        );
+    }
+    Ok(())
+}
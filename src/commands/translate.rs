@@ -0,0 +1,87 @@
+pub const COMMAND: &str = "translate";
+
+pub fn command() -> clap::Command {
+    clap::Command::new(COMMAND)
+        .about("Draft the missing and out-dated documents of a translation package with machine translation")
+        .arg(
+            clap::arg!(--provider <PROVIDER> "The machine translation provider")
+                .value_parser(["libretranslate"])
+                .default_value("libretranslate"),
+        )
+        .arg(clap::arg!(--url <URL> "The url of the provider, e.g. http://127.0.0.1:5000 for LibreTranslate"))
+        .arg(clap::arg!(--"api-key" <KEY> "The API key of the provider, FPM_TRANSLATE_API_KEY if not given"))
+        .arg(clap::arg!(file: <FILE>... "Only draft these documents").required(false))
+        .hide(true) // hidden since the feature is not being released yet.
+}
+
+pub async fn handle_command(matches: &clap::ArgMatches) -> fpm::Result<()> {
+    use fpm::utils::ValueOf;
+
+    let config = fpm::Config::read(None, true, None).await?;
+    if !config.is_translation_package() {
+        return fpm::usage_error(
+            "`translate` works only in a translation package, set `translation-of` in FPM.ftd"
+                .to_string(),
+        );
+    }
+    let target_language = match config.package.language {
+        Some(ref language) => language.to_string(),
+        None => {
+            return fpm::usage_error(
+                "Set `language` of the package in FPM.ftd to translate to it".to_string(),
+            )
+        }
+    };
+    let api_key = std::env::var("FPM_TRANSLATE_API_KEY").ok();
+    let provider = fpm::translation_exchange::provider::from_name(
+        matches.value_of_("provider").unwrap_or("libretranslate"),
+        matches.value_of_("url"),
+        matches.value_of_("api-key").or(api_key.as_deref()),
+    )?;
+    let files: Option<Vec<String>> = matches
+        .get_many::<String>("file")
+        .map(|v| v.map(|v| v.to_string()).collect());
+
+    let source_language = fpm::commands::translation::source_language(&config);
+    let original_path = config.original_path()?;
+    let documents =
+        fpm::commands::translation::pending_documents(&config, files.as_deref()).await?;
+    if documents.is_empty() {
+        println!("Nothing to translate, every document is up to date or never marked");
+        return Ok(());
+    }
+    for mut document in documents {
+        let translated = fpm::translation_exchange::provider::draft(
+            provider.as_ref(),
+            &mut document.units,
+            source_language.as_str(),
+            target_language.as_str(),
+        )
+        .await?;
+        let original = tokio::fs::read_to_string(original_path.join(&document.id)).await?;
+        let (content, _) = fpm::translation_exchange::apply(original.as_str(), &document.units);
+        fpm::utils::update(&config.root.join(&document.id), content.as_bytes()).await?;
+        unapprove(&config, document.id.as_str()).await?;
+        println!(
+            "{}: drafted, {} strings machine translated. Review it, sync it, and run \
+            `fpm mark-upto-date {}`",
+            document.id, translated, document.id
+        );
+    }
+    Ok(())
+}
+
+/// Forgets when `file` was last marked up to date, so its draft shows as unapproved till it is
+/// reviewed and marked again
+async fn unapprove(config: &fpm::Config, file: &str) -> fpm::Result<()> {
+    let track_path = fpm::utils::track_path(file, config.root.as_str());
+    if !track_path.exists() {
+        return Ok(());
+    }
+    let mut tracks = fpm::tracker::get_tracks(config.root.as_str(), &track_path)?;
+    match tracks.get_mut(file) {
+        Some(track) if track.last_merged_version.is_some() => track.last_merged_version = None,
+        _ => return Ok(()),
+    }
+    fpm::commands::mark_upto_date::write(&track_path, &tracks).await
+}
@@ -0,0 +1,263 @@
+pub const COMMAND: &str = "translation";
+
+pub fn command() -> clap::Command {
+    clap::Command::new(COMMAND)
+        .about("Exchange the strings of a translation package with translators as XLIFF or PO")
+        .subcommand_required(true)
+        .subcommand(
+            clap::Command::new("export")
+                .about("Export the strings of the missing and out-dated documents to translate")
+                .arg(
+                    clap::arg!(--format <FORMAT> "The format of the export")
+                        .value_parser(["xliff", "po"])
+                        .default_value("xliff"),
+                )
+                .arg(clap::arg!(-o --output <FILE> "Write the export to this file instead of stdout"))
+                .arg(clap::arg!(file: <FILE>... "Only export these documents").required(false)),
+        )
+        .subcommand(
+            clap::Command::new("import")
+                .about("Write the translated documents and mark the fully translated ones up to date, sync them after")
+                .arg(clap::arg!(file: <FILE> "The translated XLIFF or PO file"))
+                .arg(
+                    clap::arg!(--format <FORMAT> "The format of the file, guessed from its extension if not given")
+                        .value_parser(["xliff", "po"]),
+                ),
+        )
+        .hide(true) // hidden since the feature is not being released yet.
+}
+
+pub async fn handle_command(matches: &clap::ArgMatches) -> fpm::Result<()> {
+    use fpm::utils::ValueOf;
+
+    let config = fpm::Config::read(None, true, None).await?;
+    if !config.is_translation_package() {
+        return fpm::usage_error(
+            "`translation` works only in a translation package, set `translation-of` in FPM.ftd"
+                .to_string(),
+        );
+    }
+    match matches.subcommand() {
+        Some(("export", matches)) => {
+            export(
+                &config,
+                fpm::translation_exchange::Format::from_name(
+                    matches.value_of_("format").unwrap_or("xliff"),
+                )?,
+                matches.value_of_("output"),
+                matches
+                    .get_many::<String>("file")
+                    .map(|v| v.map(|v| v.to_string()).collect()),
+            )
+            .await
+        }
+        Some(("import", matches)) => {
+            let file = camino::Utf8PathBuf::from(matches.value_of_("file").unwrap());
+            let format = match matches.value_of_("format") {
+                Some(format) => fpm::translation_exchange::Format::from_name(format)?,
+                None => match fpm::translation_exchange::Format::from_path(&file) {
+                    Some(format) => format,
+                    None => {
+                        return fpm::usage_error(format!(
+                            "Can't guess the format of {}, use `--format xliff|po`",
+                            file
+                        ))
+                    }
+                },
+            };
+            import(&config, &file, format).await
+        }
+        Some((command, _)) => unreachable!("unknown translation subcommand: {}", command),
+        None => unreachable!("subcommand is required"),
+    }
+}
+
+async fn export(
+    config: &fpm::Config,
+    format: fpm::translation_exchange::Format,
+    output: Option<&str>,
+    files: Option<Vec<String>>,
+) -> fpm::Result<()> {
+    let documents = pending_documents(config, files.as_deref()).await?;
+    let source_language = source_language(config);
+    let exported = fpm::translation_exchange::export(
+        &documents,
+        source_language.as_str(),
+        config.package.language.as_deref(),
+        format,
+    );
+    match output {
+        Some(output) => {
+            tokio::fs::write(output, exported).await?;
+            println!(
+                "Exported {} strings of {} documents to {}",
+                documents.iter().map(|v| v.units.len()).sum::<usize>(),
+                documents.len(),
+                output
+            );
+        }
+        None => print!("{}", exported),
+    }
+    Ok(())
+}
+
+async fn import(
+    config: &fpm::Config,
+    file: &camino::Utf8Path,
+    format: fpm::translation_exchange::Format,
+) -> fpm::Result<()> {
+    let documents =
+        fpm::translation_exchange::import(tokio::fs::read_to_string(file).await?.as_str(), format)?;
+    let original_path = config.original_path()?;
+
+    let mut written = vec![];
+    let mut translated = vec![];
+    for document in documents {
+        let original_file = original_path.join(&document.id);
+        if !original_file.exists() {
+            fpm::warning!(
+                "{} is not in the original package, it is not imported",
+                document.id
+            );
+            continue;
+        }
+        let original = tokio::fs::read_to_string(&original_file).await?;
+        let (content, untranslated) =
+            fpm::translation_exchange::apply(original.as_str(), &document.units);
+        fpm::utils::update(&config.root.join(&document.id), content.as_bytes()).await?;
+        if untranslated == 0 {
+            translated.push(document.id.clone());
+        } else {
+            fpm::warning!(
+                "{}: {} strings are not translated or need review, it is not marked up to date",
+                document.id,
+                untranslated
+            );
+        }
+        written.push(document.id);
+    }
+    if written.is_empty() {
+        println!("Nothing to import");
+        return Ok(());
+    }
+
+    let original_snapshots = fpm::snapshot::get_latest_snapshots(&original_path).await?;
+    let snapshots = fpm::snapshot::get_latest_snapshots(&config.root).await?;
+    for file in translated {
+        mark_translated(config, file.as_str(), &original_snapshots, &snapshots).await?;
+    }
+    println!(
+        "Imported {} documents, review them and run `fpm sync` to sync them",
+        written.len()
+    );
+    Ok(())
+}
+
+/// Marks `file` up to date with the latest snapshot of its original. A document missing till now
+/// has no track, and no snapshot of its own till it is synced.
+async fn mark_translated(
+    config: &fpm::Config,
+    file: &str,
+    original_snapshots: &std::collections::BTreeMap<String, u128>,
+    snapshots: &std::collections::BTreeMap<String, u128>,
+) -> fpm::Result<()> {
+    let original_timestamp = match original_snapshots.get(file) {
+        Some(timestamp) => *timestamp,
+        None => return fpm::usage_error(format!("{} is not synced in the original package", file)),
+    };
+    let track_path = fpm::utils::track_path(file, config.root.as_str());
+    let mut tracks = fpm::tracker::get_tracks(config.root.as_str(), &track_path)?;
+    match tracks.get_mut(file) {
+        Some(track) => track.last_merged_version = Some(original_timestamp),
+        None => {
+            tracks.insert(
+                file.to_string(),
+                fpm::Track {
+                    filename: file.to_string(),
+                    package: config
+                        .package
+                        .translation_of
+                        .as_ref()
+                        .as_ref()
+                        .map(|v| v.name.clone()),
+                    version: None,
+                    other_timestamp: None,
+                    self_timestamp: snapshots
+                        .get(file)
+                        .copied()
+                        .unwrap_or_else(fpm::timestamp_nanosecond),
+                    last_merged_version: Some(original_timestamp),
+                },
+            );
+        }
+    }
+    println!("{} is now marked upto date", file);
+    fpm::commands::mark_upto_date::write(&track_path, &tracks).await
+}
+
+/// The strings of the missing and out-dated documents, or of the `files` among them. The strings
+/// of an out-dated document not changed since it was last marked up to date already have their
+/// translation.
+pub(crate) async fn pending_documents(
+    config: &fpm::Config,
+    files: Option<&[String]>,
+) -> fpm::Result<Vec<fpm::translation_exchange::Document>> {
+    use fpm::commands::translation_status::TranslationStatus;
+
+    let original_path = config.original_path()?;
+    let original_snapshots = fpm::snapshot::get_latest_snapshots(&original_path).await?;
+    let translation_status = fpm::commands::translation_status::get_translation_status(
+        &original_snapshots,
+        &config.root,
+    )?;
+
+    let mut documents = vec![];
+    for (file, status) in translation_status {
+        if !file.ends_with(".ftd") || files.map(|v| !v.contains(&file)).unwrap_or(false) {
+            continue;
+        }
+        let mut units = match status {
+            TranslationStatus::Missing | TranslationStatus::Outdated => {
+                let original = tokio::fs::read_to_string(original_path.join(&file)).await?;
+                fpm::translation_exchange::extract(original.as_str())
+            }
+            TranslationStatus::NeverMarked | TranslationStatus::UptoDate => continue,
+        };
+        if let TranslationStatus::Outdated = status {
+            let tracks = fpm::tracker::get_tracks(
+                config.root.as_str(),
+                &fpm::utils::track_path(file.as_str(), config.root.as_str()),
+            )?;
+            if let Some(last_merged_version) = tracks.get(&file).and_then(|v| v.last_merged_version)
+            {
+                let last_marked = fpm::history_store::read_to_string(&fpm::utils::history_path(
+                    file.as_str(),
+                    original_path.as_str(),
+                    &last_merged_version,
+                ))
+                .await?;
+                let translated = tokio::fs::read_to_string(config.root.join(&file)).await?;
+                fpm::translation_exchange::prefill(
+                    &mut units,
+                    last_marked.as_str(),
+                    translated.as_str(),
+                );
+            }
+        }
+        if !units.is_empty() {
+            documents.push(fpm::translation_exchange::Document { id: file, units });
+        }
+    }
+    Ok(documents)
+}
+
+/// Language of the original package, `und` if it is not set
+pub(crate) fn source_language(config: &fpm::Config) -> String {
+    config
+        .package
+        .translation_of
+        .as_ref()
+        .as_ref()
+        .and_then(|v| v.language.clone())
+        .unwrap_or_else(|| "und".to_string())
+}
@@ -105,6 +105,10 @@ impl Config {
         self.cr_path(cr_number).join("-/meta.ftd")
     }
 
+    pub fn cr_reviews_path(&self, cr_number: usize) -> camino::Utf8PathBuf {
+        self.cr_path(cr_number).join("-/reviews.ftd")
+    }
+
     pub fn remote_dir(&self) -> camino::Utf8PathBuf {
         self.root.join(".remote-state")
     }
@@ -1290,6 +1294,18 @@ impl Config {
                     s.writers = sitemap_temp.writers.clone();
                     Some(s)
                 }
+                None if package.auto_sitemap => {
+                    let body = fpm::sitemap::auto::body(&config).await?;
+                    Some(
+                        fpm::sitemap::Sitemap::parse(
+                            body.as_str(),
+                            &package,
+                            &mut config,
+                            resolve_sitemap,
+                        )
+                        .await?,
+                    )
+                }
                 None => None,
             }
         };
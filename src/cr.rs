@@ -190,6 +190,366 @@ pub(crate) fn generate_deleted_files_content(cr_deleted_files: &[CRDeleted]) ->
     format!("{content}\n")
 }
 
+#[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ReviewStatus {
+    Approved,
+    ChangesRequested,
+}
+
+impl ReviewStatus {
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            ReviewStatus::Approved => "approved",
+            ReviewStatus::ChangesRequested => "changes requested",
+        }
+    }
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct CRReview {
+    pub author: String,
+    pub status: ReviewStatus,
+    pub timestamp: u128,
+    pub message: Option<String>,
+}
+
+/// Review comment anchored to a line of a file, `filename` is the file name without the cr
+/// prefix, same as in main
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct CRComment {
+    pub author: String,
+    pub filename: String,
+    pub line: usize,
+    pub timestamp: u128,
+    pub message: String,
+}
+
+#[derive(serde::Serialize, Debug, Default)]
+pub struct CRReviews {
+    pub reviews: Vec<CRReview>,
+    pub comments: Vec<CRComment>,
+}
+
+impl CRReviews {
+    /// The latest review of every reviewer, earlier reviews are superseded by it
+    pub(crate) fn latest(&self) -> std::collections::BTreeMap<&str, &CRReview> {
+        let mut latest: std::collections::BTreeMap<&str, &CRReview> = Default::default();
+        for review in self.reviews.iter() {
+            match latest.get(review.author.as_str()) {
+                Some(v) if v.timestamp > review.timestamp => {}
+                _ => {
+                    latest.insert(review.author.as_str(), review);
+                }
+            }
+        }
+        latest
+    }
+
+    /// `status` of the CR for merging into main, approvals by `cr_author` don't count
+    pub(crate) fn merge_status(&self, cr_author: Option<&str>) -> Option<ReviewStatus> {
+        CRReviews {
+            reviews: self
+                .reviews
+                .iter()
+                .filter(|v| {
+                    !(v.status.eq(&ReviewStatus::Approved)
+                        && Some(v.author.as_str()).eq(&cr_author))
+                })
+                .cloned()
+                .collect(),
+            comments: vec![],
+        }
+        .status()
+    }
+
+    /// `ChangesRequested` if any reviewer still requests changes, else `Approved` if approved by
+    /// someone, `None` if the CR is not reviewed yet
+    pub(crate) fn status(&self) -> Option<ReviewStatus> {
+        let latest = self.latest();
+        if latest
+            .values()
+            .any(|v| v.status.eq(&ReviewStatus::ChangesRequested))
+        {
+            Some(ReviewStatus::ChangesRequested)
+        } else if latest
+            .values()
+            .any(|v| v.status.eq(&ReviewStatus::Approved))
+        {
+            Some(ReviewStatus::Approved)
+        } else {
+            None
+        }
+    }
+}
+
+pub(crate) async fn get_cr_reviews(
+    config: &fpm::Config,
+    cr_number: usize,
+) -> fpm::Result<CRReviews> {
+    if !config.cr_path(cr_number).exists() {
+        return fpm::usage_error(format!("CR#{} doesn't exist", cr_number));
+    }
+    let reviews_path = config.cr_reviews_path(cr_number);
+    if !reviews_path.exists() {
+        return Ok(Default::default());
+    }
+    let reviews_content = tokio::fs::read_to_string(&reviews_path).await?;
+    resolve_cr_reviews(reviews_content.as_str())
+}
+
+pub(crate) fn resolve_cr_reviews(content: &str) -> fpm::Result<CRReviews> {
+    if content.trim().is_empty() {
+        return Ok(Default::default());
+    }
+    let lib = fpm::FPMLibrary::default();
+    let b = fpm::doc::parse_ftd("reviews.ftd", content, &lib)?;
+    Ok(CRReviews {
+        reviews: b.get("fpm#cr-review")?,
+        comments: b.get("fpm#cr-comment")?,
+    })
+}
+
+pub(crate) fn generate_cr_reviews_content(cr_reviews: &CRReviews) -> String {
+    let mut reviews_content = vec!["-- import: fpm".to_string()];
+
+    for review in cr_reviews.reviews.iter() {
+        let mut content = format!(
+            "-- fpm.cr-review: {}\nstatus: {:?}\ntimestamp: {}",
+            review.author, review.status, review.timestamp
+        );
+        if let Some(ref message) = review.message {
+            content = format!("{}\n\n{}", content, message);
+        }
+        reviews_content.push(content)
+    }
+
+    for comment in cr_reviews.comments.iter() {
+        reviews_content.push(format!(
+            "-- fpm.cr-comment: {}\nfilename: {}\nline: {}\ntimestamp: {}\n\n{}",
+            comment.author, comment.filename, comment.line, comment.timestamp, comment.message
+        ))
+    }
+
+    let content = reviews_content.join("\n\n");
+    format!("{content}\n")
+}
+
+/// Adds a review to CR `cr_number`, the message is cleaned like the sync messages in history
+pub(crate) async fn add_cr_review(
+    config: &fpm::Config,
+    cr_number: usize,
+    status: ReviewStatus,
+    message: Option<&str>,
+    author: Option<&str>,
+) -> fpm::Result<CRReviews> {
+    let author = review_author(author)?;
+    if !is_open_cr_exists(config, cr_number).await? {
+        return fpm::usage_error(format!("CR#{} is closed", cr_number));
+    }
+    if status.eq(&ReviewStatus::Approved)
+        && get_cr_author(config, cr_number).await?.as_deref() == Some(author.as_str())
+    {
+        return fpm::usage_error(format!(
+            "CR#{} can't be approved by its own author, it needs a second pair of eyes",
+            cr_number
+        ));
+    }
+    let mut cr_reviews = get_cr_reviews(config, cr_number).await?;
+    cr_reviews.reviews.push(CRReview {
+        author,
+        status,
+        timestamp: fpm::timestamp_nanosecond(),
+        message: fpm::history::clean_message(message)?,
+    });
+    Ok(cr_reviews)
+}
+
+/// Adds a comment on `line` of `filename`, which has to be a file changed by CR `cr_number`
+pub(crate) async fn add_cr_comment(
+    config: &fpm::Config,
+    cr_number: usize,
+    filename: &str,
+    line: usize,
+    message: &str,
+    author: Option<&str>,
+) -> fpm::Result<CRReviews> {
+    let author = review_author(author)?;
+    if !is_open_cr_exists(config, cr_number).await? {
+        return fpm::usage_error(format!("CR#{} is closed", cr_number));
+    }
+    let message = match fpm::history::clean_message(Some(message))? {
+        Some(message) => message,
+        None => return fpm::usage_error("Comment can't be empty".to_string()),
+    };
+    if line == 0 {
+        return fpm::usage_error("Line numbers start from 1".to_string());
+    }
+    let filename = filename.trim_start_matches('/');
+    if !cr_diff(config, cr_number)
+        .await?
+        .iter()
+        .any(|v| v.filename.eq(filename))
+    {
+        return fpm::usage_error(format!(
+            "`{}` is not changed in CR#{}, Help: Use `fpm cr show {}`",
+            filename, cr_number, cr_number
+        ));
+    }
+    let mut cr_reviews = get_cr_reviews(config, cr_number).await?;
+    cr_reviews.comments.push(CRComment {
+        author,
+        filename: filename.to_string(),
+        line,
+        timestamp: fpm::timestamp_nanosecond(),
+        message,
+    });
+    Ok(cr_reviews)
+}
+
+fn review_author(author: Option<&str>) -> fpm::Result<String> {
+    match fpm::history::clean_author(author)? {
+        Some(author) => Ok(author),
+        None => fpm::usage_error(
+            "Reviews need an author, Help: Use `fpm login` to review as the logged in user"
+                .to_string(),
+        ),
+    }
+}
+
+/// Author of the sync which created CR `cr_number`, the first version of its meta file
+pub(crate) async fn get_cr_author(
+    config: &fpm::Config,
+    cr_number: usize,
+) -> fpm::Result<Option<String>> {
+    let meta_path = config.path_without_root(&config.cr_meta_path(cr_number))?;
+    Ok(config
+        .get_history()
+        .await?
+        .into_iter()
+        .find(|v| v.filename.eq(meta_path.as_str()))
+        .and_then(|v| v.file_edit.into_iter().min_by_key(|v| v.version))
+        .and_then(|v| v.author))
+}
+
+/// All the CRs of the package, in the order of their numbers
+pub(crate) async fn get_cr_list(config: &fpm::Config) -> fpm::Result<Vec<CRMeta>> {
+    let cr_dir = config.root.join("-");
+    if !cr_dir.exists() {
+        return Ok(vec![]);
+    }
+    let mut cr_numbers = vec![];
+    let mut entries = tokio::fs::read_dir(&cr_dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        if let Some(cr_number) = entry
+            .file_name()
+            .to_str()
+            .and_then(|v| v.parse::<usize>().ok())
+        {
+            if config.cr_meta_path(cr_number).exists() {
+                cr_numbers.push(cr_number);
+            }
+        }
+    }
+    cr_numbers.sort_unstable();
+
+    let mut cr_list = vec![];
+    for cr_number in cr_numbers {
+        cr_list.push(get_cr_meta(config, cr_number).await?);
+    }
+    Ok(cr_list)
+}
+
+#[derive(serde::Serialize, Debug)]
+pub struct CRFileDiff {
+    pub filename: String,
+    pub operation: fpm::history::FileOperation,
+    /// Unified diff, `None` for binary files
+    pub diff: Option<String>,
+}
+
+/// Combined diff of the synced files of CR `cr_number` against the latest version in main
+pub(crate) async fn cr_diff(
+    config: &fpm::Config,
+    cr_number: usize,
+) -> fpm::Result<Vec<CRFileDiff>> {
+    let remote_manifest = config.get_remote_manifest(false).await?;
+    let cr_manifest = config.get_cr_manifest(cr_number).await?;
+    let cr_prefix = format!("{}/", cr_path(cr_number));
+
+    let read_main = |filename: &str| {
+        let path = remote_manifest
+            .get(filename)
+            .map(|file_edit| config.history_path(filename, file_edit.version));
+        async move {
+            match path {
+                Some(path) => fpm::history_store::read(&path).await.map(Some),
+                None => Ok(None),
+            }
+        }
+    };
+
+    let mut diffs = vec![];
+    for (filename, file_edit) in cr_manifest.iter() {
+        let main_filename = match filename.strip_prefix(cr_prefix.as_str()) {
+            // `-/` of the cr has the cr meta files, like `deleted.ftd`
+            Some(main_filename) if !main_filename.starts_with("-/") => main_filename,
+            _ => continue,
+        };
+        if file_edit.is_deleted() {
+            continue;
+        }
+        let cr_content =
+            fpm::history_store::read(&config.history_path(filename, file_edit.version)).await?;
+        let main_content = read_main(main_filename).await?;
+        if let Some(file_diff) = file_diff(main_filename, main_content, Some(cr_content)) {
+            diffs.push(file_diff);
+        }
+    }
+
+    let deleted_files_path = config.path_without_root(&config.cr_deleted_file_path(cr_number))?;
+    if let Some(file_edit) = cr_manifest
+        .get(deleted_files_path.as_str())
+        .filter(|v| !v.is_deleted())
+    {
+        let content = fpm::history_store::read_to_string(
+            &config.history_path(deleted_files_path.as_str(), file_edit.version),
+        )
+        .await?;
+        for cr_deleted in resolve_cr_deleted(content.as_str(), cr_number).await? {
+            let main_content = read_main(cr_deleted.filename.as_str()).await?;
+            if let Some(file_diff) = file_diff(cr_deleted.filename.as_str(), main_content, None) {
+                diffs.push(file_diff);
+            }
+        }
+    }
+
+    diffs.sort_by(|a, b| a.filename.cmp(&b.filename));
+    Ok(diffs)
+}
+
+fn file_diff(filename: &str, main: Option<Vec<u8>>, cr: Option<Vec<u8>>) -> Option<CRFileDiff> {
+    let operation = match (&main, &cr) {
+        (None, None) => return None,
+        (Some(main), Some(cr)) if main.eq(cr) => return None,
+        (None, Some(_)) => fpm::history::FileOperation::Added,
+        (Some(_), None) => fpm::history::FileOperation::Deleted,
+        (Some(_), Some(_)) => fpm::history::FileOperation::Updated,
+    };
+    let as_str = |content: &Option<Vec<u8>>| match content {
+        Some(content) => std::str::from_utf8(content).ok().map(ToString::to_string),
+        None => Some("".to_string()),
+    };
+    let diff = match (as_str(&main), as_str(&cr)) {
+        (Some(main), Some(cr)) => Some(diffy::create_patch(&main, &cr).to_string()),
+        _ => None,
+    };
+    Some(CRFileDiff {
+        filename: filename.to_string(),
+        operation,
+        diff,
+    })
+}
+
 impl fpm::Config {
     #[allow(dead_code)]
     pub(crate) async fn get_cr_tracking_info(
@@ -244,6 +604,14 @@ pub(crate) fn get_cr_path_from_url(path: &str) -> Option<usize> {
     cr_number
 }
 
+/// `/-/<cr number>/-/review/` => cr number
+pub(crate) fn get_cr_number_from_review_url(path: &str) -> Option<usize> {
+    path.trim_matches('/')
+        .strip_prefix("-/")
+        .and_then(|v| v.strip_suffix("/-/review"))
+        .and_then(|v| v.parse::<usize>().ok())
+}
+
 pub(crate) fn get_id_from_cr_id(id: &str, cr_number: usize) -> fpm::Result<String> {
     let cr_path = cr_path(cr_number);
     if let Some(id) = id.trim_start_matches('/').strip_prefix(cr_path.as_str()) {
@@ -282,7 +650,7 @@ pub(crate) async fn cr_clone_file_info(
             continue;
         }
         let file_path = config.history_path(filename.as_str(), file_edit.version);
-        let content = tokio::fs::read(&file_path).await?;
+        let content = fpm::history_store::read(&file_path).await?;
 
         let path = config.path_without_root(&file_path)?;
 
@@ -319,7 +687,7 @@ pub(crate) async fn cr_clone_file_info(
             } else {
                 config.root.join(workspace_entry.filename)
             };
-            let cr_deleted_files = tokio::fs::read_to_string(cr_deleted_path).await?;
+            let cr_deleted_files = fpm::history_store::read_to_string(&cr_deleted_path).await?;
             fpm::cr::resolve_cr_deleted(cr_deleted_files.as_str(), cr_number)
                 .await?
                 .into_iter()
@@ -364,7 +732,7 @@ pub(crate) async fn cr_remote_file_info(
             continue;
         }
         let file_path = config.history_path(filename.as_str(), file_edit.version);
-        let content = tokio::fs::read(&file_path).await?;
+        let content = fpm::history_store::read(&file_path).await?;
 
         let path = config.path_without_root(&file_path)?;
 
@@ -394,7 +762,7 @@ pub(crate) async fn cr_remote_file_info(
 
         if filename.eq(&deleted_file_str) {
             let cr_deleted_path = config.history_path(filename.as_str(), file_edit.version);
-            let cr_deleted_files = tokio::fs::read_to_string(cr_deleted_path).await?;
+            let cr_deleted_files = fpm::history_store::read_to_string(&cr_deleted_path).await?;
             fpm::cr::resolve_cr_deleted(cr_deleted_files.as_str(), cr_number)
                 .await?
                 .into_iter()
@@ -406,7 +774,7 @@ pub(crate) async fn cr_remote_file_info(
         }
 
         let file_path = config.history_path(filename.as_str(), file_edit.version);
-        let content = tokio::fs::read(&file_path).await?;
+        let content = fpm::history_store::read(&file_path).await?;
 
         let path = config.path_without_root(&file_path)?;
 
@@ -421,3 +789,68 @@ pub(crate) async fn cr_remote_file_info(
 
     Ok(file_info)
 }
+
+#[cfg(test)]
+mod tests {
+    fn review(author: &str, status: super::ReviewStatus, timestamp: u128) -> super::CRReview {
+        super::CRReview {
+            author: author.to_string(),
+            status,
+            timestamp,
+            message: None,
+        }
+    }
+
+    #[test]
+    fn review_status() {
+        use super::ReviewStatus::{Approved, ChangesRequested};
+
+        let mut cr_reviews = super::CRReviews::default();
+        assert_eq!(cr_reviews.status(), None);
+
+        cr_reviews
+            .reviews
+            .push(review("github:amitu", ChangesRequested, 1));
+        cr_reviews
+            .reviews
+            .push(review("github:arpita", Approved, 2));
+        assert_eq!(cr_reviews.status(), Some(ChangesRequested));
+
+        // later review of the same reviewer supersedes the earlier one
+        cr_reviews.reviews.push(review("github:amitu", Approved, 3));
+        assert_eq!(cr_reviews.status(), Some(Approved));
+        assert_eq!(cr_reviews.latest().len(), 2);
+    }
+
+    #[test]
+    fn merge_status() {
+        use super::ReviewStatus::{Approved, ChangesRequested};
+
+        let mut cr_reviews = super::CRReviews::default();
+        cr_reviews.reviews.push(review("github:amitu", Approved, 1));
+        assert_eq!(
+            cr_reviews.merge_status(Some("github:arpita")),
+            Some(Approved)
+        );
+        // the author approving their own CR is no review
+        assert_eq!(cr_reviews.merge_status(Some("github:amitu")), None);
+
+        cr_reviews
+            .reviews
+            .push(review("github:arpita", ChangesRequested, 2));
+        assert_eq!(
+            cr_reviews.merge_status(Some("github:arpita")),
+            Some(ChangesRequested)
+        );
+    }
+
+    #[test]
+    fn get_cr_number_from_review_url() {
+        assert_eq!(
+            super::get_cr_number_from_review_url("/-/12/-/review/"),
+            Some(12)
+        );
+        assert_eq!(super::get_cr_number_from_review_url("/-/12/-/about/"), None);
+        assert_eq!(super::get_cr_number_from_review_url("/-/x/-/review/"), None);
+    }
+}
@@ -45,6 +45,9 @@ pub enum Error {
     #[error("UTF8Error: {}", _0)]
     UTF8Error(#[from] std::string::FromUtf8Error),
 
+    #[error("ImageError: {}", _0)]
+    ImageError(#[from] image::ImageError),
+
     #[error("ParseIntError: {}", _0)]
     ParseIntError(#[from] std::num::ParseIntError),
 
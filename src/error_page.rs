@@ -0,0 +1,115 @@
+const ERROR_PAGE_STATUS: &[actix_web::http::StatusCode] = &[
+    actix_web::http::StatusCode::NOT_FOUND,
+    actix_web::http::StatusCode::UNAUTHORIZED,
+    actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
+];
+
+/// `handle()` replaces the body of 404, 401 and 500 responses with the package error page.
+///
+/// If the package has no error page, the body is replaced with the status text, the actual
+/// error message is already logged by the `not_found!` etc macros and is not meant for readers.
+pub(crate) async fn handle(
+    config: &mut fpm::Config,
+    path: &str,
+    response: fpm::http::Response,
+) -> fpm::http::Response {
+    let status = response.status();
+    if !ERROR_PAGE_STATUS.contains(&status) || fpm::file::is_static(path).unwrap_or(false) {
+        return response;
+    }
+
+    match render(config, status, path, "/", false).await {
+        Ok(Some(body)) => actix_web::HttpResponse::build(status)
+            .content_type(mime_guess::mime::TEXT_HTML_UTF_8)
+            .body(body),
+        Ok(None) => actix_web::HttpResponse::build(status)
+            .body(status.canonical_reason().unwrap_or_default()),
+        Err(e) => {
+            fpm::warning!("failed to render {} page: {:?}", status.as_u16(), e);
+            actix_web::HttpResponse::build(status)
+                .body(status.canonical_reason().unwrap_or_default())
+        }
+    }
+}
+
+/// `build()` writes `.build/404.html`, the file static hosts serve for missing pages, if the
+/// package or its theme has a `404.ftd`.
+pub(crate) async fn build(config: &mut fpm::Config, base_url: &str) -> fpm::Result<()> {
+    if let Some(body) = render(
+        config,
+        actix_web::http::StatusCode::NOT_FOUND,
+        "",
+        base_url,
+        true,
+    )
+    .await?
+    {
+        fpm::utils::update(config.build_dir().join("404.html"), body.as_slice()).await?;
+    }
+    Ok(())
+}
+
+/// Error pages are regular ftd documents named after the status code, `404.ftd`, `401.ftd`
+/// and `500.ftd`. They are looked up in the package itself, and if not found there, in the
+/// dependency implementing the theme interface.
+///
+/// The error context is attached to the config as extra data, so the page can read it using
+/// `get-data` processor:
+///
+/// ```ftd
+/// -- integer status:
+/// $processor$: get-data
+///
+/// -- string path:
+/// $processor$: get-data
+/// ```
+///
+/// Available keys: `status`, `status-text` and `path`.
+async fn render(
+    config: &mut fpm::Config,
+    status: actix_web::http::StatusCode,
+    path: &str,
+    base_url: &str,
+    download_assets: bool,
+) -> fpm::Result<Option<Vec<u8>>> {
+    let document = match find(config, status.as_u16()).await {
+        Some(document) => document,
+        None => return Ok(None),
+    };
+
+    config.attach_data(serde_json::json!({
+        "status": status.as_u16(),
+        "status-text": status.canonical_reason().unwrap_or_default(),
+        "path": format!("/{}", path.trim_start_matches('/')),
+    }))?;
+    config.current_document = Some(document.id.to_string());
+    fpm::package::package_doc::read_ftd(config, &document, base_url, download_assets)
+        .await
+        .map(Some)
+}
+
+async fn find(config: &mut fpm::Config, status: u16) -> Option<fpm::Document> {
+    let id = format!("{}.ftd", status);
+    let path = config.root.join(id.as_str());
+    if path.is_file() {
+        if let Ok(fpm::File::Ftd(document)) =
+            fpm::get_file(config.package.name.to_string(), &path, &config.root).await
+        {
+            return Some(document);
+        }
+    }
+
+    let theme = config
+        .package
+        .get_dependency_for_interface(fpm::PACKAGE_THEME_INTERFACE)?
+        .package
+        .name
+        .to_string();
+    match config
+        .get_file_and_package_by_id(format!("-/{}/{}/", theme, status).as_str())
+        .await
+    {
+        Ok(fpm::File::Ftd(document)) => Some(document),
+        _ => None,
+    }
+}
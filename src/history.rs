@@ -66,7 +66,67 @@ impl FileOperation {
     }
 }
 
+pub(crate) const MAX_MESSAGE_LEN: usize = 4096;
+const MAX_AUTHOR_LEN: usize = 256;
+
+/// Empty message is same as no message. The message is stored as the body of `file-edit` in
+/// `history.ftd`, so a line starting with `--` would be read as a new section.
+pub(crate) fn clean_message(message: Option<&str>) -> fpm::Result<Option<String>> {
+    let message = match message.map(str::trim) {
+        Some(message) if !message.is_empty() => message,
+        _ => return Ok(None),
+    };
+    if message.len() > MAX_MESSAGE_LEN {
+        return fpm::usage_error(format!(
+            "sync message is too long, {} bytes, at most {} allowed",
+            message.len(),
+            MAX_MESSAGE_LEN
+        ));
+    }
+    if message.lines().any(|l| l.trim_start().starts_with("--")) {
+        return fpm::usage_error("lines of sync message can't start with `--`".to_string());
+    }
+    Ok(Some(message.to_string()))
+}
+
+/// The author is stored as `author` header of `file-edit` in `history.ftd`, so it has to be a
+/// single line.
+pub(crate) fn clean_author(author: Option<&str>) -> fpm::Result<Option<String>> {
+    let author = match author.map(str::trim) {
+        Some(author) if !author.is_empty() => author,
+        _ => return Ok(None),
+    };
+    if author.len() > MAX_AUTHOR_LEN || author.contains(|c| c == '\n' || c == '\r') {
+        return fpm::usage_error(format!(
+            "invalid author `{}`, it should be a single line of at most {} bytes",
+            author, MAX_AUTHOR_LEN
+        ));
+    }
+    Ok(Some(author.to_string()))
+}
+
 impl fpm::Config {
+    /// Author recorded in history for the edits synced from this package: `FPM_AUTHOR`
+    /// environment variable if set, else `author` in `.clone-state/config.json`.
+    pub(crate) async fn get_author(&self) -> fpm::Result<Option<String>> {
+        if let Ok(author) = std::env::var("FPM_AUTHOR") {
+            return clean_author(Some(author.as_str()));
+        }
+
+        #[derive(serde::Deserialize)]
+        struct CloneConfig {
+            author: Option<String>,
+        }
+
+        let path = self.clone_dir().join("config.json");
+        if !path.exists() {
+            return Ok(None);
+        }
+        let clone_config: CloneConfig =
+            serde_json::from_slice(tokio::fs::read(path).await?.as_slice())?;
+        clean_author(clone_config.author.as_deref())
+    }
+
     pub async fn get_history(&self) -> fpm::Result<Vec<FileHistory>> {
         let history_file_path = self.history_file();
         let history_content = tokio::fs::read_to_string(history_file_path).await?;
@@ -0,0 +1,565 @@
+//! `fpm gc` moves old versions of files out of the history folders, `.history/` and
+//! `.remote-state/history/`, into a packfile in `<history folder>/.packs/`.
+//!
+//! Every distinct content is stored once in the pack, keyed by its sha256, either in full or as
+//! a patch against the previous version of the same file, and compressed. The latest version
+//! of every file is kept as a loose file, so the common reads do not touch the pack.
+//!
+//! Code reading a version from history should use `read` or `read_to_string` with the same path
+//! as before, they fall back to the pack if the loose file is not there.
+
+const PACK_DIR: &str = ".packs";
+const INDEX_FILE: &str = "index.json";
+const INDEX_VERSION: u32 = 1;
+/// Longest chain of patches applied to read a version, keeps reading old versions cheap
+const MAX_DELTA_DEPTH: usize = 16;
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+struct Index {
+    version: u32,
+    /// path relative to the history folder, e.g. `foo/bar.3.ftd` => its content
+    files: std::collections::BTreeMap<String, FileEntry>,
+    /// content hash => where the content is stored
+    objects: std::collections::BTreeMap<String, Object>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+struct FileEntry {
+    hash: String,
+    /// seconds since unix epoch the loose file was last modified before it was packed
+    created: u64,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+struct Object {
+    pack: String,
+    offset: u64,
+    length: u64,
+    /// If present the stored data is a patch to apply on this content
+    base: Option<String>,
+}
+
+impl Default for Index {
+    fn default() -> Self {
+        Index {
+            version: INDEX_VERSION,
+            files: Default::default(),
+            objects: Default::default(),
+        }
+    }
+}
+
+impl Index {
+    fn read(history_dir: &camino::Utf8Path) -> fpm::Result<Option<Index>> {
+        let path = history_dir.join(PACK_DIR).join(INDEX_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let index: Index = serde_json::from_slice(std::fs::read(path)?.as_slice())?;
+        if index.version != INDEX_VERSION {
+            return Err(fpm::Error::GenericError(format!(
+                "{}: unsupported pack index version {}, expected {}",
+                history_dir, index.version, INDEX_VERSION
+            )));
+        }
+        Ok(Some(index))
+    }
+
+    fn read_object(&self, history_dir: &camino::Utf8Path, hash: &str) -> fpm::Result<Vec<u8>> {
+        use std::io::{Read, Seek};
+
+        let object = self.objects.get(hash).ok_or_else(|| {
+            fpm::Error::GenericError(format!(
+                "{}: object {} not found in pack",
+                history_dir, hash
+            ))
+        })?;
+        let mut pack = std::fs::File::open(history_dir.join(PACK_DIR).join(object.pack.as_str()))?;
+        pack.seek(std::io::SeekFrom::Start(object.offset))?;
+        let mut compressed = vec![0; object.length as usize];
+        pack.read_exact(compressed.as_mut_slice())?;
+        let mut data = vec![];
+        flate2::read::ZlibDecoder::new(compressed.as_slice()).read_to_end(&mut data)?;
+
+        let content = match object.base {
+            Some(ref base) => {
+                let base = String::from_utf8(self.read_object(history_dir, base)?)?;
+                let patch = String::from_utf8(data)?;
+                let patch = diffy::Patch::from_str(patch.as_str()).map_err(|e| {
+                    fpm::Error::GenericError(format!(
+                        "{}: invalid patch {}: {}",
+                        history_dir, hash, e
+                    ))
+                })?;
+                diffy::apply(base.as_str(), &patch)
+                    .map_err(|e| {
+                        fpm::Error::GenericError(format!(
+                            "{}: can't apply patch {}: {}",
+                            history_dir, hash, e
+                        ))
+                    })?
+                    .into_bytes()
+            }
+            None => data,
+        };
+        if content_hash(content.as_slice()) != hash {
+            return Err(fpm::Error::GenericError(format!(
+                "{}: pack is corrupt, content of {} does not match its hash",
+                history_dir, hash
+            )));
+        }
+        Ok(content)
+    }
+}
+
+pub(crate) fn content_hash(content: &[u8]) -> String {
+    use sha2::Digest;
+    format!("{:x}", sha2::Sha256::digest(content))
+}
+
+/// Reads a version of a file from history, `path` being the path of the loose file, e.g.
+/// `config.history_path(id, version)`.
+pub(crate) async fn read(path: &camino::Utf8Path) -> fpm::Result<Vec<u8>> {
+    let not_found = match tokio::fs::read(path).await {
+        Ok(content) => return Ok(content),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => e,
+        Err(e) => return Err(e.into()),
+    };
+
+    for history_dir in path.ancestors().skip(1) {
+        if let Some(index) = Index::read(history_dir)? {
+            let key = relative_path(history_dir, path)?;
+            return match index.files.get(key.as_str()) {
+                Some(entry) => index.read_object(history_dir, entry.hash.as_str()),
+                None => Err(not_found.into()),
+            };
+        }
+    }
+    Err(not_found.into())
+}
+
+pub(crate) async fn read_to_string(path: &camino::Utf8Path) -> fpm::Result<String> {
+    Ok(String::from_utf8(read(path).await?)?)
+}
+
+pub(crate) fn exists(path: &camino::Utf8Path) -> bool {
+    if path.exists() {
+        return true;
+    }
+    path.ancestors().skip(1).any(|history_dir| {
+        matches!(
+            (Index::read(history_dir), relative_path(history_dir, path)),
+            (Ok(Some(index)), Ok(key)) if index.files.contains_key(key.as_str())
+        )
+    })
+}
+
+/// Paths, relative to `history_dir`, of the versions moved to the pack, to be listed along with
+/// the loose files.
+pub(crate) fn packed_files(history_dir: &camino::Utf8Path) -> fpm::Result<Vec<String>> {
+    Ok(Index::read(history_dir)?
+        .map(|index| index.files.into_keys().collect())
+        .unwrap_or_default())
+}
+
+fn relative_path(history_dir: &camino::Utf8Path, path: &camino::Utf8Path) -> fpm::Result<String> {
+    Ok(path
+        .strip_prefix(history_dir)?
+        .as_str()
+        .replace(std::path::MAIN_SEPARATOR, "/"))
+}
+
+/// `foo/bar.3.ftd` => (`foo/bar.ftd`, 3), versions in history are named by `snapshot_id`
+fn split_version(path: &str) -> Option<(String, u128)> {
+    static VERSIONED: once_cell::sync::Lazy<regex::Regex> = once_cell::sync::Lazy::new(|| {
+        regex::Regex::new(r"^(?P<name>.+?)\.(?P<version>[0-9]+)(?P<ext>\.[^./]+)?$").unwrap()
+    });
+
+    let captures = VERSIONED.captures(path)?;
+    Some((
+        format!(
+            "{}{}",
+            &captures["name"],
+            captures.name("ext").map(|e| e.as_str()).unwrap_or_default()
+        ),
+        captures["version"].parse().ok()?,
+    ))
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct GcStats {
+    pub packed: usize,
+    pub deduplicated: usize,
+    pub pruned: usize,
+    pub bytes_before: u64,
+    pub bytes_after: u64,
+}
+
+/// What `fpm gc --prune-older-than` removes from a history folder whose versions are named by the
+/// timestamp they were written at, like `.history/`
+pub(crate) struct Prune {
+    /// nanoseconds since unix epoch, versions written before are removed
+    pub before: u128,
+    /// versions, relative to the history folder, which are still read, e.g. by `.tracks`, they
+    /// are kept however old they are
+    pub referenced: std::collections::HashSet<String>,
+}
+
+enum Source {
+    Loose(camino::Utf8PathBuf),
+    Packed(String),
+}
+
+/// Packs all but the latest version of every file in `history_dir`, the versions `prune` allows
+/// are removed instead. The age of a version is the timestamp in its name, not the time of the
+/// file which changes when it is copied or checked out.
+pub(crate) async fn gc(
+    history_dir: &camino::Utf8Path,
+    prune: Option<&Prune>,
+) -> fpm::Result<GcStats> {
+    use itertools::Itertools;
+
+    let mut stats = GcStats::default();
+    if !history_dir.exists() {
+        return Ok(stats);
+    }
+    let old_index = Index::read(history_dir)?;
+    let pack_dir = history_dir.join(PACK_DIR);
+
+    // path => (source, created), latest version of every file stays loose
+    let mut versions: std::collections::BTreeMap<String, (Source, u64)> = Default::default();
+    if let Some(ref index) = old_index {
+        for (path, entry) in index.files.iter() {
+            versions.insert(
+                path.to_string(),
+                (Source::Packed(entry.hash.to_string()), entry.created),
+            );
+        }
+    }
+    for entry in ignore::WalkBuilder::new(history_dir)
+        .hidden(false)
+        .git_ignore(false)
+        .build()
+    {
+        let path = camino::Utf8PathBuf::from_path_buf(entry?.into_path())
+            .map_err(|p| fpm::Error::GenericError(format!("non utf8 path: {:?}", p)))?;
+        if !path.is_file() || path.starts_with(&pack_dir) {
+            continue;
+        }
+        let key = relative_path(history_dir, &path)?;
+        if split_version(key.as_str()).is_none() {
+            // not a version of a file, e.g. `.latest.ftd`
+            continue;
+        }
+        let metadata = tokio::fs::metadata(&path).await?;
+        stats.bytes_before += metadata.len();
+        let created = metadata
+            .modified()?
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        versions.insert(key, (Source::Loose(path), created));
+    }
+    if let Ok(mut entries) = tokio::fs::read_dir(&pack_dir).await {
+        while let Some(entry) = entries.next_entry().await? {
+            stats.bytes_before += entry.metadata().await?.len();
+        }
+    }
+
+    let groups = versions
+        .keys()
+        .filter_map(|k| split_version(k).map(|(name, version)| (name, (version, k.to_string()))))
+        .into_group_map();
+
+    let mut pack: Vec<u8> = vec![];
+    let pack_name = format!("{}.pack", fpm::timestamp_nanosecond());
+    let mut index = Index::default();
+    let mut depth: std::collections::HashMap<String, usize> = Default::default();
+    let mut to_remove: Vec<camino::Utf8PathBuf> = vec![];
+
+    for (_, mut group) in groups.into_iter().sorted_by(|a, b| a.0.cmp(&b.0)) {
+        group.sort();
+        let latest = group.pop();
+        let mut previous: Option<(String, String)> = None;
+        for (version, key) in group.into_iter() {
+            let (source, created) = versions.remove(key.as_str()).unwrap();
+            if matches!(prune, Some(prune) if version < prune.before && !prune.referenced.contains(&key))
+            {
+                stats.pruned += 1;
+                if let Source::Loose(path) = source {
+                    to_remove.push(path);
+                }
+                continue;
+            }
+
+            let content = match source {
+                Source::Loose(ref path) => tokio::fs::read(path).await?,
+                Source::Packed(ref hash) => match old_index {
+                    Some(ref old_index) => old_index.read_object(history_dir, hash)?,
+                    None => unreachable!("packed version without index"),
+                },
+            };
+            let hash = content_hash(content.as_slice());
+            if let Source::Loose(path) = source {
+                to_remove.push(path);
+            }
+            index.files.insert(
+                key,
+                FileEntry {
+                    hash: hash.to_string(),
+                    created,
+                },
+            );
+            stats.packed += 1;
+            if index.objects.contains_key(hash.as_str()) {
+                stats.deduplicated += 1;
+                continue;
+            }
+
+            let text = String::from_utf8(content).map_err(|e| e.into_bytes());
+            let delta = match (&previous, &text) {
+                (Some((base_hash, base)), Ok(text))
+                    if depth.get(base_hash).copied().unwrap_or_default() < MAX_DELTA_DEPTH =>
+                {
+                    let patch = diffy::create_patch(base.as_str(), text.as_str()).to_string();
+                    // only keep patches which are smaller and reproduce the content exactly
+                    let round_trip = diffy::Patch::from_str(patch.as_str())
+                        .ok()
+                        .and_then(|p| diffy::apply(base.as_str(), &p).ok());
+                    if patch.len() < text.len() && round_trip.as_deref() == Some(text.as_str()) {
+                        Some((base_hash.to_string(), patch))
+                    } else {
+                        None
+                    }
+                }
+                _ => None,
+            };
+            let (data, base) = match (delta, &text) {
+                (Some((base_hash, patch)), _) => {
+                    depth.insert(
+                        hash.to_string(),
+                        depth.get(base_hash.as_str()).copied().unwrap_or_default() + 1,
+                    );
+                    (patch.into_bytes(), Some(base_hash))
+                }
+                (None, Ok(text)) => (text.as_bytes().to_vec(), None),
+                (None, Err(bytes)) => (bytes.clone(), None),
+            };
+            let compressed = compress(data.as_slice())?;
+            index.objects.insert(
+                hash.to_string(),
+                Object {
+                    pack: pack_name.to_string(),
+                    offset: pack.len() as u64,
+                    length: compressed.len() as u64,
+                    base,
+                },
+            );
+            pack.extend(compressed);
+            previous = text.ok().map(|text| (hash, text));
+        }
+        if let Some((_, key)) = latest {
+            // latest version is usually loose, but it may be packed if newer loose version was
+            // removed by hand, keep it where it is
+            if let Some((Source::Packed(hash), created)) = versions.get(key.as_str()) {
+                let entry = FileEntry {
+                    hash: hash.to_string(),
+                    created: *created,
+                };
+                versions.remove(key.as_str());
+                copy_object(
+                    old_index.as_ref(),
+                    history_dir,
+                    &mut index,
+                    &mut pack,
+                    pack_name.as_str(),
+                    entry.hash.as_str(),
+                )?;
+                index.files.insert(key, entry);
+            }
+        }
+    }
+
+    tokio::fs::create_dir_all(&pack_dir).await?;
+    if !index.files.is_empty() {
+        tokio::fs::write(pack_dir.join(pack_name.as_str()), pack.as_slice()).await?;
+    }
+    // index is replaced atomically, readers see either the old or the new pack
+    let index_tmp = pack_dir.join(format!("{}.tmp", INDEX_FILE));
+    tokio::fs::write(&index_tmp, serde_json::to_vec(&index)?).await?;
+    tokio::fs::rename(&index_tmp, pack_dir.join(INDEX_FILE)).await?;
+
+    let mut entries = tokio::fs::read_dir(&pack_dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name.ends_with(".pack") && !name.eq(&pack_name) {
+            tokio::fs::remove_file(entry.path()).await?;
+        } else {
+            stats.bytes_after += entry.metadata().await?.len();
+        }
+    }
+    for path in to_remove {
+        tokio::fs::remove_file(path).await?;
+    }
+    for (source, _) in versions.into_values() {
+        if let Source::Loose(path) = source {
+            stats.bytes_after += tokio::fs::metadata(path).await?.len();
+        }
+    }
+    Ok(stats)
+}
+
+/// Copies the content `hash` from the old pack into the new one, in full
+fn copy_object(
+    old_index: Option<&Index>,
+    history_dir: &camino::Utf8Path,
+    index: &mut Index,
+    pack: &mut Vec<u8>,
+    pack_name: &str,
+    hash: &str,
+) -> fpm::Result<()> {
+    if index.objects.contains_key(hash) {
+        return Ok(());
+    }
+    let content = match old_index {
+        Some(old_index) => old_index.read_object(history_dir, hash)?,
+        None => unreachable!("packed version without index"),
+    };
+    let compressed = compress(content.as_slice())?;
+    index.objects.insert(
+        hash.to_string(),
+        Object {
+            pack: pack_name.to_string(),
+            offset: pack.len() as u64,
+            length: compressed.len() as u64,
+            base: None,
+        },
+    );
+    pack.extend(compressed);
+    Ok(())
+}
+
+fn compress(data: &[u8]) -> fpm::Result<Vec<u8>> {
+    use std::io::Write;
+
+    let mut encoder = flate2::write::ZlibEncoder::new(vec![], flate2::Compression::best());
+    encoder.write_all(data)?;
+    Ok(encoder.finish()?)
+}
+
+#[cfg(test)]
+mod tests {
+    fn history_dir(name: &str) -> fpm::test_utils::TempPackage {
+        let package = fpm::test_utils::TempPackage::empty(format!("gc-{}", name).as_str());
+        for (path, content) in [
+            ("index.100.ftd", "-- ftd.text: hello\n"),
+            ("index.200.ftd", "-- ftd.text: hello world\n"),
+            (
+                "index.300.ftd",
+                "-- ftd.text: hello world\n\n-- ftd.text: bye\n",
+            ),
+            ("blog/post.100.ftd", "-- ftd.text: hello\n"),
+            ("blog/post.200.ftd", "-- ftd.text: post\n"),
+            (".latest.ftd", "-- import: fpm\n"),
+        ] {
+            package.write(path, content);
+        }
+        package
+    }
+
+    #[test]
+    fn gc() {
+        fpm::test_utils::block_on(async {
+            let package = history_dir("pack");
+            let dir = &package.root;
+            let stats = super::gc(dir, None).await.unwrap();
+            assert_eq!(stats.packed, 3);
+            // `blog/post.100.ftd` has the content of `index.100.ftd`
+            assert_eq!(stats.deduplicated, 1);
+            assert_eq!(stats.pruned, 0);
+
+            for path in ["index.100.ftd", "index.200.ftd", "blog/post.100.ftd"] {
+                assert!(!dir.join(path).exists());
+                assert!(super::exists(&dir.join(path)));
+            }
+            assert!(dir.join("index.300.ftd").exists());
+            assert!(dir.join("blog/post.200.ftd").exists());
+            assert!(dir.join(".latest.ftd").exists());
+            assert_eq!(
+                super::read_to_string(&dir.join("index.100.ftd"))
+                    .await
+                    .unwrap(),
+                "-- ftd.text: hello\n"
+            );
+            assert_eq!(
+                super::read_to_string(&dir.join("index.200.ftd"))
+                    .await
+                    .unwrap(),
+                "-- ftd.text: hello world\n"
+            );
+
+            // gc again reads the versions back from the pack
+            let stats = super::gc(dir, None).await.unwrap();
+            assert_eq!(stats.packed, 3);
+            assert_eq!(
+                super::read_to_string(&dir.join("blog/post.100.ftd"))
+                    .await
+                    .unwrap(),
+                "-- ftd.text: hello\n"
+            );
+        });
+    }
+
+    #[test]
+    fn gc_prune() {
+        fpm::test_utils::block_on(async {
+            let package = history_dir("prune");
+            let dir = &package.root;
+            // the file times say nothing, only the timestamps in the names count
+            let prune = super::Prune {
+                before: 250,
+                referenced: std::iter::once("index.100.ftd".to_string()).collect(),
+            };
+            let stats = super::gc(dir, Some(&prune)).await.unwrap();
+            assert_eq!(stats.pruned, 2);
+            assert_eq!(stats.packed, 1);
+
+            assert!(!super::exists(&dir.join("index.200.ftd")));
+            assert!(!super::exists(&dir.join("blog/post.100.ftd")));
+            assert_eq!(
+                super::read_to_string(&dir.join("index.100.ftd"))
+                    .await
+                    .unwrap(),
+                "-- ftd.text: hello\n"
+            );
+            // the latest version is kept however old it is
+            assert!(dir.join("index.300.ftd").exists());
+            assert!(dir.join("blog/post.200.ftd").exists());
+
+            let prune = super::Prune {
+                before: 150,
+                referenced: Default::default(),
+            };
+            let stats = super::gc(dir, Some(&prune)).await.unwrap();
+            assert_eq!(stats.pruned, 1);
+            assert!(!super::exists(&dir.join("index.100.ftd")));
+        });
+    }
+
+    #[test]
+    fn split_version() {
+        assert_eq!(
+            super::split_version("foo/bar.3.ftd"),
+            Some(("foo/bar.ftd".to_string(), 3))
+        );
+        assert_eq!(
+            super::split_version("-/1/a.b.1666174931000000000.md"),
+            Some(("-/1/a.b.md".to_string(), 1666174931000000000))
+        );
+        assert_eq!(
+            super::split_version("LICENSE.2"),
+            Some(("LICENSE".to_string(), 2))
+        );
+        assert_eq!(super::split_version(".latest.ftd"), None);
+    }
+}
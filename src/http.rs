@@ -181,6 +181,15 @@ impl Request {
         )
     }
 
+    /// Request with `cookies` added, they replace the cookies of the same name
+    pub(crate) fn with_cookies(
+        mut self,
+        cookies: std::collections::HashMap<String, String>,
+    ) -> Self {
+        self.cookies.extend(cookies);
+        self
+    }
+
     pub fn cookie(&self, name: &str) -> Option<String> {
         self.cookies().get(name).map(|v| v.to_string())
     }
@@ -311,6 +320,7 @@ pub(crate) async fn get_json<T: serde::de::DeserializeOwned>(url: &str) -> fpm::
         .get(url)
         .header(reqwest::header::CONTENT_TYPE, "application/json")
         .header(reqwest::header::USER_AGENT, "fpm")
+        .headers(authorization(url).await?)
         .send()
         .await?
         .json::<T>()
@@ -325,6 +335,7 @@ pub(crate) async fn post_json<T: serde::de::DeserializeOwned, B: Into<reqwest::B
         .post(url)
         .header(reqwest::header::CONTENT_TYPE, "application/json")
         .header(reqwest::header::USER_AGENT, "fpm")
+        .headers(authorization(url).await?)
         .body(body)
         .send()
         .await?
@@ -332,6 +343,44 @@ pub(crate) async fn post_json<T: serde::de::DeserializeOwned, B: Into<reqwest::B
         .await?)
 }
 
+/// Posts `body` as `content_type`, returns the content type and the body of the response
+pub(crate) async fn post_bytes(
+    url: &str,
+    content_type: &str,
+    body: Vec<u8>,
+) -> fpm::Result<(Option<String>, Vec<u8>)> {
+    let response = reqwest::Client::new()
+        .post(url)
+        .header(reqwest::header::CONTENT_TYPE, content_type)
+        .header(reqwest::header::USER_AGENT, "fpm")
+        .headers(authorization(url).await?)
+        .body(body)
+        .send()
+        .await?;
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.to_string());
+    Ok((content_type, response.bytes().await?.to_vec()))
+}
+
+/// `Authorization` header with the token `fpm login` saved for the server of `url`, if any
+async fn authorization(url: &str) -> fpm::Result<reqwest::header::HeaderMap> {
+    let mut headers = reqwest::header::HeaderMap::new();
+    if let Some(token) = fpm::commands::login::token_for(url).await? {
+        headers.insert(
+            reqwest::header::AUTHORIZATION,
+            format!("Bearer {}", token)
+                .parse()
+                .map_err(|_| fpm::Error::UsageError {
+                    message: "Invalid token, run `fpm login` again".to_string(),
+                })?,
+        );
+    }
+    Ok(headers)
+}
+
 pub(crate) async fn http_get(url: &str) -> fpm::Result<Vec<u8>> {
     http_get_with_cookie(url, None, &std::collections::HashMap::new()).await
 }
@@ -0,0 +1,234 @@
+//! Message catalogs of the package, `i18n/<language code>/*.ftl` in the package root, used by
+//! `$processor$: i18n`. A message is looked up in the language of the package, then in the
+//! language of the original package of a translation package, then in English. The catalogs of
+//! the original package are used for the messages the translation package does not have.
+
+/// (path, modified time) of every `.ftl` file of a bundle, the bundle is read again when one of
+/// them changes
+type Fingerprint = Vec<(std::path::PathBuf, Option<std::time::SystemTime>)>;
+
+/// The last bundle read of every (package root, language code), `None` if the package has no
+/// catalogs in the language
+static BUNDLES: once_cell::sync::Lazy<
+    antidote::Mutex<
+        std::collections::HashMap<
+            (camino::Utf8PathBuf, String),
+            (Fingerprint, Option<std::sync::Arc<super::Bundle>>),
+        >,
+    >,
+> = once_cell::sync::Lazy::new(Default::default);
+
+/// `key`, or its `attribute`, formatted with `args`
+pub(crate) fn lookup(
+    config: &fpm::Config,
+    key: &str,
+    attribute: Option<&str>,
+    args: &fluent::FluentArgs,
+) -> fpm::Result<String> {
+    let mut roots = vec![config.root.clone()];
+    if let Ok(original_path) = config.original_path() {
+        roots.push(original_path);
+    }
+
+    let mut languages = vec![];
+    let package_languages = [
+        config.package.language.as_ref(),
+        config
+            .package
+            .translation_of
+            .as_ref()
+            .as_ref()
+            .and_then(|v| v.language.as_ref()),
+    ];
+    for language in package_languages.into_iter().flatten() {
+        match realm_lang::Language::from_2_letter_code(language) {
+            Ok(language) if !languages.contains(&language) => languages.push(language),
+            Ok(_) => {}
+            Err(_) => {
+                fpm::warning!("i18n: unknown language `{}`", language);
+            }
+        }
+    }
+    if !languages.contains(&realm_lang::Language::English) {
+        languages.push(realm_lang::Language::English);
+    }
+
+    for language in languages.iter() {
+        for root in roots.iter() {
+            let bundle = match cached_bundle(root, language)? {
+                Some(bundle) => bundle,
+                None => continue,
+            };
+            if let Some(message) = format(&bundle, key, attribute, args) {
+                return message;
+            }
+        }
+    }
+    Err(fpm::Error::UsageError {
+        message: format!(
+            "i18n: no message `{}` in i18n/{}/*.ftl",
+            match attribute {
+                Some(attribute) => format!("{}.{}", key, attribute),
+                None => key.to_string(),
+            },
+            languages
+                .iter()
+                .map(|v| v.to_2_letter_code())
+                .collect::<Vec<_>>()
+                .join("|")
+        ),
+    })
+}
+
+/// The bundle of `root` in `language`, the catalogs are only read again if one of them is added,
+/// removed or modified, so `fpm serve` shows the edits without parsing them on every request
+fn cached_bundle(
+    root: &camino::Utf8Path,
+    language: &realm_lang::Language,
+) -> fpm::Result<Option<std::sync::Arc<super::Bundle>>> {
+    let dir = root.join("i18n").join(language.to_2_letter_code());
+    let fingerprint: Fingerprint = ftl_files(&dir)?
+        .into_iter()
+        .map(|v| {
+            let modified = std::fs::metadata(&v).and_then(|m| m.modified()).ok();
+            (v, modified)
+        })
+        .collect();
+    let key = (root.to_path_buf(), language.to_2_letter_code().to_string());
+    if let Some((cached, bundle)) = BUNDLES.lock().get(&key) {
+        if *cached == fingerprint {
+            return Ok(bundle.clone());
+        }
+    }
+    let bundle = read_bundle(&dir, language)?.map(std::sync::Arc::new);
+    BUNDLES.lock().insert(key, (fingerprint, bundle.clone()));
+    Ok(bundle)
+}
+
+/// The `.ftl` files in `dir`, by path
+fn ftl_files(dir: &camino::Utf8Path) -> fpm::Result<Vec<std::path::PathBuf>> {
+    if !dir.is_dir() {
+        return Ok(vec![]);
+    }
+    let mut files = vec![];
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().map(|v| v == "ftl").unwrap_or(false) {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// All the `.ftl` files of `dir`, `i18n/<language code>/` of a package, in one bundle
+fn read_bundle(
+    dir: &camino::Utf8Path,
+    language: &realm_lang::Language,
+) -> fpm::Result<Option<super::Bundle>> {
+    let files = ftl_files(dir)?;
+    if files.is_empty() {
+        return Ok(None);
+    }
+    let mut contents = vec![];
+    for path in files {
+        contents.push((
+            path.to_string_lossy().to_string(),
+            std::fs::read_to_string(&path)?,
+        ));
+    }
+    bundle(language, contents).map(Some)
+}
+
+fn bundle(
+    language: &realm_lang::Language,
+    files: Vec<(String, String)>,
+) -> fpm::Result<super::Bundle> {
+    let mut bundle = fluent::bundle::FluentBundle::new_concurrent(vec![language
+        .to_2_letter_code()
+        .parse()
+        .map_err(|e| fpm::Error::GenericError(format!("i18n: {:?}", e)))?]);
+    // the messages are put in ftd documents, not in text with mixed directions
+    bundle.set_use_isolating(false);
+    for (path, content) in files {
+        let resource = fluent::FluentResource::try_new(content).map_err(|(_, errors)| {
+            fpm::Error::UsageError {
+                message: format!("i18n: {}: {:?}", path, errors),
+            }
+        })?;
+        bundle
+            .add_resource(resource)
+            .map_err(|errors| fpm::Error::UsageError {
+                message: format!("i18n: {}: {:?}", path, errors),
+            })?;
+    }
+    Ok(bundle)
+}
+
+/// `None` if the bundle does not have the message
+fn format(
+    bundle: &super::Bundle,
+    key: &str,
+    attribute: Option<&str>,
+    args: &fluent::FluentArgs,
+) -> Option<fpm::Result<String>> {
+    let message = bundle.get_message(key)?;
+    let pattern = match attribute {
+        Some(attribute) => message.get_attribute(attribute)?.value(),
+        None => message.value()?,
+    };
+    let mut errors = vec![];
+    let formatted = bundle.format_pattern(pattern, Some(args), &mut errors);
+    if !errors.is_empty() {
+        return Some(Err(fpm::Error::UsageError {
+            message: format!("i18n: `{}`: {:?}", key, errors),
+        }));
+    }
+    Some(Ok(formatted.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn format() {
+        let bundle = super::bundle(
+            &realm_lang::Language::English,
+            vec![(
+                "i18n/en/cart.ftl".to_string(),
+                indoc::indoc! {"
+                    items = { $count ->
+                        [one] One item in { $name }'s cart
+                       *[other] { $count } items in { $name }'s cart
+                    }
+                        .title = Cart
+                "}
+                .to_string(),
+            )],
+        )
+        .unwrap();
+
+        let mut args = fluent::FluentArgs::new();
+        args.set("count", fluent::FluentValue::from(1));
+        args.set("name", fluent::FluentValue::from("Arpita"));
+        assert_eq!(
+            super::format(&bundle, "items", None, &args)
+                .unwrap()
+                .unwrap(),
+            "One item in Arpita's cart"
+        );
+        args.set("count", fluent::FluentValue::from(3));
+        assert_eq!(
+            super::format(&bundle, "items", None, &args)
+                .unwrap()
+                .unwrap(),
+            "3 items in Arpita's cart"
+        );
+        assert_eq!(
+            super::format(&bundle, "items", Some("title"), &args)
+                .unwrap()
+                .unwrap(),
+            "Cart"
+        );
+        assert!(super::format(&bundle, "missing", None, &args).is_none());
+    }
+}
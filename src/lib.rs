@@ -5,6 +5,7 @@ pub mod utils;
 
 // Temp comment
 mod apis;
+mod assets;
 mod auto_import;
 mod cache;
 pub mod commands;
@@ -15,22 +16,29 @@ mod doc;
 mod file;
 mod font;
 mod history;
+mod history_store;
 mod package;
 #[macro_use]
 mod http;
 mod auth;
 mod ds;
 mod error;
+mod error_page;
 mod i18n;
 pub mod library;
 mod proxy;
 mod render;
+mod responsive_image;
 pub mod sitemap;
 mod snapshot;
 mod sync_utils;
+#[cfg(test)]
+mod test_utils;
+pub mod tls;
 mod track;
 mod tracker;
 mod translation;
+mod translation_exchange;
 mod version;
 // mod wasm;
 mod workspace;
@@ -38,8 +46,8 @@ mod workspace;
 pub(crate) use auto_import::AutoImport;
 pub use commands::{
     abort_merge::abort_merge, add::add, build::build, clone::clone, close_cr::close_cr,
-    create_cr::create_cr, create_package::create_package, diff::diff, edit::edit,
-    mark_resolved::mark_resolved, mark_upto_date::mark_upto_date, merge::merge,
+    create_cr::create_cr, create_package::create_package, diff::diff, diff::diff_versions,
+    edit::edit, mark_resolved::mark_resolved, mark_upto_date::mark_upto_date, merge::merge,
     resolve_conflict::resolve_conflict, revert::revert, rm::rm, serve::listen,
     start_tracking::start_tracking, status::status, sync2::sync2,
     translation_status::translation_status, update::update,
@@ -266,6 +274,83 @@ fn package_info_create_cr(config: &fpm::Config) -> fpm::Result<String> {
     })
 }
 
+fn package_info_cr_review(
+    config: &fpm::Config,
+    cr_meta: &fpm::cr::CRMeta,
+    cr_reviews: &fpm::cr::CRReviews,
+    cr_diff: &[fpm::cr::CRFileDiff],
+) -> fpm::Result<String> {
+    let package_info_package = match config
+        .package
+        .get_dependency_for_interface(fpm::FPM_UI_INTERFACE)
+        .or_else(|| {
+            config
+                .package
+                .get_dependency_for_interface(fpm::PACKAGE_THEME_INTERFACE)
+        }) {
+        Some(dep) => dep.package.name.as_str(),
+        None => fpm::FPM_UI_INTERFACE,
+    };
+    let body_prefix = match config.package.generate_prefix_string(false) {
+        Some(bp) => bp,
+        None => String::new(),
+    };
+    let status = if !cr_meta.open {
+        "closed"
+    } else {
+        cr_reviews
+            .status()
+            .map(|v| v.as_str())
+            .unwrap_or("not reviewed")
+    };
+    let mut cr_review_ftd = indoc::formatdoc! {"
+            {body_prefix}
+    
+            -- import: {package_info_package}/cr-review as pi
+
+            -- pi.cr-review: {title}
+            cr-number: {cr_number}
+            status: {status}
+        ",
+        body_prefix = body_prefix,
+        package_info_package = package_info_package,
+        title = cr_meta.title,
+        cr_number = cr_meta.cr_number,
+        status = status,
+    };
+    for review in cr_reviews.latest().values() {
+        cr_review_ftd = format!(
+            "{}\n\n-- pi.review: {}\nstatus: {}\ndate: {}\n\n{}\n",
+            cr_review_ftd,
+            review.author,
+            review.status.as_str(),
+            fpm::commands::log::format_timestamp(review.timestamp),
+            fpm::utils::escape_ftd(review.message.as_deref().unwrap_or_default())
+        );
+    }
+    for file_diff in cr_diff {
+        cr_review_ftd = format!(
+            "{}\n\n-- pi.file-diff: {}\noperation: {:?}\n\n{}\n",
+            cr_review_ftd,
+            file_diff.filename,
+            file_diff.operation,
+            fpm::utils::escape_ftd(file_diff.diff.as_deref().unwrap_or("Binary file"))
+        );
+    }
+    for comment in cr_reviews.comments.iter() {
+        cr_review_ftd = format!(
+            "{}\n\n-- pi.comment: {}\nfilename: {}\nline: {}\ndate: {}\n\n{}\n",
+            cr_review_ftd,
+            comment.author,
+            comment.filename,
+            comment.line,
+            fpm::commands::log::format_timestamp(comment.timestamp),
+            fpm::utils::escape_ftd(comment.message.as_str())
+        );
+    }
+    Ok(cr_review_ftd)
+}
+
 fn package_info_code(
     config: &fpm::Config,
     file_name: &str,
@@ -704,17 +704,21 @@ pub(crate) async fn get(lib: &fpm::Library) -> String {
         };
 
         for lang_package in other_language_packages {
-            let language = if let Some(ref lang) = lang_package.language {
-                fpm::utils::language_to_human(lang)
+            let (language, code) = if let Some(ref lang) = lang_package.language {
+                (fpm::utils::language_to_human(lang), lang)
             } else {
                 continue;
             };
 
-            let domain = if lang_package.name.ends_with('/') {
+            let mut domain = if lang_package.name.ends_with('/') {
                 format!("https://{}{}", lang_package.name, doc_id)
             } else {
                 format!("https://{}/{}", lang_package.name, doc_id)
             };
+            // the original package sends readers to their language unless they pick one
+            if lang_package.translation_of.is_none() && lang_package.translations.has_elements() {
+                domain = format!("{}?{}={}", domain, fpm::package::languages::COOKIE, code);
+            }
 
             languages = format!(
                 indoc::indoc! {"
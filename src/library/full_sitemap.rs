@@ -51,7 +51,7 @@ struct SectionCompat {
     writers: Vec<String>,
 }
 
-#[derive(Default, Debug, serde::Serialize)]
+#[derive(Default, Debug, Clone, PartialEq, serde::Serialize)]
 pub struct KeyValueData {
     pub key: String,
     pub value: String,
@@ -96,7 +96,9 @@ pub fn request_data_processor<'a>(
 ) -> ftd::p1::Result<ftd::Value> {
     // TODO: URL params not yet handled
     let req = match config.request.as_ref() {
-        Some(v) => v,
+        Some(v) => Some(v),
+        // `fpm build` renders the dynamic urls with only the path parameters
+        None if !config.path_parameters.is_empty() => None,
         None => {
             return ftd::p2::utils::e2(
                 "HttpRequest object should not be null",
@@ -105,7 +107,7 @@ pub fn request_data_processor<'a>(
             )
         }
     };
-    let mut data = req.query().clone();
+    let mut data = req.map(|v| v.query().clone()).unwrap_or_default();
 
     let mut path_parameters = std::collections::HashMap::new();
     for (name, value) in config.path_parameters.iter() {
@@ -119,7 +121,7 @@ pub fn request_data_processor<'a>(
 
     data.extend(path_parameters);
 
-    match req.body_as_json() {
+    match req.map(|v| v.body_as_json()).unwrap_or(Ok(None)) {
         Ok(Some(b)) => {
             data.extend(b);
         }
@@ -0,0 +1,60 @@
+/// Message of the package catalogs, `i18n/<language code>/*.ftl`, in the language of the package.
+/// The headers other than `key` and `attribute` are the arguments of the message, `integer` and
+/// `decimal` variables are passed as numbers so plural rules apply, everything else is text.
+///
+/// ```ftd
+/// -- string cart-title:
+/// $processor$: i18n
+/// key: cart-items
+/// count: $cart.count
+/// ```
+pub fn processor<'a>(
+    section: &ftd::p1::Section,
+    doc: &ftd::p2::TDoc<'a>,
+    config: &fpm::Config,
+) -> ftd::p1::Result<ftd::Value> {
+    let key = section
+        .header
+        .str(doc.name, section.line_number, "key")?
+        .to_string();
+    let attribute = section
+        .header
+        .string_optional(doc.name, section.line_number, "attribute")?;
+
+    let mut args = fluent::FluentArgs::new();
+    for (line, name, value) in section.header.0.iter() {
+        if name == "$processor$" || name == "key" || name == "attribute" {
+            continue;
+        }
+        if !value.starts_with('$') {
+            args.set(
+                name.to_string(),
+                fluent::FluentValue::from(value.to_string()),
+            );
+            continue;
+        }
+        match doc.get_value(*line, value)? {
+            ftd::Value::Integer { value } => {
+                args.set(name.to_string(), fluent::FluentValue::from(value))
+            }
+            ftd::Value::Decimal { value } => {
+                args.set(name.to_string(), fluent::FluentValue::from(value))
+            }
+            value => match value.to_string() {
+                Some(value) => args.set(name.to_string(), fluent::FluentValue::from(value)),
+                None => continue,
+            },
+        }
+    }
+
+    let text = fpm::i18n::package::lookup(config, key.as_str(), attribute.as_deref(), &args)
+        .map_err(|e| ftd::p1::Error::ParseError {
+            message: e.to_string(),
+            doc_id: doc.name.to_string(),
+            line_number: section.line_number,
+        })?;
+    Ok(ftd::Value::String {
+        text,
+        source: ftd::TextSource::Default,
+    })
+}
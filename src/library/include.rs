@@ -1,3 +1,16 @@
+/// Code of a file of the package, or of a dependency with `-/<package>/<path>`, for `ftd.code`.
+/// `path` selects the lines by range or `ANCHOR:` comments, `symbol` selects the definition of a
+/// function, class etc. by name, `Type.method` selects one inside another. The included lines
+/// are dedented. `highlight: 2-4,7` is checked against the included lines and passed on as is,
+/// for code components that take it.
+///
+/// ```ftd
+/// -- ftd.code:
+/// $processor$: include
+/// $path$: -/fifthtry.github.io/amitu/code/main.rs
+/// symbol: Guess.read
+/// highlight: 2-3
+/// ```
 pub fn processor(
     section: &ftd::p1::Section,
     doc: &ftd::p2::TDoc,
@@ -24,9 +37,43 @@ pub fn processor(
             }
         }
     };
+    let symbol = section
+        .header
+        .str_optional(doc.name, section.line_number, "symbol")?;
+    let highlight = section
+        .header
+        .str_optional(doc.name, section.line_number, "highlight")?;
+    let error = |e: ParseError| ftd::p1::Error::ParseError {
+        message: format!("include: {}", e),
+        doc_id: doc.name.to_string(),
+        line_number: section.line_number,
+    };
+
     let mut v: ftd::Map<ftd::PropertyValue> = Default::default();
 
-    let code_item = IncludeCode::parse(doc_path, config).unwrap();
+    let code_item = IncludeCode::parse(doc_path, symbol, config).map_err(error)?;
+    if let Some(highlight) = highlight {
+        let ranges = parse_highlight(highlight, code_item.body.lines().count()).map_err(error)?;
+        v.insert(
+            "highlight".to_string(),
+            ftd::PropertyValue::Value {
+                value: ftd::Value::String {
+                    text: ranges
+                        .iter()
+                        .map(|(start, end)| {
+                            if start == end {
+                                start.to_string()
+                            } else {
+                                format!("{}-{}", start, end)
+                            }
+                        })
+                        .collect::<Vec<String>>()
+                        .join(","),
+                    source: ftd::TextSource::Header,
+                },
+            },
+        );
+    }
 
     v.insert(
         "$body$".to_string(),
@@ -116,7 +163,10 @@ impl IncludeDocument {
                             let start = k.parse::<i32>()?;
                             let end = l.parse::<i32>()?;
                             if end < start {
-                                panic!("End can't be higher than start")
+                                return Err(ParseError::Invalid(format!(
+                                    "`{}`: the range ends at line {} before it starts at line {}",
+                                    s, end, start
+                                )));
                             }
                             Ok(IncludeDocument {
                                 path: doc_path.to_string(),
@@ -178,53 +228,330 @@ pub fn sanitize_anchored_lines(s: &str) -> String {
 }
 
 impl IncludeCode {
-    pub fn parse(s: &str, config: &fpm::Config) -> Result<Self, ParseError> {
+    pub fn parse(s: &str, symbol: Option<&str>, config: &fpm::Config) -> Result<Self, ParseError> {
         let doc = IncludeDocument::parse(s)?;
-        let extension = match &doc.path.rsplit_once('.') {
+        let extension = match doc.path.rsplit('/').next().and_then(|v| v.rsplit_once('.')) {
             Some((_, ex)) => ex,
             None => "txt",
         };
-        let file_path = config.root.join(
-            doc.path
-                .replace('/', std::path::MAIN_SEPARATOR.to_string().as_str()),
-        );
-        let file_content = std::fs::read_to_string(file_path)?
-            .lines()
-            .into_iter()
-            .fold(String::new(), |accumulator, instance| {
-                let should_escape = instance.starts_with('$') || instance.starts_with('/');
-                format!(
-                    "{}{}{}\n",
-                    accumulator.as_str(),
-                    if should_escape { "\\" } else { "" },
-                    instance
-                )
-            });
+        let file_path = file_path(doc.path.as_str(), config)?;
+        let file_content = std::fs::read_to_string(&file_path)
+            .map_err(|e| ParseError::Invalid(format!("{}: {}", file_path, e)))?;
         let output = match (file_content, doc.roa) {
-            (fc, RangeOrAnchor::Anchor(anchor_name)) => take_anchored_lines(&fc, &anchor_name),
+            (fc, RangeOrAnchor::Anchor(anchor_name)) => {
+                if !fc.lines().any(|l| {
+                    ANCHOR_START
+                        .captures(l)
+                        .map(|cap| &cap["anchor_name"] == anchor_name.as_str())
+                        .unwrap_or(false)
+                }) {
+                    return Err(ParseError::Invalid(format!(
+                        "no `ANCHOR: {}` in {}",
+                        anchor_name, doc.path
+                    )));
+                }
+                take_anchored_lines(&fc, &anchor_name)
+            }
             (fc, RangeOrAnchor::Range(LineRange::RangeFull)) => fc,
             (fc, RangeOrAnchor::Range(r)) => {
                 let lines = fc.lines();
-                let len = lines.clone().count();
+                let len = lines.clone().count() as i32;
                 let (start, end) = match r {
-                    LineRange::Range((s, e)) => (s - 1, e),
-                    LineRange::RangeFrom(s) => (s - 1, len as i32),
-                    LineRange::RangeTo(e) => (0, e),
-                    LineRange::SingleLine(e) => (e - 1, e),
-                    LineRange::RangeFull => (0, len as i32),
+                    LineRange::Range((s, e)) => (s, e),
+                    LineRange::RangeFrom(s) => (s, len),
+                    LineRange::RangeTo(e) => (1, e),
+                    LineRange::SingleLine(e) => (e, e),
+                    LineRange::RangeFull => (1, len),
                 };
+                if start < 1 || start > len || end < start {
+                    return Err(ParseError::Invalid(format!(
+                        "{} has {} lines, can't include lines {} to {}",
+                        doc.path, len, start, end
+                    )));
+                }
+                let end = std::cmp::min(end, len);
                 lines
-                    .skip(start as usize)
-                    .take(std::cmp::min(end as usize, len))
+                    .skip((start - 1) as usize)
+                    .take((end - start + 1) as usize)
                     .collect::<Vec<&str>>()
                     .join("\n")
             }
         };
+        let output = match symbol {
+            Some(symbol) => find_symbol(output.as_str(), symbol, extension)
+                .ok_or_else(|| ParseError::Invalid(format!("no symbol `{}` in {}", symbol, s)))?,
+            None => output,
+        };
         Ok(IncludeCode {
             extension: extension.to_string(),
-            body: sanitize_anchored_lines(output.as_str()),
+            body: escape(dedent(sanitize_anchored_lines(output.as_str()).as_str()).as_str()),
+        })
+    }
+}
+
+/// `path` in the package, or in the dependency for `-/<package>/<path>`
+fn file_path(path: &str, config: &fpm::Config) -> Result<camino::Utf8PathBuf, ParseError> {
+    let (root, path) = if path.starts_with("-/") {
+        let mut found: Option<(&str, &fpm::Package, String)> = None;
+        let packages = std::iter::once((None, &config.package)).chain(
+            config
+                .package
+                .dependencies
+                .iter()
+                .map(|d| (d.alias.as_deref(), &d.package)),
+        );
+        for (alias, package) in packages {
+            for name in std::iter::once(package.name.as_str()).chain(alias) {
+                let rest = match fpm::config::utils::trim_package_name(path, name) {
+                    Some(rest) if rest.is_empty() || rest.starts_with('/') => rest,
+                    _ => continue,
+                };
+                // the longest name wins, `-/a.com/b/x` is `x` of `a.com/b`, not `b/x` of `a.com`
+                if found
+                    .as_ref()
+                    .map(|v| v.0.len() < name.len())
+                    .unwrap_or(true)
+                {
+                    found = Some((name, package, rest));
+                }
+            }
+        }
+        match found {
+            Some((_, package, rest)) => (
+                config.get_root_for_package(package),
+                rest.trim_start_matches('/').to_string(),
+            ),
+            None => {
+                return Err(ParseError::Invalid(format!(
+                    "`{}`: no such package in the dependencies",
+                    path
+                )))
+            }
+        }
+    } else {
+        (config.root.clone(), path.to_string())
+    };
+    Ok(root.join(path.replace('/', std::path::MAIN_SEPARATOR.to_string().as_str())))
+}
+
+/// Removes the indentation common to all the non blank lines
+fn dedent(s: &str) -> String {
+    let indent = s
+        .lines()
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| l.len() - l.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    s.lines()
+        .map(|l| l.get(indent..).unwrap_or_else(|| l.trim_start()))
+        .collect::<Vec<&str>>()
+        .join("\n")
+}
+
+/// Lines starting with `$` or `/` would be read as ftd, they are escaped
+fn escape(s: &str) -> String {
+    s.lines()
+        .map(|l| {
+            if l.starts_with('$') || l.starts_with('/') {
+                format!("\\{}", l)
+            } else {
+                l.to_string()
+            }
         })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// `2-4,7` as `[(2, 4), (7, 7)]`, the lines are counted from 1 and must be among the `len`
+/// included lines
+fn parse_highlight(s: &str, len: usize) -> Result<Vec<(usize, usize)>, ParseError> {
+    let mut ranges = vec![];
+    for part in s.split(',').map(str::trim).filter(|v| !v.is_empty()) {
+        let (start, end) = match part.split_once('-') {
+            Some((start, end)) => (start.trim().parse::<usize>()?, end.trim().parse::<usize>()?),
+            None => {
+                let line = part.parse::<usize>()?;
+                (line, line)
+            }
+        };
+        if start < 1 || end < start || end > len {
+            return Err(ParseError::Invalid(format!(
+                "highlight `{}`: the included code has lines 1-{}",
+                part, len
+            )));
+        }
+        ranges.push((start, end));
+    }
+    if ranges.is_empty() {
+        return Err(ParseError::Invalid(format!(
+            "highlight `{}`: expected lines like `2-4,7`",
+            s
+        )));
+    }
+    Ok(ranges)
+}
+
+/// Definition of `symbol` in `s`, with the doc comments, attributes or decorators above it.
+/// `Type.method` or `Type::method` is `method` defined inside `Type`. Python is parsed by
+/// indentation, the other languages by braces, a definition ends at its closing brace or at the
+/// `;` ending it before any brace.
+fn find_symbol(s: &str, symbol: &str, extension: &str) -> Option<String> {
+    let lines: Vec<&str> = s.lines().collect();
+    let names: Vec<&str> = symbol
+        .split("::")
+        .flat_map(|v| v.split('.'))
+        .map(str::trim)
+        .collect();
+    if names.iter().any(|v| v.is_empty()) {
+        return None;
     }
+    let (start, end) = symbol_lines(&lines, 0, lines.len(), &names, extension == "py")?;
+    Some(lines[start..end].join("\n"))
+}
+
+/// [start, end) of the lines of the innermost of `names` within lines [from, to)
+fn symbol_lines(
+    lines: &[&str],
+    from: usize,
+    to: usize,
+    names: &[&str],
+    indented: bool,
+) -> Option<(usize, usize)> {
+    let (name, rest) = names.split_first()?;
+    let definition = definition_regex(name, indented)?;
+    for (start, line) in lines.iter().enumerate().take(to).skip(from) {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("//") || trimmed.starts_with('*') || trimmed.starts_with('#') {
+            continue;
+        }
+        if !definition.is_match(line) {
+            continue;
+        }
+        let end = if indented {
+            indented_block_end(lines, start, to)
+        } else {
+            braced_block_end(lines, start, to)
+        };
+        if !rest.is_empty() {
+            match symbol_lines(lines, start + 1, end, rest, indented) {
+                Some(found) => return Some(found),
+                None => continue,
+            }
+        }
+        let mut first = start;
+        while first > from {
+            let above = lines[first - 1].trim_start();
+            if above.starts_with("///") || above.starts_with("#[") || above.starts_with('@') {
+                first -= 1;
+            } else {
+                break;
+            }
+        }
+        return Some((first, end));
+    }
+    None
+}
+
+fn definition_regex(name: &str, indented: bool) -> Option<regex::Regex> {
+    let name = regex::escape(name);
+    let pattern = if indented {
+        format!(r"^\s*(async\s+)?(def|class)\s+{}\b", name)
+    } else {
+        format!(
+            r"(^|\W)((fn|struct|enum|trait|union|mod|type|macro_rules!|function\*?|class|interface|def|(func|fun)(\s*\([^)]*\))?)\s+{name}\b|impl\b(\s*<[^{{]*?>)?\s+([\w:<>, ]+\s+for\s+)?{name}\b|(const|let|var)\s+{name}\s*=)",
+            name = name
+        )
+    };
+    regex::Regex::new(pattern.as_str()).ok()
+}
+
+/// End, exclusive, of the block starting at line `start`: the line with the brace closing its
+/// first brace, or with the `;` ending it before any brace
+fn braced_block_end(lines: &[&str], start: usize, to: usize) -> usize {
+    let mut depth = 0;
+    let mut opened = false;
+    for (idx, line) in lines.iter().enumerate().take(to).skip(start) {
+        let chars: Vec<char> = line.chars().collect();
+        let mut i = 0;
+        let mut quote: Option<char> = None;
+        while i < chars.len() {
+            let c = chars[i];
+            if let Some(q) = quote {
+                if c == '\\' {
+                    i += 1;
+                } else if c == q {
+                    quote = None;
+                }
+                i += 1;
+                continue;
+            }
+            match c {
+                '/' if chars.get(i + 1) == Some(&'/') => break,
+                '"' | '`' => quote = Some(c),
+                // a char literal, `'a` alone is a lifetime
+                '\'' if chars.get(i + 2) == Some(&'\'') => i += 2,
+                '\'' if chars.get(i + 1) == Some(&'\\') && chars.get(i + 3) == Some(&'\'') => {
+                    i += 3
+                }
+                '{' => {
+                    depth += 1;
+                    opened = true;
+                }
+                '}' => {
+                    depth -= 1;
+                    if opened && depth == 0 {
+                        return idx + 1;
+                    }
+                }
+                ';' if !opened && depth == 0 => return idx + 1,
+                _ => {}
+            }
+            i += 1;
+        }
+    }
+    to
+}
+
+/// End, exclusive, of the python block starting at line `start`: the lines after its header
+/// which are blank or indented more than it, without the trailing blank lines
+fn indented_block_end(lines: &[&str], start: usize, to: usize) -> usize {
+    let indent_of = |l: &str| l.len() - l.trim_start().len();
+    let indent = indent_of(lines[start]);
+    let mut idx = start;
+    // the header can span lines till the `:` outside the brackets
+    let mut depth = 0;
+    while idx < to {
+        for c in lines[idx].split('#').next().unwrap_or_default().chars() {
+            match c {
+                '(' | '[' | '{' => depth += 1,
+                ')' | ']' | '}' => depth -= 1,
+                _ => {}
+            }
+        }
+        idx += 1;
+        if depth <= 0
+            && lines[idx - 1]
+                .split('#')
+                .next()
+                .unwrap_or_default()
+                .trim_end()
+                .ends_with(':')
+        {
+            break;
+        }
+    }
+    let mut end = idx;
+    while idx < to {
+        let line = lines[idx];
+        if !line.trim().is_empty() {
+            if indent_of(line) <= indent {
+                break;
+            }
+            end = idx + 1;
+        }
+        idx += 1;
+    }
+    end
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -237,6 +564,9 @@ pub enum ParseError {
 
     #[error("{}", _0)]
     FTDError(#[from] ftd::p1::Error),
+
+    #[error("{}", _0)]
+    Invalid(String),
 }
 
 #[cfg(test)]
@@ -298,4 +628,93 @@ mod test {
             }
         );
     }
+
+    #[test]
+    fn parse_invalid_range() {
+        assert!(super::IncludeDocument::parse("code.rs:5:2").is_err());
+        assert!(super::IncludeDocument::parse("code.rs:a:2").is_err());
+    }
+
+    #[test]
+    fn dedent() {
+        assert_eq!(
+            super::dedent("    fn main() {\n\n        $x\n    }"),
+            "fn main() {\n\n    $x\n}"
+        );
+        assert_eq!(super::escape("$x\n  $y\n/z"), "\\$x\n  $y\n\\/z");
+    }
+
+    #[test]
+    fn highlight() {
+        assert_eq!(
+            super::parse_highlight("2-4, 7", 7).unwrap(),
+            vec![(2, 4), (7, 7)]
+        );
+        assert!(super::parse_highlight("2-8", 7).is_err());
+        assert!(super::parse_highlight("4-2", 7).is_err());
+        assert!(super::parse_highlight("0", 7).is_err());
+        assert!(super::parse_highlight("", 7).is_err());
+    }
+
+    #[test]
+    fn find_symbol() {
+        let rust = indoc::indoc! {"
+            use std::io;
+
+            struct Guess(u32);
+
+            impl Guess {
+                /// Reads a guess
+                pub fn read() -> Guess {
+                    let c = '{';
+                    Guess(1)
+                }
+
+                fn value(&self) -> u32 {
+                    self.0
+                }
+            }
+
+            fn main() {
+                println!(\"{}\", \"}\");
+            }
+        "};
+        assert_eq!(
+            super::find_symbol(rust, "Guess", "rs").as_deref(),
+            Some("struct Guess(u32);")
+        );
+        assert_eq!(
+            super::find_symbol(rust, "Guess::read", "rs").as_deref(),
+            Some(
+                "    /// Reads a guess\n    pub fn read() -> Guess {\n        let c = '{';\n        \
+                Guess(1)\n    }"
+            )
+        );
+        assert_eq!(
+            super::find_symbol(rust, "main", "rs").as_deref(),
+            Some("fn main() {\n    println!(\"{}\", \"}\");\n}")
+        );
+        assert_eq!(super::find_symbol(rust, "missing", "rs"), None);
+
+        let python = indoc::indoc! {"
+            class Guess:
+                @staticmethod
+                def read(
+                    prompt: str,
+                ) -> int:
+                    value = input(prompt)
+
+                    return int(value)
+
+                def value(self):
+                    return 1
+        "};
+        assert_eq!(
+            super::find_symbol(python, "Guess.read", "py").as_deref(),
+            Some(
+                "    @staticmethod\n    def read(\n        prompt: str,\n    ) -> int:\n        \
+                value = input(prompt)\n\n        return int(value)"
+            )
+        );
+    }
 }
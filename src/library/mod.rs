@@ -6,11 +6,15 @@ pub(crate) mod full_sitemap;
 mod get_data;
 mod get_version_data;
 pub(crate) mod http;
+mod i18n;
 mod include;
+mod navigation;
 mod package_tree;
+mod responsive_image;
 mod sitemap;
 mod sqlite;
 mod toc;
+mod tracks;
 
 pub use document::convert_to_document_id;
 pub use full_sitemap::KeyValueData;
@@ -184,6 +188,12 @@ impl Library {
             }
             "document-name" => document::processor::document_name(section, doc, &self.config).await,
             "is-reader" => fpm::user_group::processor::is_reader(section, doc, &self.config).await,
+            "tracks" => fpm::library::tracks::processor(section, doc, &self.config).await,
+            "breadcrumbs" => {
+                fpm::library::navigation::breadcrumbs(section, doc, &self.config).await
+            }
+            "prev-next" => fpm::library::navigation::prev_next(section, doc, &self.config).await,
+            "children" => fpm::library::navigation::children(section, doc, &self.config).await,
             _ => process_sync(&self.config, section, self.document_id.as_str(), doc),
         }
     }
@@ -216,6 +226,7 @@ pub fn process_sync<'a>(
         "user-group-by-id" => fpm::user_group::processor::user_group_by_id(section, doc, config),
         "package-query" => fpm::library::sqlite::processor_(section, doc, config),
         "fetch-file" => fpm::library::fetch_file::processor_sync(section, doc, config),
+        "responsive-image" => fpm::library::responsive_image::processor(section, doc, config),
         "package-tree" => fpm::library::package_tree::processor_sync(section, doc, config),
         "document-id" => document::processor::document_id(section, doc, config),
         "document-full-id" => document::processor::document_full_id(section, doc, config),
@@ -229,6 +240,7 @@ pub fn process_sync<'a>(
         // TODO: auth feature flag
         "user-details" => fpm::auth::processor::user_details(section, doc, config),
         "fpm-apps" => fpm::package::app::processor(section, doc, config),
+        "i18n" => fpm::library::i18n::processor(section, doc, config),
         t => Err(ftd::p1::Error::NotFound {
             doc_id: document_id.to_string(),
             line_number: section.line_number,
@@ -464,6 +476,9 @@ impl Library2 {
                 fpm::library::package_tree::processor(section, doc, &self.config).await
             }
             "fetch-file" => fpm::library::fetch_file::processor(section, doc, &self.config).await,
+            "responsive-image" => {
+                fpm::library::responsive_image::processor(section, doc, &self.config)
+            }
             "get-version-data" => {
                 fpm::library::get_version_data::processor(
                     section,
@@ -482,6 +497,13 @@ impl Library2 {
             "user-details" => fpm::auth::processor::user_details(section, doc, &self.config),
             "fpm-apps" => fpm::package::app::processor(section, doc, &self.config),
             "is-reader" => fpm::user_group::processor::is_reader(section, doc, &self.config).await,
+            "tracks" => fpm::library::tracks::processor(section, doc, &self.config).await,
+            "i18n" => fpm::library::i18n::processor(section, doc, &self.config),
+            "breadcrumbs" => {
+                fpm::library::navigation::breadcrumbs(section, doc, &self.config).await
+            }
+            "prev-next" => fpm::library::navigation::prev_next(section, doc, &self.config).await,
+            "children" => fpm::library::navigation::children(section, doc, &self.config).await,
             t => Err(ftd::p1::Error::NotFound {
                 doc_id: self.document_id.to_string(),
                 line_number: section.line_number,
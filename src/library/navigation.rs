@@ -0,0 +1,127 @@
+use itertools::Itertools;
+
+/// The entries of the sitemap the current document is nested in, and its own entry.
+///
+/// ```ftd
+/// -- fpm.nav-page list breadcrumbs:
+/// $processor$: breadcrumbs
+/// ```
+pub async fn breadcrumbs<'a>(
+    section: &ftd::p1::Section,
+    doc: &ftd::p2::TDoc<'a>,
+    config: &fpm::Config,
+) -> ftd::p1::Result<ftd::Value> {
+    let sitemap = match config.package.sitemap.as_ref() {
+        Some(sitemap) => sitemap,
+        None => return doc.from_json(&Vec::<fpm::sitemap::navigation::Page>::new(), section),
+    };
+    let current = fpm::library::document::document_full_id(config, doc)?;
+    let unreadable = unreadable(section, doc, config, sitemap).await?;
+    doc.from_json(
+        &fpm::sitemap::navigation::breadcrumbs(sitemap, current.as_str(), |v| {
+            !unreadable.contains(v)
+        }),
+        section,
+    )
+}
+
+/// The pages before and after the current document in reading order.
+///
+/// ```ftd
+/// -- fpm.prev-next-data pages:
+/// $processor$: prev-next
+/// ```
+pub async fn prev_next<'a>(
+    section: &ftd::p1::Section,
+    doc: &ftd::p2::TDoc<'a>,
+    config: &fpm::Config,
+) -> ftd::p1::Result<ftd::Value> {
+    let sitemap = match config.package.sitemap.as_ref() {
+        Some(sitemap) => sitemap,
+        None => return doc.from_json(&fpm::sitemap::navigation::PrevNext::default(), section),
+    };
+    let current = fpm::library::document::document_full_id(config, doc)?;
+    let unreadable = unreadable(section, doc, config, sitemap).await?;
+    doc.from_json(
+        &fpm::sitemap::navigation::prev_next(sitemap, current.as_str(), |v| {
+            !unreadable.contains(v)
+        }),
+        section,
+    )
+}
+
+/// The entries of the sitemap nested right in the entry of the current document, with their
+/// `extra-data`.
+///
+/// ```ftd
+/// -- fpm.nav-page list children:
+/// $processor$: children
+/// ```
+pub async fn children<'a>(
+    section: &ftd::p1::Section,
+    doc: &ftd::p2::TDoc<'a>,
+    config: &fpm::Config,
+) -> ftd::p1::Result<ftd::Value> {
+    let sitemap = match config.package.sitemap.as_ref() {
+        Some(sitemap) => sitemap,
+        None => return doc.from_json(&Vec::<fpm::sitemap::navigation::Page>::new(), section),
+    };
+    let current = fpm::library::document::document_full_id(config, doc)?;
+    let unreadable = unreadable(section, doc, config, sitemap).await?;
+    doc.from_json(
+        &fpm::sitemap::navigation::children(sitemap, current.as_str(), |v| !unreadable.contains(v)),
+        section,
+    )
+}
+
+/// The urls of the sitemap the reader of the request can not read. `fpm build` has no reader,
+/// every page is in the navigation. The identities of the reader are resolved once, for the
+/// readers of all the confidential urls.
+async fn unreadable<'a>(
+    section: &ftd::p1::Section,
+    doc: &ftd::p2::TDoc<'a>,
+    config: &fpm::Config,
+    sitemap: &fpm::sitemap::Sitemap,
+) -> ftd::p1::Result<std::collections::HashSet<String>> {
+    let req = match config.request.as_ref() {
+        Some(req) => req,
+        None => return Ok(Default::default()),
+    };
+    let to_error = |e: fpm::Error| ftd::p1::Error::ParseError {
+        message: e.to_string(),
+        doc_id: doc.name.to_string(),
+        line_number: section.line_number,
+    };
+    let mut restricted = vec![];
+    let mut reader_identities: Vec<fpm::user_group::UserIdentity> = vec![];
+    for url in fpm::sitemap::navigation::urls(sitemap) {
+        let (readers, confidential) = sitemap.readers(url.as_str(), &config.package.groups);
+        if readers.is_empty() || !confidential {
+            continue;
+        }
+        for reader in readers.iter() {
+            for identity in reader.get_identities(config).map_err(to_error)? {
+                if !reader_identities.contains(&identity) {
+                    reader_identities.push(identity);
+                }
+            }
+        }
+        restricted.push((url, readers));
+    }
+    if restricted.is_empty() {
+        return Ok(Default::default());
+    }
+    let identities = fpm::user_group::request_identities(req, reader_identities.as_slice())
+        .await
+        .map_err(to_error)?;
+    let identities = identities.iter().collect_vec();
+    let mut unreadable = std::collections::HashSet::new();
+    for (url, readers) in restricted {
+        if !fpm::user_group::belongs_to(config, readers.as_slice(), identities.as_slice())
+            .map_err(to_error)?
+        {
+            unreadable.insert(url);
+        }
+    }
+    Ok(unreadable)
+}
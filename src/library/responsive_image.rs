@@ -0,0 +1,47 @@
+/// ```ftd
+/// -- fpm.responsive-image-data hero:
+/// $processor$: responsive-image
+/// src: static/hero.png
+/// width: 800
+///
+/// -- ftd.image:
+/// src: $hero.src
+/// ```
+///
+/// `width` is optional, if passed `src` is the smallest webp variant at least that wide,
+/// else it is the original image.
+pub fn processor<'a>(
+    section: &ftd::p1::Section,
+    doc: &ftd::p2::TDoc<'a>,
+    config: &fpm::Config,
+) -> ftd::p1::Result<ftd::Value> {
+    let src = section
+        .header
+        .string(doc.name, section.line_number, "src")?;
+    let width = match section.header.str(doc.name, section.line_number, "width") {
+        Ok(width) => Some(
+            width
+                .parse::<u32>()
+                .map_err(|e| ftd::p1::Error::ParseError {
+                    message: format!("responsive-image: invalid width `{}`: {:?}", width, e),
+                    doc_id: doc.name.to_string(),
+                    line_number: section.line_number,
+                })?,
+        ),
+        Err(_) => None,
+    };
+
+    let data = fpm::responsive_image::get(config, src.as_str()).map_err(|e| {
+        ftd::p1::Error::ParseError {
+            message: e.to_string(),
+            doc_id: doc.name.to_string(),
+            line_number: section.line_number,
+        }
+    })?;
+    let data = match width {
+        Some(width) => data.with_width(width),
+        None => data,
+    };
+
+    doc.from_json(&data, section)
+}
@@ -0,0 +1,14 @@
+pub async fn processor<'a>(
+    section: &ftd::p1::Section,
+    doc: &ftd::p2::TDoc<'a>,
+    config: &fpm::Config,
+) -> ftd::p1::Result<ftd::Value> {
+    let relations = fpm::tracker::get_track_relations(config)
+        .await
+        .map_err(|e| ftd::p1::Error::ParseError {
+            message: format!("Cant read the tracks: {:?}", e),
+            doc_id: doc.name.to_string(),
+            line_number: section.line_number,
+        })?;
+    doc.from_json(&relations, section)
+}
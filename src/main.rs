@@ -19,6 +19,42 @@ async fn async_main() -> fpm::Result<()> {
         Some((fpm::commands::sync_status::COMMAND, matches)) => {
             return fpm::commands::sync_status::handle_command(matches).await;
         }
+        Some((fpm::commands::log::COMMAND, matches)) => {
+            return fpm::commands::log::handle_command(matches).await;
+        }
+        Some((fpm::commands::show::COMMAND, matches)) => {
+            return fpm::commands::show::handle_command(matches).await;
+        }
+        Some((fpm::commands::gc::COMMAND, matches)) => {
+            return fpm::commands::gc::handle_command(matches).await;
+        }
+        Some((fpm::commands::cr::COMMAND, matches)) => {
+            return fpm::commands::cr::handle_command(matches).await;
+        }
+        Some((fpm::commands::export_git::COMMAND, matches)) => {
+            return fpm::commands::export_git::handle_command(matches).await;
+        }
+        Some((fpm::commands::import_git::COMMAND, matches)) => {
+            return fpm::commands::import_git::handle_command(matches).await;
+        }
+        Some((fpm::commands::login::COMMAND, matches)) => {
+            return fpm::commands::login::handle_command(matches).await;
+        }
+        Some((fpm::commands::tracks::COMMAND, matches)) => {
+            return fpm::commands::tracks::handle_command(matches).await;
+        }
+        Some((fpm::commands::translate::COMMAND, matches)) => {
+            return fpm::commands::translate::handle_command(matches).await;
+        }
+        Some((fpm::commands::translation::COMMAND, matches)) => {
+            return fpm::commands::translation::handle_command(matches).await;
+        }
+        Some((fpm::commands::check_sitemap::COMMAND, matches)) => {
+            return fpm::commands::check_sitemap::handle_command(matches).await;
+        }
+        Some((fpm::commands::sitemap::COMMAND, matches)) => {
+            return fpm::commands::sitemap::handle_command(matches).await;
+        }
         _ => {}
     }
 
@@ -42,12 +78,14 @@ async fn async_main() -> fpm::Result<()> {
         let bind = mark.value_of_("bind").unwrap_or("127.0.0.1").to_string();
         let download_base_url = mark.value_of_("download-base-url");
         let edition = mark.value_of_("edition");
+        let tls = tls_options(mark);
 
         return fpm::listen(
             bind.as_str(),
             port,
             download_base_url.map(ToString::to_string),
             edition.map(ToString::to_string),
+            tls,
         )
         .await;
     }
@@ -86,6 +124,7 @@ async fn async_main() -> fpm::Result<()> {
             merge.value_of_("src"),
             merge.value_of_("dest").unwrap(),
             merge.value_of_("file"), // TODO: support multiple files
+            merge.get_flag("force"),
         )
         .await;
     }
@@ -100,6 +139,7 @@ async fn async_main() -> fpm::Result<()> {
             build.value_of_("file"), // TODO: handle more than one files
             build.value_of_("base").unwrap_or("/"),
             build.get_flag("ignore-failed"),
+            build.get_flag("fingerprint"),
         )
         .await;
     }
@@ -117,11 +157,12 @@ async fn async_main() -> fpm::Result<()> {
     }
 
     if let Some(sync) = matches.subcommand_matches("sync") {
+        let message = sync.value_of_("message");
         return if let Some(source) = sync.get_many::<String>("file") {
             let sources = source.map(|v| v.to_string()).collect();
-            fpm::sync2(&config, Some(sources)).await
+            fpm::sync2(&config, Some(sources), message).await
         } else {
-            fpm::sync2(&config, None).await
+            fpm::sync2(&config, None, message).await
         };
     }
     if let Some(create_cr) = matches.subcommand_matches("create-cr") {
@@ -139,6 +180,13 @@ async fn async_main() -> fpm::Result<()> {
     }
     if let Some(diff) = matches.subcommand_matches("diff") {
         let all = diff.get_flag("all");
+        if let Some(from) = diff.value_of_("from") {
+            let files = diff
+                .get_many::<String>("file")
+                .map(|v| v.map(|v| v.to_string()).collect())
+                .unwrap_or_default();
+            return fpm::diff_versions(&config, files, from, diff.value_of_("to")).await;
+        }
         return if let Some(source) = diff.get_many::<String>("file") {
             fpm::diff(&config, Some(source.map(|v| v.to_string()).collect()), all).await
         } else {
@@ -172,6 +220,28 @@ async fn async_main() -> fpm::Result<()> {
     unreachable!("No subcommand matched");
 }
 
+fn tls_options(matches: &clap::ArgMatches) -> Option<fpm::tls::TlsOptions> {
+    use fpm::utils::ValueOf;
+
+    let certificate = match (matches.value_of_("tls-cert"), matches.value_of_("tls-key")) {
+        (Some(cert), Some(key)) => fpm::tls::Certificate::Files {
+            cert: cert.into(),
+            key: key.into(),
+        },
+        _ if matches.get_flag("tls-self-signed") => fpm::tls::Certificate::SelfSigned,
+        _ => return None,
+    };
+
+    Some(fpm::tls::TlsOptions {
+        certificate,
+        http_redirect_port: matches.get_one::<u16>("http-redirect-port").copied(),
+        hsts_max_age: matches
+            .get_one::<u64>("hsts-max-age")
+            .copied()
+            .unwrap_or_default(),
+    })
+}
+
 fn app(version: &'static str) -> clap::Command {
     clap::Command::new("fpm: FTD Package Manager")
         .version(version)
@@ -194,6 +264,7 @@ fn app(version: &'static str) -> clap::Command {
                 .arg(clap::arg!(file: [FILE]... "The file to build (if specified only these are built, else entire package is built)"))
                 .arg(clap::arg!(-b --base [BASE] "The base path.").default_value("/"))
                 .arg(clap::arg!(--"ignore-failed" "Ignore failed files."))
+                .arg(clap::arg!(--fingerprint "Content hash static asset file names, minify css, js and html, and write .build/-/assets.json"))
         )
         .subcommand(
             clap::Command::new("mark-resolved")
@@ -240,6 +311,7 @@ fn app(version: &'static str) -> clap::Command {
                 .arg(clap::arg!(src: <SRC> "The source manifest to merge"))
                 .arg(clap::arg!(dest: <DEST> "The destination manifest to merge"))
                 .arg(clap::arg!(file: <FILE>... "The file(s) to merge"))
+                .arg(clap::arg!(--force "Merge a change request into main even if it is not approved"))
                 .hide(true) // hidden since the feature is not being released yet.
         )
         .subcommand(
@@ -256,6 +328,7 @@ fn app(version: &'static str) -> clap::Command {
             clap::Command::new("sync")
                 .about("Sync with fpm-repo (or .history folder if not using fpm-repo)")
                 .arg(clap::arg!(file: <FILE>... "The file(s) to sync (leave empty to sync entire package)"))
+                .arg(clap::arg!(-m --message <MESSAGE> "Describe the change, recorded in history along with the author (FPM_AUTHOR or `author` in .clone-state/config.json)"))
                 .hide(true) // hidden since the feature is not being released yet.
         )
         .subcommand(
@@ -265,6 +338,18 @@ fn app(version: &'static str) -> clap::Command {
                 .hide(true) // hidden since the feature is not being released yet.
         )
         .subcommand(fpm::commands::sync_status::command())
+        .subcommand(fpm::commands::log::command())
+        .subcommand(fpm::commands::show::command())
+        .subcommand(fpm::commands::gc::command())
+        .subcommand(fpm::commands::cr::command())
+        .subcommand(fpm::commands::export_git::command())
+        .subcommand(fpm::commands::import_git::command())
+        .subcommand(fpm::commands::login::command())
+        .subcommand(fpm::commands::tracks::command())
+        .subcommand(fpm::commands::translation::command())
+        .subcommand(fpm::commands::translate::command())
+        .subcommand(fpm::commands::check_sitemap::command())
+        .subcommand(fpm::commands::sitemap::command())
         .subcommand(
             clap::Command::new("create-cr")
                 .about("Create a Change Request")
@@ -287,13 +372,15 @@ fn app(version: &'static str) -> clap::Command {
                 .about("Show un-synced changes to files in this fpm package")
                 .arg(clap::arg!(file: <FILE>... "The file(s) to see diff of (leave empty to see diff of entire package)").required(false))
                 .arg(clap::arg!(-a --all "Show all changes."))
+                .arg(clap::arg!(--from <VERSION> "Compare this version of the file(s) from history, see `fpm log`"))
+                .arg(clap::arg!(--to <VERSION> "Compare with this version of the file(s) instead of the current content").requires("from"))
                 .hide(true) // hidden since the feature is not being released yet.
         )
         .subcommand(
             clap::Command::new("resolve-conflict")
                 .about("Show un-synced changes to files in this fpm package")
-                .arg(clap::arg!(--"use-ours" "Use our version of the file"))
-                .arg(clap::arg!(--"use-theirs" "Use their version of the file"))
+                .arg(clap::arg!(--"use-ours" "Use our version of the file, or only of its conflicting hunks if it has conflict markers"))
+                .arg(clap::arg!(--"use-theirs" "Use their version of the file, or only of its conflicting hunks if it has conflict markers"))
                 .arg(clap::arg!(--"revive-it" "Revive the file"))
                 .arg(clap::arg!(--"delete-it" "Delete the file"))
                 .arg(clap::arg!(--"print" "Print the file to stdout"))
@@ -333,7 +420,12 @@ mod sub_command {
             .arg(clap::arg!(--port <PORT> "The port to listen on [default: first available port starting 8000]"))
             .arg(clap::arg!(--bind <ADDRESS> "The address to bind to").default_value("127.0.0.1"))
             .arg(clap::arg!(--edition <EDITION> "The FTD edition"))
-            .arg(clap::arg!(--"download-base-url" <URL> "If running without files locally, download needed files from here"));
+            .arg(clap::arg!(--"download-base-url" <URL> "If running without files locally, download needed files from here"))
+            .arg(clap::arg!(--"tls-cert" <PATH> "PEM encoded certificate chain, serves over HTTPS (and HTTP/2)").requires("tls-key"))
+            .arg(clap::arg!(--"tls-key" <PATH> "PEM encoded private key for --tls-cert").requires("tls-cert"))
+            .arg(clap::arg!(--"tls-self-signed" "Serve over HTTPS using a generated self-signed certificate, for local testing only").conflicts_with("tls-cert"))
+            .arg(clap::arg!(--"http-redirect-port" <PORT> "When serving over HTTPS, also listen for HTTP on this port and redirect to HTTPS").value_parser(clap::value_parser!(u16)))
+            .arg(clap::arg!(--"hsts-max-age" <SECONDS> "Strict-Transport-Security max-age sent over HTTPS, 0 disables it").value_parser(clap::value_parser!(u64)).default_value("31536000"));
         if cfg!(feature = "remote") {
             serve
         } else {
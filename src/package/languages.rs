@@ -0,0 +1,392 @@
+// A package with `translations` and its translation packages have the same documents in
+// different languages, each served on its own domain. `fpm serve` of the original package sends
+// the reader to the same document in the package of their language, picked by the `fpm-language`
+// cookie or else by the `Accept-Language` header. The pages, and the `sitemap.xml` of
+// `fpm build`, link the versions of a document in the other languages with `hreflang`.
+
+/// The language chosen by the reader, it is set by opening any page of the original package with
+/// `?fpm-language=<language>`, like the language switcher does for the original package
+pub const COOKIE: &str = "fpm-language";
+
+/// The original package followed by its translations, the packages of the other languages of
+/// `package`. Empty if the package has no translations.
+pub(crate) fn packages(package: &fpm::Package) -> Vec<&fpm::Package> {
+    let original = package.translation_of.as_ref().as_ref().unwrap_or(package);
+    let mut packages: Vec<&fpm::Package> = vec![];
+    for p in std::iter::once(original)
+        .chain(original.translations.iter())
+        .chain(std::iter::once(package))
+    {
+        if !packages.iter().any(|v| v.name == p.name) {
+            packages.push(p);
+        }
+    }
+    if packages.len() < 2 {
+        return vec![];
+    }
+    packages
+}
+
+/// `https://<package>/<path>`, the url of the document with `path` in `package`
+pub(crate) fn url(package: &fpm::Package, path: &str) -> String {
+    format!(
+        "https://{}/{}",
+        package.name.trim_end_matches('/'),
+        path.trim_start_matches('/')
+    )
+}
+
+/// Path of the document rendered at `main_id`, `fpm::utils::id_to_path` of its id, in the urls of
+/// the other language packages. `None` for the documents of the dependencies.
+fn document_path(main_id: &str) -> Option<String> {
+    let path = main_id.replace(std::path::MAIN_SEPARATOR, "/");
+    if path.starts_with("-/") {
+        return None;
+    }
+    let path = path.trim_matches('/');
+    Some(if path.is_empty() || path.ends_with(".html") {
+        path.to_string()
+    } else {
+        format!("{}/", path)
+    })
+}
+
+/// `<link rel="alternate" hreflang=".." />` of the document in every language, and `x-default`
+/// for the original package, put in the head of the page next to the canonical url
+pub fn hreflang_links(package: &fpm::Package, main_id: &str) -> String {
+    let path = match document_path(main_id) {
+        Some(path) => path,
+        None => return "".to_string(),
+    };
+    alternates(package, path.as_str())
+        .into_iter()
+        .map(|(hreflang, href)| {
+            format!(
+                "\n<link rel=\"alternate\" hreflang=\"{}\" href=\"{}\" />",
+                escape(hreflang.as_str()),
+                escape(href.as_str())
+            )
+        })
+        .collect()
+}
+
+/// (hreflang, url) of the document with `path` in every language package, and `x-default`
+fn alternates(package: &fpm::Package, path: &str) -> Vec<(String, String)> {
+    let packages = packages(package);
+    let mut alternates: Vec<(String, String)> = packages
+        .iter()
+        .filter_map(|p| Some((p.language.as_ref()?.to_string(), url(p, path))))
+        .collect();
+    if let Some(original) = packages.first() {
+        alternates.push(("x-default".to_string(), url(original, path)));
+    }
+    alternates
+}
+
+/// Language tags of an `Accept-Language` header in lower case, the most preferred first. `*`
+/// and the tags with `q=0` are left out.
+pub(crate) fn accepted_languages(header: &str) -> Vec<String> {
+    let mut languages: Vec<(String, f32)> = header
+        .split(',')
+        .filter_map(|v| {
+            let mut parts = v.split(';');
+            let tag = parts.next()?.trim();
+            if tag.is_empty() || tag == "*" {
+                return None;
+            }
+            let q = parts
+                .find_map(|p| p.trim().strip_prefix("q="))
+                .map(|q| q.trim().parse::<f32>().unwrap_or(0.0))
+                .unwrap_or(1.0);
+            if q <= 0.0 {
+                return None;
+            }
+            Some((tag.to_lowercase(), q))
+        })
+        .collect();
+    // stable, the tags with the same weight stay in the order of the header
+    languages.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+    languages.into_iter().map(|(tag, _)| tag).collect()
+}
+
+/// The package in the first of the `languages` any package is in. A package in `hi` matches
+/// `hi-IN` if no package is in `hi-IN`.
+fn best_match<'a>(packages: &[&'a fpm::Package], languages: &[String]) -> Option<&'a fpm::Package> {
+    fn language_of(p: &fpm::Package) -> Option<String> {
+        p.language
+            .as_ref()
+            .map(|v| v.to_lowercase().replace('_', "-"))
+    }
+
+    for language in languages {
+        let primary = language.split('-').next().unwrap_or_default();
+        let found = packages
+            .iter()
+            .find(|p| language_of(p).as_ref() == Some(language))
+            .or_else(|| {
+                packages.iter().find(|p| {
+                    language_of(p)
+                        .map(|v| v.split('-').next().unwrap_or_default() == primary)
+                        .unwrap_or(false)
+                })
+            });
+        if let Some(found) = found {
+            return Some(found);
+        }
+    }
+    None
+}
+
+/// Only the documents of the package itself have versions in the other languages. Not the `-/`
+/// urls of fpm and the dependencies, the urls of the apps and dependencies mounted in the
+/// package, or the urls the package passes on to its endpoint.
+fn is_document_path(config: &fpm::Config, path: &str) -> bool {
+    let path = path.trim_matches('/');
+    if path == "-" || path.starts_with("-/") {
+        return false;
+    }
+    let package = &config.package;
+    let mut mount_points = package.apps.iter().map(|v| v.mount_point.as_str()).chain(
+        package
+            .dependencies
+            .iter()
+            .filter_map(|v| v.mountpoint.as_deref()),
+    );
+    if mount_points.any(|mount_point| {
+        let mount_point = mount_point.trim_matches('/');
+        !mount_point.is_empty()
+            && (path == mount_point || path.starts_with(format!("{}/", mount_point).as_str()))
+    }) {
+        return false;
+    }
+    let id = if path.is_empty() { "/" } else { path };
+    fpm::Config::get_file_name(&config.root, id).is_ok()
+        || package
+            .sitemap
+            .as_ref()
+            .and_then(|v| v.resolve_document(id))
+            .is_some()
+        || package
+            .dynamic_urls
+            .as_ref()
+            .and_then(|v| v.resolve_document(id).ok())
+            .and_then(|(document, _)| document)
+            .is_some()
+}
+
+/// Redirect to the document at `path` in the package of the reader's language, if `fpm serve`
+/// serves an original package and the reader prefers the language of one of its translations.
+/// `?fpm-language=<language>` picks the language and remembers it in the `fpm-language` cookie,
+/// the cookie wins over the `Accept-Language` header.
+pub(crate) fn negotiate(config: &fpm::Config, path: &str) -> Option<fpm::http::Response> {
+    let package = &config.package;
+    if package.translation_of.is_some() {
+        return None;
+    }
+    let packages = packages(package);
+    if packages.is_empty() {
+        return None;
+    }
+    let req = config.request.as_ref()?;
+    if req.method() != "GET"
+        || fpm::file::is_static(path).unwrap_or(true)
+        || !is_document_path(config, path)
+    {
+        return None;
+    }
+
+    let mut chosen = None;
+    let mut query = vec![];
+    for (key, value) in url::form_urlencoded::parse(req.query_string().as_bytes()) {
+        if key == COOKIE {
+            chosen = Some(value.to_lowercase());
+        } else {
+            query.push((key.into_owned(), value.into_owned()));
+        }
+    }
+    let query = url::form_urlencoded::Serializer::new(String::new())
+        .extend_pairs(query)
+        .finish();
+
+    let languages = match chosen.as_ref() {
+        Some(chosen) => vec![chosen.to_string()],
+        None => match req.cookie(COOKIE) {
+            Some(cookie) => vec![cookie.to_lowercase()],
+            None => accepted_languages(
+                req.headers()
+                    .get(reqwest::header::ACCEPT_LANGUAGE)
+                    .and_then(|v| v.to_str().ok())
+                    .unwrap_or_default(),
+            ),
+        },
+    };
+    let found = best_match(&packages, &languages);
+
+    let location = match found {
+        Some(found) if found.name != package.name => url(found, path),
+        // served here, only the choice of the language needs to be remembered
+        _ if chosen.is_some() => format!("/{}", path.trim_start_matches('/')),
+        _ => return None,
+    };
+    let location = if query.is_empty() {
+        location
+    } else {
+        format!("{}?{}", location, query)
+    };
+
+    let mut resp = actix_web::HttpResponse::Found();
+    resp.append_header((actix_web::http::header::LOCATION, location))
+        .append_header((actix_web::http::header::VARY, "Accept-Language, Cookie"));
+    if let Some(chosen) = chosen {
+        resp.cookie(
+            actix_web::cookie::Cookie::build(COOKIE, chosen)
+                .path("/")
+                .permanent()
+                .finish(),
+        );
+    }
+    Some(resp.finish())
+}
+
+/// `sitemap.xml` of the documents of the package, with the urls of the documents in the other
+/// languages. Written only for packages with translations, and not over a `sitemap.xml` of the
+/// package itself.
+pub(crate) async fn build_sitemap(
+    config: &fpm::Config,
+    documents: &std::collections::BTreeMap<String, fpm::File>,
+) -> fpm::Result<()> {
+    if packages(&config.package).is_empty() {
+        return Ok(());
+    }
+    if documents.values().any(|v| v.get_id() == "sitemap.xml") {
+        fpm::warning!(
+            "sitemap.xml of the package is kept, it does not get the urls of the other languages"
+        );
+        return Ok(());
+    }
+
+    let mut urls = "".to_string();
+    for document in documents.values() {
+        let id = match document {
+            fpm::File::Ftd(doc) | fpm::File::Markdown(doc) if doc.id != "FPM.ftd" => {
+                doc.id.as_str()
+            }
+            _ => continue,
+        };
+        let path = match document_path(fpm::utils::id_to_path(id).as_str()) {
+            Some(path) => path,
+            None => continue,
+        };
+        urls.push_str(
+            format!(
+                "  <url>\n    <loc>{}</loc>\n",
+                escape(url(&config.package, path.as_str()).as_str())
+            )
+            .as_str(),
+        );
+        for (hreflang, href) in alternates(&config.package, path.as_str()) {
+            urls.push_str(
+                format!(
+                    "    <xhtml:link rel=\"alternate\" hreflang=\"{}\" href=\"{}\" />\n",
+                    escape(hreflang.as_str()),
+                    escape(href.as_str())
+                )
+                .as_str(),
+            );
+        }
+        urls.push_str("  </url>\n");
+    }
+
+    fpm::utils::update(
+        config.build_dir().join("sitemap.xml"),
+        format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+            <urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\" \
+            xmlns:xhtml=\"http://www.w3.org/1999/xhtml\">\n{}</urlset>\n",
+            urls
+        )
+        .as_bytes(),
+    )
+    .await
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    fn package(name: &str, language: &str) -> fpm::Package {
+        let mut package = fpm::Package::new(name);
+        package.language = Some(language.to_string());
+        package
+    }
+
+    #[test]
+    fn accepted_languages() {
+        assert_eq!(
+            super::accepted_languages("fr-CH, fr;q=0.9, en;q=0.8, de;q=0.7, *;q=0.5"),
+            vec!["fr-ch", "fr", "en", "de"]
+        );
+        assert_eq!(
+            super::accepted_languages("en;q=0.5, hi-IN, gu;q=0"),
+            vec!["hi-in", "en"]
+        );
+        assert!(super::accepted_languages("").is_empty());
+    }
+
+    #[test]
+    fn best_match() {
+        let en = package("www.amitu.com", "en");
+        let hi = package("hi.amitu.com", "hi");
+        let pt = package("br.amitu.com", "pt-BR");
+        let packages = vec![&en, &hi, &pt];
+        let best = |v: &str| {
+            super::best_match(&packages, &super::accepted_languages(v)).map(|p| p.name.as_str())
+        };
+        assert_eq!(best("hi-IN, en;q=0.8"), Some("hi.amitu.com"));
+        assert_eq!(best("pt-BR"), Some("br.amitu.com"));
+        assert_eq!(best("pt-PT"), Some("br.amitu.com"));
+        assert_eq!(best("de, en;q=0.2"), Some("www.amitu.com"));
+        assert_eq!(best("de"), None);
+    }
+
+    #[test]
+    fn hreflang_links() {
+        let mut original = package("www.amitu.com", "en");
+        original.translations = vec![package("hi.amitu.com", "hi")];
+        let mut hindi = package("hi.amitu.com", "hi");
+        hindi.translation_of = Box::new(Some(original.clone()));
+
+        let expected = "\n<link rel=\"alternate\" hreflang=\"en\" href=\"https://www.amitu.com/blog/\" />\
+            \n<link rel=\"alternate\" hreflang=\"hi\" href=\"https://hi.amitu.com/blog/\" />\
+            \n<link rel=\"alternate\" hreflang=\"x-default\" href=\"https://www.amitu.com/blog/\" />";
+        assert_eq!(super::hreflang_links(&original, "blog/"), expected);
+        assert_eq!(super::hreflang_links(&hindi, "/blog/"), expected);
+        assert_eq!(super::hreflang_links(&original, "-/www.amitu.com/x/"), "");
+        assert_eq!(
+            super::hreflang_links(&package("www.amitu.com", "en"), "blog/"),
+            ""
+        );
+    }
+
+    #[test]
+    fn is_document_path() {
+        fpm::test_utils::block_on(async {
+            let package = fpm::test_utils::TempPackage::new("languages");
+            package.write("index.ftd", "-- ftd.text: hello\n");
+            package.write("blog/index.ftd", "-- ftd.text: blog\n");
+            let config = package.config().await;
+
+            assert!(super::is_document_path(&config, "/"));
+            assert!(super::is_document_path(&config, "blog/"));
+            assert!(!super::is_document_path(&config, "-/amitu.com/blog/"));
+            assert!(!super::is_document_path(&config, "-/fpm.dev/assets/"));
+            // passed on to the endpoint, if the package has one
+            assert!(!super::is_document_path(&config, "api/todos/"));
+        });
+    }
+}
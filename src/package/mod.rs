@@ -1,6 +1,8 @@
 pub mod app;
 pub mod dependency;
+pub mod languages;
 pub mod package_doc;
+pub mod redirects;
 pub mod user_group;
 
 #[derive(Debug, Clone)]
@@ -40,9 +42,17 @@ pub struct Package {
     pub sitemap: Option<fpm::sitemap::Sitemap>,
     pub sitemap_temp: Option<fpm::sitemap::SitemapTemp>,
 
+    /// `auto-sitemap: true` generates the sitemap from the directory tree of the package if it
+    /// has no `fpm.sitemap`, see `fpm::sitemap::auto`
+    pub auto_sitemap: bool,
+
     pub dynamic_urls: Option<fpm::sitemap::DynamicUrls>,
     pub dynamic_urls_temp: Option<fpm::sitemap::DynamicUrlsTemp>,
 
+    /// `redirects` are the rules declared in `fpm.redirects`, old urls which should be sent
+    /// to their new location.
+    pub redirects: Vec<redirects::Redirect>,
+
     /// Optional path for favicon icon to be used.
     ///
     /// By default if any file favicon.* is present in package and favicon is not specified
@@ -90,8 +100,10 @@ impl Package {
             groups: std::collections::BTreeMap::new(),
             sitemap_temp: None,
             sitemap: None,
+            auto_sitemap: false,
             dynamic_urls: None,
             dynamic_urls_temp: None,
+            redirects: vec![],
             favicon: None,
             endpoint: None,
             backend: false,
@@ -582,6 +594,11 @@ impl Package {
         package.fonts = fpm_doc.get("fpm#font")?;
         package.sitemap_temp = fpm_doc.get("fpm#sitemap")?;
         package.dynamic_urls_temp = fpm_doc.get("fpm#dynamic-urls")?;
+        package.redirects =
+            match fpm_doc.get::<Option<redirects::RedirectsTemp>>("fpm#redirects")? {
+                Some(redirects) => redirects.into_redirects()?,
+                None => vec![],
+            };
 
         // TODO: resolve group dependent packages, there may be imported group from foreign package
         //   We need to make sure to resolve that package as well before moving ahead
@@ -688,6 +705,8 @@ pub(crate) struct PackageTemp {
     pub backend_headers: Option<Vec<BackendHeader>>,
     #[serde(rename = "icon")]
     pub icon: Option<ftd::ImageSrc>,
+    #[serde(rename = "auto-sitemap")]
+    pub auto_sitemap: bool,
 }
 
 impl PackageTemp {
@@ -723,8 +742,10 @@ impl PackageTemp {
             groups: std::collections::BTreeMap::new(),
             sitemap: None,
             sitemap_temp: None,
+            auto_sitemap: self.auto_sitemap,
             dynamic_urls: None,
             dynamic_urls_temp: None,
+            redirects: vec![],
             favicon: self.favicon,
             endpoint: self.endpoint,
             backend: self.backend,
@@ -0,0 +1,343 @@
+// -- fpm.redirects:
+//
+// /old-url/: /new-url/
+// /blog/<string:slug>/: /posts/<slug>/
+// status: 302
+//
+// Every line is `<from>: <to>`, `<from>` can contain path parameters the same way
+// `fpm.dynamic-urls` does, and `<to>` can refer them by name. An optional `status:` line
+// following a rule sets its status code, default is 301.
+
+#[derive(serde::Deserialize, Debug, Clone)]
+pub struct RedirectsTemp {
+    #[serde(rename = "redirects-body")]
+    pub body: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Redirect {
+    pub from: String,
+    pub to: String,
+    pub status: u16,
+    /// [(type, name)] of the path parameters in `from`, like `fpm.dynamic-urls`
+    pub path_parameters: Vec<(String, String)>,
+}
+
+impl RedirectsTemp {
+    pub fn into_redirects(self) -> fpm::Result<Vec<Redirect>> {
+        let mut redirects: Vec<Redirect> = vec![];
+        for (idx, line) in self.body.split('\n').enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with(";;") {
+                continue;
+            }
+
+            if let Some(status) = line.strip_prefix("status:") {
+                let redirect = redirects.last_mut().ok_or_else(|| parse_error(idx, line))?;
+                redirect.status = match status.trim().parse() {
+                    Ok(s @ (301 | 302 | 307 | 308)) => s,
+                    _ => return Err(parse_error(idx, line)),
+                };
+                continue;
+            }
+
+            let (from, to) = split_rule(line).ok_or_else(|| parse_error(idx, line))?;
+            redirects.push(Redirect {
+                path_parameters: fpm::sitemap::utils::parse_path_params(from.as_str()),
+                from,
+                to,
+                status: 301,
+            });
+        }
+        return Ok(redirects);
+
+        fn parse_error(idx: usize, line: &str) -> fpm::Error {
+            fpm::Error::PackageError {
+                message: format!(
+                    "fpm.redirects: invalid line {}: `{}`, expected `<from>: <to>` or `status: <301|302|307|308>`",
+                    idx + 1,
+                    line
+                ),
+            }
+        }
+    }
+}
+
+/// `<from>: <to>`, the separator is the first `: ` which is not inside a path parameter
+fn split_rule(line: &str) -> Option<(String, String)> {
+    let mut depth = 0;
+    for (idx, c) in line.char_indices() {
+        match c {
+            '<' => depth += 1,
+            '>' => depth -= 1,
+            ':' if depth == 0 && line[idx + 1..].starts_with(' ') => {
+                let from = line[..idx].trim();
+                let to = line[idx + 1..].trim();
+                if from.is_empty() || to.is_empty() {
+                    return None;
+                }
+                return Some((from.to_string(), to.to_string()));
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+impl Redirect {
+    /// Returns the url to redirect to, if `path` matches `self.from`
+    pub fn target(&self, path: &str) -> Option<String> {
+        if self.path_parameters.is_empty() {
+            return if fpm::utils::ids_matches(self.from.as_str(), path) {
+                Some(self.to.to_string())
+            } else {
+                None
+            };
+        }
+
+        let request_attrs: Vec<&str> = path.trim_matches('/').split('/').collect();
+        let rule_attrs: Vec<&str> = self.from.trim_matches('/').split('/').collect();
+        if request_attrs.len() != rule_attrs.len() {
+            return None;
+        }
+
+        let mut to = self.to.to_string();
+        for (value, attr) in request_attrs.into_iter().zip(rule_attrs) {
+            match fpm::sitemap::utils::parse_path_params(attr).first() {
+                Some((kind, name)) => {
+                    let valid = match kind.as_str() {
+                        "integer" => value.parse::<i64>().is_ok(),
+                        "decimal" => value.parse::<f64>().is_ok(),
+                        "boolean" => value.parse::<bool>().is_ok(),
+                        _ => !value.is_empty(),
+                    };
+                    if !valid {
+                        return None;
+                    }
+                    to = to.replace(format!("<{}>", name).as_str(), value);
+                }
+                None if attr.eq(value) => {}
+                None => return None,
+            }
+        }
+        Some(to)
+    }
+
+    /// Netlify style `_redirects` line: `/blog/:slug/ /posts/:slug/ 301`
+    pub(crate) fn to_redirects_file_rule(&self) -> String {
+        let mut from = self.from.to_string();
+        let mut to = self.to.to_string();
+        for (_, name) in self.path_parameters.iter() {
+            to = to.replace(
+                format!("<{}>", name).as_str(),
+                format!(":{}", name).as_str(),
+            );
+        }
+        for attr in self.from.trim_matches('/').split('/') {
+            if let Some((_, name)) = fpm::sitemap::utils::parse_path_params(attr).first() {
+                from = from.replace(attr, format!(":{}", name).as_str());
+            }
+        }
+        format!("{} {} {}", from, to, self.status)
+    }
+
+    /// nginx `rewrite` directive, meant to be `include`d in a `server` block
+    pub(crate) fn to_nginx_rule(&self) -> String {
+        let mut pattern = String::new();
+        let mut to = self.to.to_string();
+        let mut group = 0;
+        for attr in self.from.trim_matches('/').split('/') {
+            if attr.is_empty() {
+                continue;
+            }
+            pattern.push('/');
+            match fpm::sitemap::utils::parse_path_params(attr).first() {
+                Some((kind, name)) => {
+                    group += 1;
+                    pattern.push_str(match kind.as_str() {
+                        "integer" => "(-?[0-9]+)",
+                        "decimal" => "(-?[0-9.]+)",
+                        "boolean" => "(true|false)",
+                        _ => "([^/]+)",
+                    });
+                    to = to.replace(
+                        format!("<{}>", name).as_str(),
+                        format!("${}", group).as_str(),
+                    );
+                }
+                None => pattern.push_str(regex::escape(attr).as_str()),
+            }
+        }
+        format!(
+            "rewrite ^{}/?$ {} {};",
+            pattern,
+            to,
+            if self.status == 301 || self.status == 308 {
+                "permanent"
+            } else {
+                "redirect"
+            }
+        )
+    }
+
+    /// html page for static hosting, redirects using meta refresh
+    pub(crate) fn to_html(&self) -> String {
+        format!(
+            indoc::indoc! {"
+                <!DOCTYPE html>
+                <html>
+                <head>
+                <meta charset=\"utf-8\">
+                <title>Redirecting to {to}</title>
+                <meta http-equiv=\"refresh\" content=\"0; url={to}\">
+                <link rel=\"canonical\" href=\"{to}\">
+                </head>
+                <body>
+                <a href=\"{to}\">Click here if you are not redirected.</a>
+                </body>
+                </html>
+            "},
+            to = self.to
+        )
+    }
+}
+
+pub fn find(redirects: &[Redirect], path: &str) -> Option<(String, u16)> {
+    redirects
+        .iter()
+        .find_map(|r| r.target(path).map(|to| (to, r.status)))
+}
+
+/// `fpm build` creates a meta refresh html page for every redirect without path parameters,
+/// and writes all of them in `_redirects` and `nginx-redirects.conf` so the static host can
+/// do proper redirects with status code.
+pub(crate) async fn build(config: &fpm::Config) -> fpm::Result<()> {
+    use itertools::Itertools;
+
+    let redirects = &config.package.redirects;
+    if redirects.is_empty() {
+        return Ok(());
+    }
+
+    for redirect in redirects.iter().filter(|r| r.path_parameters.is_empty()) {
+        let path = config
+            .build_dir()
+            .join(redirect.from.trim_matches('/'))
+            .join("index.html");
+        fpm::utils::update(path, redirect.to_html().as_bytes()).await?;
+    }
+
+    fpm::utils::update(
+        config.build_dir().join("_redirects"),
+        redirects
+            .iter()
+            .map(|r| r.to_redirects_file_rule())
+            .join("\n")
+            .as_bytes(),
+    )
+    .await?;
+
+    fpm::utils::update(
+        config.build_dir().join("nginx-redirects.conf"),
+        redirects
+            .iter()
+            .map(|r| r.to_nginx_rule())
+            .join("\n")
+            .as_bytes(),
+    )
+    .await
+}
+
+/// Adds `<from>: <to>` to `fpm.redirects` of the package, creating the section if needed.
+/// Used when a document is renamed so the old url keeps working.
+pub(crate) async fn add(config: &fpm::Config, from: &str, to: &str) -> fpm::Result<()> {
+    let fpm_path = config.root.join("FPM.ftd");
+    let content = tokio::fs::read_to_string(&fpm_path).await?;
+    let rule = format!("{}: {}", from, to);
+
+    let mut lines: Vec<String> = content.split('\n').map(ToString::to_string).collect();
+    let content = match lines.iter().position(|l| l.trim().eq("-- fpm.redirects:")) {
+        Some(start) => {
+            // the section ends where the next section starts
+            let end = lines
+                .iter()
+                .enumerate()
+                .skip(start + 1)
+                .find(|(_, l)| l.starts_with("-- "))
+                .map(|(idx, _)| idx)
+                .unwrap_or(lines.len());
+            let mut insert_at = end;
+            while insert_at > start + 1 && lines[insert_at - 1].trim().is_empty() {
+                insert_at -= 1;
+            }
+            if insert_at == start + 1 {
+                // section has no body yet, body starts after an empty line
+                lines.insert(insert_at, "".to_string());
+                insert_at += 1;
+            }
+            lines.insert(insert_at, rule);
+            lines.join("\n")
+        }
+        None => format!("{}\n\n-- fpm.redirects:\n\n{}\n", content.trim_end(), rule),
+    };
+
+    fpm::utils::update(&fpm_path, content.as_bytes()).await
+}
+
+#[cfg(test)]
+mod tests {
+    fn parse(body: &str) -> Vec<super::Redirect> {
+        super::RedirectsTemp {
+            body: body.to_string(),
+        }
+        .into_redirects()
+        .unwrap()
+    }
+
+    #[test]
+    fn static_redirect() {
+        let redirects = parse("/old/: /new/\n/temp/: https://fpm.dev/\nstatus: 302");
+        assert_eq!(
+            super::find(&redirects, "/old/"),
+            Some(("/new/".to_string(), 301))
+        );
+        assert_eq!(
+            super::find(&redirects, "temp"),
+            Some(("https://fpm.dev/".to_string(), 302))
+        );
+        assert_eq!(super::find(&redirects, "/other/"), None);
+    }
+
+    #[test]
+    fn redirect_with_path_params() {
+        let redirects = parse("/blog/<string:slug>/<integer:id>/: /posts/<slug>-<id>/");
+        assert_eq!(
+            super::find(&redirects, "/blog/hello/12/"),
+            Some(("/posts/hello-12/".to_string(), 301))
+        );
+        assert_eq!(super::find(&redirects, "/blog/hello/world/"), None);
+        assert_eq!(super::find(&redirects, "/news/hello/12/"), None);
+    }
+
+    #[test]
+    fn rule_files() {
+        let redirects = parse("/blog/<string:slug>/: /posts/<slug>/");
+        assert_eq!(
+            redirects[0].to_redirects_file_rule(),
+            "/blog/:slug/ /posts/:slug/ 301"
+        );
+        assert_eq!(
+            redirects[0].to_nginx_rule(),
+            "rewrite ^/blog/([^/]+)/?$ /posts/$1/ permanent;"
+        );
+    }
+
+    #[test]
+    fn invalid_status() {
+        assert!(super::RedirectsTemp {
+            body: "/old/: /new/\nstatus: 200".to_string(),
+        }
+        .into_redirects()
+        .is_err());
+    }
+}
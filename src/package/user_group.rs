@@ -461,7 +461,16 @@ pub async fn access_identities(
     // github-starred: fpm-lang/ftd
     // discord-server: abrark.com
     // github-watches: fpm-lang/ftd
-    match fpm::auth::get_auth_identities(req.cookies(), sitemap_identities.as_slice()).await {
+    request_identities(req, sitemap_identities.as_slice()).await
+}
+
+/// The identities among `identities` the reader of `req` has, none if the request has no
+/// logged in user
+pub async fn request_identities(
+    req: &fpm::http::Request,
+    identities: &[UserIdentity],
+) -> fpm::Result<Vec<UserIdentity>> {
+    match fpm::auth::get_auth_identities(req.cookies(), identities).await {
         Ok(ids) => Ok(ids),
         Err(fpm::Error::GenericError(_err)) => Ok(vec![]),
         e => e,
@@ -0,0 +1,235 @@
+/// Widths of the resized variants generated for every raster image. Widths larger than the
+/// original image are skipped, the original width is always included.
+const VARIANT_WIDTHS: &[u32] = &[320, 640, 1024, 1600];
+
+/// Bump this if the way variants or metadata are generated changes, so the old cache entries
+/// are not used anymore.
+const CACHE_VERSION: u32 = 1;
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct ImageData {
+    /// The url to use in `ftd.image`, picked from `variants` based on requested width
+    pub src: String,
+    /// The url of the original image
+    pub original: String,
+    pub width: u32,
+    pub height: u32,
+    pub blurhash: String,
+    pub variants: Vec<Variant>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct Variant {
+    pub src: String,
+    pub width: u32,
+    pub height: u32,
+    pub format: String,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+struct Meta {
+    version: u32,
+    width: u32,
+    height: u32,
+    blurhash: String,
+    variants: Vec<Variant>,
+}
+
+/// Variants are generated once per image content, and stored in
+/// `.packages/.cache/images/<sha256 of content>/`, along with `meta.json` containing the
+/// metadata. They are served from `/-/images/<sha256 of content>/<file>` by `fpm serve`, and
+/// copied to `.build/-/images/` by `fpm build`.
+fn cache_dir(config: &fpm::Config) -> camino::Utf8PathBuf {
+    config.root.join(".packages").join(".cache").join("images")
+}
+
+pub(crate) fn is_responsive(id: &str) -> bool {
+    matches!(
+        fpm::utils::get_extension(id).ok().as_deref(),
+        Some("jpg" | "jpeg" | "png")
+    )
+}
+
+/// Returns the metadata and variants of the image `id` of the current package, generating
+/// them if they are not in the cache already.
+pub(crate) fn get(config: &fpm::Config, id: &str) -> fpm::Result<ImageData> {
+    let id = id.trim_start_matches('/');
+    if !is_responsive(id) {
+        return Err(fpm::Error::UsageError {
+            message: format!(
+                "responsive-image: `{}` is not a jpg or png image, other images are served as is",
+                id
+            ),
+        });
+    }
+    let content = std::fs::read(config.root.join(id))?;
+    let hash = content_hash(content.as_slice());
+    let meta = match read_meta(config, hash.as_str()) {
+        Some(meta) => meta,
+        None => generate(config, hash.as_str(), content.as_slice())?,
+    };
+
+    Ok(ImageData {
+        src: format!("/-/{}/{}", config.package.name, id),
+        original: format!("/-/{}/{}", config.package.name, id),
+        width: meta.width,
+        height: meta.height,
+        blurhash: meta.blurhash,
+        variants: meta.variants,
+    })
+}
+
+impl ImageData {
+    /// Picks the smallest webp variant at least `width` wide, falls back to the largest one.
+    pub(crate) fn with_width(mut self, width: u32) -> ImageData {
+        let mut candidates: Vec<&Variant> = self
+            .variants
+            .iter()
+            .filter(|v| v.format.eq("webp"))
+            .collect();
+        candidates.sort_by_key(|v| v.width);
+        if let Some(variant) = candidates
+            .iter()
+            .find(|v| v.width >= width)
+            .or_else(|| candidates.last())
+        {
+            self.src = variant.src.to_string();
+        }
+        self
+    }
+}
+
+fn content_hash(content: &[u8]) -> String {
+    use sha2::Digest;
+    format!("{:x}", sha2::Sha256::digest(content))
+}
+
+fn read_meta(config: &fpm::Config, hash: &str) -> Option<Meta> {
+    let content = std::fs::read(cache_dir(config).join(hash).join("meta.json")).ok()?;
+    let meta: Meta = serde_json::from_slice(content.as_slice()).ok()?;
+    if meta.version != CACHE_VERSION {
+        return None;
+    }
+    Some(meta)
+}
+
+fn generate(config: &fpm::Config, hash: &str, content: &[u8]) -> fpm::Result<Meta> {
+    use image::GenericImageView;
+
+    let dir = cache_dir(config).join(hash);
+    std::fs::create_dir_all(&dir)?;
+
+    let format = image::guess_format(content)?;
+    let img = image::load_from_memory_with_format(content, format)?;
+    let (width, height) = img.dimensions();
+
+    let mut formats = vec![match format {
+        image::ImageFormat::Jpeg => ("jpg", image::ImageOutputFormat::Jpeg(80)),
+        _ => ("png", image::ImageOutputFormat::Png),
+    }];
+    formats.push(("webp", image::ImageOutputFormat::WebP));
+    #[cfg(feature = "avif")]
+    formats.push(("avif", image::ImageOutputFormat::Avif));
+
+    let mut widths: Vec<u32> = VARIANT_WIDTHS
+        .iter()
+        .copied()
+        .filter(|w| *w < width)
+        .collect();
+    widths.push(width);
+
+    let mut variants = vec![];
+    for w in widths {
+        let resized = if w == width {
+            img.clone()
+        } else {
+            img.resize(w, u32::MAX, image::imageops::FilterType::Lanczos3)
+        };
+        for (ext, output_format) in formats.iter() {
+            let file_name = format!("{}.{}", w, ext);
+            let mut buf = std::io::Cursor::new(vec![]);
+            resized.write_to(&mut buf, output_format.clone())?;
+            std::fs::write(dir.join(file_name.as_str()), buf.into_inner())?;
+            variants.push(Variant {
+                src: format!("/-/images/{}/{}", hash, file_name),
+                width: resized.width(),
+                height: resized.height(),
+                format: ext.to_string(),
+            });
+        }
+    }
+
+    let thumbnail = img.thumbnail(32, 32);
+    let meta = Meta {
+        version: CACHE_VERSION,
+        width,
+        height,
+        blurhash: blurhash::encode(
+            4,
+            3,
+            thumbnail.width(),
+            thumbnail.height(),
+            thumbnail.to_rgba8().into_raw().as_slice(),
+        ),
+        variants,
+    };
+    std::fs::write(dir.join("meta.json"), serde_json::to_vec_pretty(&meta)?)?;
+    Ok(meta)
+}
+
+/// Generates the variants of the image (if not cached) and copies them to
+/// `.build/-/images/<hash>/`
+pub(crate) async fn build(config: &fpm::Config, image: &fpm::Static) -> fpm::Result<()> {
+    if !is_responsive(image.id.as_str()) {
+        return Ok(());
+    }
+    let content = tokio::fs::read(image.base_path.join(image.id.as_str())).await?;
+    let hash = content_hash(content.as_slice());
+    if read_meta(config, hash.as_str()).is_none() {
+        generate(config, hash.as_str(), content.as_slice())?;
+    }
+
+    let build_dir = config
+        .build_dir()
+        .join("-")
+        .join("images")
+        .join(hash.as_str());
+    tokio::fs::create_dir_all(&build_dir).await?;
+    let mut entries = tokio::fs::read_dir(cache_dir(config).join(hash.as_str())).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let file_name = entry.file_name();
+        if file_name.eq("meta.json") {
+            continue;
+        }
+        tokio::fs::copy(
+            entry.path(),
+            build_dir.join(file_name.to_string_lossy().as_ref()),
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+/// Serves `/-/images/<hash>/<file>` from the cache
+pub(crate) async fn serve(config: &fpm::Config, path: &str) -> fpm::http::Response {
+    let file = match path.trim_matches('/').strip_prefix("-/images/") {
+        Some(file)
+            if !file.contains("..") && !file.ends_with("meta.json") && file.contains('/') =>
+        {
+            file
+        }
+        _ => return fpm::not_found!("FPM-Error: invalid image path: {}", path),
+    };
+
+    match tokio::fs::read(cache_dir(config).join(file)).await {
+        Ok(content) => actix_web::HttpResponse::Ok()
+            .content_type(mime_guess::from_path(file).first_or_octet_stream())
+            // the url contains the content hash, so the content never changes
+            .insert_header((
+                actix_web::http::header::CACHE_CONTROL,
+                "public, max-age=31536000, immutable",
+            ))
+            .body(content),
+        Err(e) => fpm::not_found!("FPM-Error: image not found: {}, {:?}", path, e),
+    }
+}
@@ -0,0 +1,425 @@
+// With `auto-sitemap: true` in `fpm.package` and no `-- fpm.sitemap:`, the sitemap is generated
+// from the directory tree of the package: the root documents are in the first section, every top
+// level directory is a section, its directories are subsections and the deeper ones are nested
+// toc items. The body is the same as a hand written `fpm.sitemap` body, `fpm sitemap generate`
+// prints it to start one from.
+
+// documents which are not pages of the package, they are not put in the sitemap
+pub(crate) const NOT_IN_SITEMAP: &[&str] = &["FPM.ftd", "401.ftd", "404.ftd", "500.ftd"];
+
+/// What a document says about itself in `-- fpm.info DOCUMENT_INFO:`, the title falls back to
+/// the heading with the highest priority
+#[derive(Debug, Default, PartialEq)]
+struct Info {
+    title: Option<String>,
+    order: Option<i64>,
+    skip: bool,
+    bury: bool,
+}
+
+impl Info {
+    fn parse(id: &str, content: &str) -> Info {
+        let mut info = Info::default();
+        let mut in_info = false;
+        // (level, title) of the heading with the highest priority so far
+        let mut heading: Option<(usize, String)> = None;
+        for line in content.lines() {
+            if id.ends_with(".md") {
+                let level = line.chars().take_while(|c| *c == '#').count();
+                let title = line[level..].trim();
+                if level > 0 && !title.is_empty() && heading.as_ref().map_or(true, |v| level < v.0)
+                {
+                    heading = Some((level, title.to_string()));
+                }
+                continue;
+            }
+            if let Some(section) = line.strip_prefix("-- ") {
+                in_info = section.trim().starts_with("fpm.info ");
+                if let Some((kind, caption)) = section.split_once(':') {
+                    let level = kind
+                        .trim()
+                        .rsplit('.')
+                        .next()
+                        .and_then(|v| v.strip_prefix('h'))
+                        .and_then(|v| v.parse::<usize>().ok());
+                    if let Some(level) = level {
+                        let caption = caption.trim();
+                        if !caption.is_empty() && heading.as_ref().map_or(true, |v| level < v.0) {
+                            heading = Some((level, caption.to_string()));
+                        }
+                    }
+                }
+                continue;
+            }
+            if !in_info {
+                continue;
+            }
+            if line.trim().is_empty() {
+                in_info = false;
+                continue;
+            }
+            let (key, value) = match line.split_once(':') {
+                Some((key, value)) => (key.trim(), value.trim()),
+                None => continue,
+            };
+            match key {
+                "title" if !value.is_empty() => info.title = Some(value.to_string()),
+                "order" => match value.parse::<i64>() {
+                    Ok(order) => info.order = Some(order),
+                    Err(_) => {
+                        fpm::warning!("{}: `order: {}` is not a number", id, value);
+                    }
+                },
+                "skip" => info.skip = value == "true",
+                "bury" => info.bury = value == "true",
+                _ => {}
+            }
+        }
+        if info.title.is_none() {
+            info.title = heading.map(|(_, title)| title);
+        }
+        info
+    }
+}
+
+#[derive(Debug, Default)]
+struct Node {
+    /// the index document of the directory, or the document itself
+    document: Option<(String, Info)>,
+    /// documents and directories in the directory, by name
+    children: std::collections::BTreeMap<String, Node>,
+}
+
+impl Node {
+    fn is_directory(&self) -> bool {
+        !self.children.is_empty()
+            || self
+                .document
+                .as_ref()
+                .map(|(id, _)| is_index(id))
+                .unwrap_or(false)
+    }
+
+    fn order(&self) -> Option<i64> {
+        self.document.as_ref().and_then(|(_, info)| info.order)
+    }
+
+    /// The children, the ones with `order` first and the others by name
+    fn sorted_children(&self) -> Vec<(&String, &Node)> {
+        let mut children: Vec<(&String, &Node)> = self.children.iter().collect();
+        children.sort_by_key(|(name, node)| (node.order().unwrap_or(i64::MAX), name.to_string()));
+        children
+    }
+}
+
+fn is_index(id: &str) -> bool {
+    let name = id.rsplit('/').next().unwrap_or(id);
+    name == "index.ftd" || name == "index.md" || name == "README.md"
+}
+
+/// `Getting Started` for `getting-started`, the title of a document or directory saying nothing
+/// about itself
+fn title_from_name(name: &str) -> String {
+    name.split(['-', '_'])
+        .filter(|v| !v.is_empty())
+        .map(|v| {
+            let mut chars = v.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect::<String>(),
+                None => "".to_string(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn tree(documents: &[(String, String)]) -> Node {
+    let mut root = Node::default();
+    for (id, content) in documents {
+        let id = id.trim_start_matches('/');
+        if NOT_IN_SITEMAP.contains(&id) || id.starts_with('.') || id.starts_with("-/") {
+            continue;
+        }
+        let mut parts: Vec<&str> = id.split('/').collect();
+        if is_index(id) {
+            parts.pop();
+        } else if let Some(name) = parts.pop() {
+            // `linux.ftd` is the document of the directory `linux/` if there is one
+            parts.push(
+                name.strip_suffix(".ftd")
+                    .or_else(|| name.strip_suffix(".md"))
+                    .unwrap_or(name),
+            );
+        }
+        let mut node = &mut root;
+        for part in parts {
+            node = node.children.entry(part.to_string()).or_default();
+        }
+        // `index.ftd` wins over `README.md` of the same directory
+        if node.document.is_none() || id.ends_with(".ftd") {
+            node.document = Some((id.to_string(), Info::parse(id, content)));
+        }
+    }
+    root
+}
+
+/// One entry of the sitemap body, `prefix` is `#`, `##` or the indented `-`
+fn entry(body: &mut String, prefix: &str, name: &str, node: &Node) {
+    let indent = " ".repeat(prefix.len() - prefix.trim_start().len() + 2);
+    let title = node
+        .document
+        .as_ref()
+        .and_then(|(_, info)| info.title.clone())
+        .unwrap_or_else(|| title_from_name(name));
+    match node.document.as_ref() {
+        // a colon in the title can not be told from the one before the url
+        Some((id, _)) if title.contains(':') => body.push_str(
+            format!(
+                "{} {}\n{}url: {}\n",
+                prefix,
+                title,
+                indent,
+                url(id.as_str())
+            )
+            .as_str(),
+        ),
+        Some((id, _)) => {
+            body.push_str(format!("{} {}: {}\n", prefix, title, url(id.as_str())).as_str())
+        }
+        None => body.push_str(format!("{} {}\n", prefix, title).as_str()),
+    }
+    if let Some((_, info)) = node.document.as_ref() {
+        if info.skip {
+            body.push_str(format!("{}skip: true\n", indent).as_str());
+        }
+        if info.bury {
+            body.push_str(format!("{}bury: true\n", indent).as_str());
+        }
+    }
+}
+
+fn url(id: &str) -> String {
+    format!("/{}", fpm::utils::id_to_path(id).trim_start_matches('/'))
+}
+
+fn toc(body: &mut String, depth: usize, name: &str, node: &Node) {
+    entry(
+        body,
+        format!("{}-", " ".repeat(depth * 2)).as_str(),
+        name,
+        node,
+    );
+    for (name, child) in node.sorted_children() {
+        toc(body, depth + 1, name, child);
+    }
+}
+
+/// The files of `node` as toc items followed by its directories as `## ` subsections
+fn section_contents(body: &mut String, node: &Node) {
+    let children = node.sorted_children();
+    let (directories, files): (Vec<_>, Vec<_>) =
+        children.into_iter().partition(|(_, v)| v.is_directory());
+    if !files.is_empty() {
+        body.push('\n');
+    }
+    for (name, file) in files {
+        toc(body, 0, name, file);
+    }
+    for (name, directory) in directories {
+        body.push('\n');
+        entry(body, "##", name, directory);
+        if !directory.children.is_empty() {
+            body.push('\n');
+        }
+        for (name, child) in directory.sorted_children() {
+            toc(body, 0, name, child);
+        }
+    }
+}
+
+/// The `fpm.sitemap` body of the package with `documents`, (id, content) of every document
+pub(crate) fn generate(documents: &[(String, String)]) -> String {
+    let root = tree(documents);
+    let mut body = "".to_string();
+    let (directories, files): (Vec<_>, Vec<_>) = root
+        .sorted_children()
+        .into_iter()
+        .partition(|(_, v)| v.is_directory());
+
+    if root.document.is_some() || !files.is_empty() {
+        entry(&mut body, "#", "Home", &root);
+        if !files.is_empty() {
+            body.push('\n');
+        }
+        for (name, file) in files {
+            toc(&mut body, 0, name, file);
+        }
+    }
+    for (name, directory) in directories {
+        if !body.is_empty() {
+            body.push('\n');
+        }
+        entry(&mut body, "#", name, directory);
+        section_contents(&mut body, directory);
+    }
+    body
+}
+
+/// (path, modified time) of every file of a package, the generated body is reused till one of
+/// them changes
+type Fingerprint = Vec<(camino::Utf8PathBuf, Option<std::time::SystemTime>)>;
+
+/// The last generated body by package root, `fpm serve` reads the config on every request
+static BODIES: once_cell::sync::Lazy<
+    antidote::Mutex<std::collections::HashMap<camino::Utf8PathBuf, (Fingerprint, String)>>,
+> = once_cell::sync::Lazy::new(Default::default);
+
+/// The `fpm.sitemap` body generated from the documents of the package of `config`, it is only
+/// generated again if a file of the package is added, removed or modified
+pub async fn body(config: &fpm::Config) -> fpm::Result<String> {
+    let mut fingerprint: Fingerprint = config
+        .get_all_file_paths1(&config.package, true)?
+        .into_iter()
+        .map(|v| {
+            let modified = std::fs::metadata(&v).and_then(|m| m.modified()).ok();
+            (v, modified)
+        })
+        .collect();
+    fingerprint.sort();
+    if let Some((cached, body)) = BODIES.lock().get(&config.root) {
+        if *cached == fingerprint {
+            return Ok(body.to_string());
+        }
+    }
+    let documents: Vec<(String, String)> = config
+        .get_files(&config.package)
+        .await?
+        .into_iter()
+        .filter_map(|v| match v {
+            fpm::File::Ftd(doc) | fpm::File::Markdown(doc) => Some((doc.id, doc.content)),
+            _ => None,
+        })
+        .collect();
+    let body = generate(&documents);
+    BODIES
+        .lock()
+        .insert(config.root.clone(), (fingerprint, body.clone()));
+    Ok(body)
+}
+
+#[cfg(test)]
+mod tests {
+    fn documents(documents: &[(&str, &str)]) -> Vec<(String, String)> {
+        documents
+            .iter()
+            .map(|(id, content)| (id.to_string(), content.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn info() {
+        assert_eq!(
+            super::Info::parse(
+                "foo.ftd",
+                indoc::indoc! {"
+                    -- import: fpm
+
+                    -- fpm.info DOCUMENT_INFO:
+                    title: Foo Title
+                    order: 2
+                    bury: true
+
+                    -- ft.h0: Foo Heading Title
+                "}
+            ),
+            super::Info {
+                title: Some("Foo Title".to_string()),
+                order: Some(2),
+                skip: false,
+                bury: true,
+            }
+        );
+        assert_eq!(
+            super::Info::parse("foo.ftd", "-- ft.h2: Second\n\n-- ft.h1: First\n")
+                .title
+                .as_deref(),
+            Some("First")
+        );
+        assert_eq!(
+            super::Info::parse("foo.md", "Intro\n\n## Second\n\n# First\n")
+                .title
+                .as_deref(),
+            Some("First")
+        );
+    }
+
+    #[test]
+    fn generate() {
+        let documents = documents(&[
+            ("FPM.ftd", "-- import: fpm\n"),
+            ("index.ftd", "-- ds.h0: Amit Upadhyay\n"),
+            ("about.ftd", "-- ds.h0: About Me\n"),
+            ("404.ftd", "-- ds.h0: Not Found\n"),
+            (
+                "blog/index.ftd",
+                "-- fpm.info DOCUMENT_INFO:\ntitle: Blog\n",
+            ),
+            ("blog/first-post.ftd", "-- ds.h0: First: A Start\n"),
+            ("blog/draft.ftd", "-- fpm.info DOCUMENT_INFO:\nskip: true\n"),
+            ("blog/2022/index.ftd", "-- ds.h0: Posts of 2022\n"),
+            ("blog/2022/new-year.md", "# Happy New Year\n"),
+            ("docs/setup/index.ftd", "-- ds.h0: Setup\n"),
+            ("docs/setup/linux.ftd", "-- ds.h1: Linux\n"),
+            ("docs/setup/linux/arch.ftd", "-- ds.h1: Arch\n"),
+            (
+                "docs/intro.ftd",
+                "-- fpm.info DOCUMENT_INFO:\norder: 1\n\n-- ds.h0: Introduction\n",
+            ),
+        ]);
+        assert_eq!(
+            super::generate(&documents),
+            indoc::indoc! {"
+                # Amit Upadhyay: /
+
+                - About Me: /about/
+
+                # Blog: /blog/
+
+                - Draft: /blog/draft/
+                  skip: true
+                - First: A Start
+                  url: /blog/first-post/
+
+                ## Posts of 2022: /blog/2022/
+
+                - Happy New Year: /blog/2022/new-year/
+
+                # Docs
+
+                - Introduction: /docs/intro/
+
+                ## Setup: /docs/setup/
+
+                - Linux: /docs/setup/linux/
+                  - Arch: /docs/setup/linux/arch/
+            "}
+        );
+    }
+
+    #[test]
+    fn body() {
+        fpm::test_utils::block_on(async {
+            let package = fpm::test_utils::TempPackage::new("auto-sitemap");
+            package.write("index.ftd", "-- ds.h0: Home\n");
+            let config = package.config().await;
+            assert_eq!(super::body(&config).await.unwrap(), "# Home: /\n");
+            assert_eq!(super::body(&config).await.unwrap(), "# Home: /\n");
+            // a new document is in the sitemap of the next request
+            package.write("about.ftd", "-- ds.h0: About\n");
+            assert_eq!(
+                super::body(&config).await.unwrap(),
+                "# Home: /\n\n- About: /about/\n"
+            );
+        });
+    }
+}
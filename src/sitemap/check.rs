@@ -0,0 +1,391 @@
+// `Sitemap::parse` only checks the syntax of `fpm.sitemap`, these are the checks of what it
+// refers to, reported by `fpm check-sitemap`.
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Problem {
+    /// A document of the package in no entry of the sitemap or the dynamic urls
+    Orphan { document: String },
+    /// An entry whose `document`, or id, is no document of the package
+    Missing { id: String, document: String },
+    /// Entries with the same url, an entry repeating the url of an entry it is nested in is fine
+    Duplicate { id: String, titles: Vec<String> },
+    /// `readers` or `writers` of an entry naming no `fpm.user-group`
+    UndefinedGroup { id: String, group: String },
+    /// A dynamic url matching the url of a sitemap entry. The sitemap entry is served there if it
+    /// has a `document`, else the url resolves to the document of the dynamic url.
+    Shadowed {
+        pattern: String,
+        id: String,
+        dynamic_wins: bool,
+    },
+}
+
+impl std::fmt::Display for Problem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Problem::Orphan { document } => {
+                write!(f, "{}: not in the sitemap", document)
+            }
+            Problem::Missing { id, document } if id == document => {
+                write!(f, "`{}`: no such document", id)
+            }
+            Problem::Missing { id, document } => {
+                write!(f, "`{}`: no such document `{}`", id, document)
+            }
+            Problem::Duplicate { id, titles } => write!(
+                f,
+                "`{}` is the url of more than one entry: {}",
+                id,
+                titles.join(", ")
+            ),
+            Problem::UndefinedGroup { id, group } => write!(
+                f,
+                "`{}`: no user group `{}`, define it with `-- fpm.user-group:`",
+                id, group
+            ),
+            Problem::Shadowed {
+                pattern,
+                id,
+                dynamic_wins: false,
+            } => write!(
+                f,
+                "dynamic url `{}` matches `{}` of the sitemap, the `document` of the sitemap entry is served",
+                pattern, id
+            ),
+            Problem::Shadowed {
+                pattern,
+                id,
+                dynamic_wins: true,
+            } => write!(
+                f,
+                "dynamic url `{}` matches `{}` of the sitemap, which has no `document`, the dynamic url is served",
+                pattern, id
+            ),
+        }
+    }
+}
+
+struct Entry<'a> {
+    title: Option<&'a str>,
+    id: &'a str,
+    document: Option<&'a str>,
+    readers: &'a [String],
+    writers: &'a [String],
+    is_dynamic: bool,
+    /// ids of the entries it is nested in
+    ancestors: Vec<&'a str>,
+}
+
+impl<'a> Entry<'a> {
+    /// The id, if it is the url of a document of `package_name`. An entry with only a title, an
+    /// external link or a document of another package has none.
+    fn local_id(&self, package_name: &str) -> Option<String> {
+        let id = self.id.trim();
+        if id.is_empty() || self.title == Some(self.id) {
+            return None;
+        }
+        let id = id.rsplit_once('#').map(|(v, _)| v).unwrap_or(id);
+        if id.starts_with("-/") {
+            return fpm::config::utils::trim_package_name(id, package_name)
+                .filter(|v| v.is_empty() || v.starts_with('/'));
+        }
+        if fpm::http::url_regex().is_match(id) {
+            return None;
+        }
+        Some(id.to_string())
+    }
+}
+
+fn entries(sections: &[super::section::Section]) -> Vec<Entry> {
+    fn toc_entries<'a>(
+        toc: &'a super::toc::TocItem,
+        ancestors: &[&'a str],
+        entries: &mut Vec<Entry<'a>>,
+    ) {
+        entries.push(Entry {
+            title: toc.title.as_deref(),
+            id: toc.id.as_str(),
+            document: toc.document.as_deref(),
+            readers: &toc.readers,
+            writers: &toc.writers,
+            is_dynamic: !toc.path_parameters.is_empty(),
+            ancestors: ancestors.to_vec(),
+        });
+        let mut ancestors = ancestors.to_vec();
+        ancestors.push(toc.id.as_str());
+        for child in toc.children.iter() {
+            toc_entries(child, &ancestors, entries);
+        }
+    }
+
+    let mut entries = vec![];
+    for section in sections {
+        entries.push(Entry {
+            title: section.title.as_deref(),
+            id: section.id.as_str(),
+            document: section.document.as_deref(),
+            readers: &section.readers,
+            writers: &section.writers,
+            is_dynamic: !section.path_parameters.is_empty(),
+            ancestors: vec![],
+        });
+        for subsection in section.subsections.iter() {
+            let mut ancestors = vec![section.id.as_str()];
+            if let Some(id) = subsection.id.as_ref() {
+                entries.push(Entry {
+                    title: subsection.title.as_deref(),
+                    id: id.as_str(),
+                    document: subsection.document.as_deref(),
+                    readers: &subsection.readers,
+                    writers: &subsection.writers,
+                    is_dynamic: !subsection.path_parameters.is_empty(),
+                    ancestors: ancestors.clone(),
+                });
+                ancestors.push(id.as_str());
+            }
+            for toc in subsection.toc.iter() {
+                toc_entries(toc, &ancestors, &mut entries);
+            }
+        }
+    }
+    entries
+}
+
+/// Problems of the `sitemap` and `dynamic_urls` of the package `package_name`. `documents` are
+/// the ids of the documents of the package, `resolve` gives the document id of an entry id or
+/// `document`, if the package has one, and `groups` are the ids of the user groups.
+pub(crate) fn check(
+    sitemap: Option<&super::Sitemap>,
+    dynamic_urls: Option<&super::DynamicUrls>,
+    package_name: &str,
+    documents: &[String],
+    groups: &[&str],
+    resolve: impl Fn(&str) -> Option<String>,
+) -> Vec<Problem> {
+    fn normalize(id: &str) -> String {
+        id.trim_matches('/').to_string()
+    }
+
+    let mut problems = vec![];
+    let static_entries = sitemap.map(|v| entries(&v.sections)).unwrap_or_default();
+    let dynamic_entries = dynamic_urls
+        .map(|v| entries(&v.sections))
+        .unwrap_or_default();
+
+    let mut used = std::collections::HashSet::new();
+    for entry in static_entries.iter().chain(dynamic_entries.iter()) {
+        let target = match (entry.document, entry.local_id(package_name)) {
+            (Some(document), _) => document.to_string(),
+            (None, Some(id)) if !entry.is_dynamic => id,
+            _ => continue,
+        };
+        match resolve(target.as_str()) {
+            Some(document) => {
+                used.insert(document);
+            }
+            None => problems.push(Problem::Missing {
+                id: entry.id.to_string(),
+                document: target,
+            }),
+        }
+    }
+    for document in documents {
+        if !used.contains(document) {
+            problems.push(Problem::Orphan {
+                document: document.to_string(),
+            });
+        }
+    }
+
+    let mut by_url: std::collections::BTreeMap<String, Vec<&Entry>> = Default::default();
+    for entry in static_entries.iter() {
+        if let Some(id) = entry.local_id(package_name) {
+            by_url
+                .entry(normalize(id.as_str()))
+                .or_default()
+                .push(entry);
+        }
+    }
+    for (id, same_url) in by_url {
+        let titles: Vec<String> = same_url
+            .iter()
+            .filter(|v| !v.ancestors.iter().any(|a| normalize(a) == id))
+            .map(|v| v.title.unwrap_or(v.id).to_string())
+            .collect();
+        if titles.len() > 1 {
+            problems.push(Problem::Duplicate {
+                id: format!("/{}/", id).replace("//", "/"),
+                titles,
+            });
+        }
+    }
+
+    let sitemap_groups = sitemap
+        .map(|v| v.readers.iter().chain(v.writers.iter()).collect::<Vec<_>>())
+        .unwrap_or_default();
+    for group in sitemap_groups {
+        if !groups.contains(&group.as_str()) {
+            problems.push(Problem::UndefinedGroup {
+                id: "fpm.sitemap".to_string(),
+                group: group.to_string(),
+            });
+        }
+    }
+    for entry in static_entries.iter().chain(dynamic_entries.iter()) {
+        for group in entry.readers.iter().chain(entry.writers.iter()) {
+            if !groups.contains(&group.as_str()) {
+                problems.push(Problem::UndefinedGroup {
+                    id: entry.id.to_string(),
+                    group: group.to_string(),
+                });
+            }
+        }
+    }
+
+    for entry in dynamic_entries.iter().filter(|v| v.is_dynamic) {
+        let pattern = match super::utils::UrlPattern::parse(entry.id) {
+            Ok(pattern) => pattern,
+            Err(_) => continue,
+        };
+        for static_entry in static_entries.iter() {
+            if let Some(id) = static_entry.local_id(package_name) {
+                if pattern.matches(id.as_str()).is_some() {
+                    // `Config::get_file_and_package_by_id` takes the `document` of the sitemap
+                    // entry, and looks in the dynamic urls only if it has none
+                    problems.push(Problem::Shadowed {
+                        pattern: entry.id.to_string(),
+                        id,
+                        dynamic_wins: static_entry.document.is_none(),
+                    });
+                }
+            }
+        }
+    }
+
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    fn sitemap(body: &str) -> super::super::Sitemap {
+        let mut parser = super::super::SitemapParser {
+            state: super::super::ParsingState::WaitingForSection,
+            sections: vec![],
+            temp_item: None,
+            doc_name: "www.amitu.com".to_string(),
+        };
+        for line in body.split('\n') {
+            parser.read_line(line, &Default::default()).unwrap();
+        }
+        if parser.temp_item.is_some() {
+            parser.eval_temp_item(&Default::default()).unwrap();
+        }
+        super::super::Sitemap {
+            sections: super::super::construct_tree_util(parser.finalize().unwrap()),
+            readers: vec![],
+            writers: vec!["admins".to_string()],
+        }
+    }
+
+    #[test]
+    fn check() {
+        let sitemap = sitemap(indoc::indoc! {"
+            # Home: /
+
+            # Blog: /blog/
+            readers: everyone
+
+            - Blog: /blog/
+            - First Post: /blog/first/
+              document: blog/post.ftd
+            - Missing: /blog/missing/
+
+            # About: /about/
+
+            - Team: /about/
+            - GitHub: https://github.com/amitu
+            - Theme: -/fifthtry.github.io/doc-site/
+        "});
+        let dynamic_urls = super::super::DynamicUrls::parse(
+            &Default::default(),
+            "www.amitu.com",
+            indoc::indoc! {"
+                # Posts
+
+                - Post
+                  url: /blog/<string:slug>/
+                  document: blog/post.ftd
+            "},
+        )
+        .unwrap();
+        let documents = vec![
+            "index.ftd".to_string(),
+            "blog/index.ftd".to_string(),
+            "blog/post.ftd".to_string(),
+            "about.ftd".to_string(),
+            "draft.ftd".to_string(),
+        ];
+        let resolve = |id: &str| {
+            let id = id.trim_matches('/');
+            documents
+                .iter()
+                .find(|v| {
+                    v.as_str() == id
+                        || v.as_str() == format!("{}.ftd", id)
+                        || v.as_str() == format!("{}/index.ftd", id).trim_start_matches('/')
+                })
+                .cloned()
+        };
+
+        let problems = super::check(
+            Some(&sitemap),
+            Some(&dynamic_urls),
+            "www.amitu.com",
+            &documents,
+            &["everyone"],
+            resolve,
+        );
+        assert_eq!(
+            problems,
+            vec![
+                super::Problem::Missing {
+                    id: "/blog/missing/".to_string(),
+                    document: "/blog/missing/".to_string(),
+                },
+                super::Problem::Orphan {
+                    document: "draft.ftd".to_string(),
+                },
+                super::Problem::UndefinedGroup {
+                    id: "fpm.sitemap".to_string(),
+                    group: "admins".to_string(),
+                },
+                super::Problem::Shadowed {
+                    pattern: "/blog/<string:slug>/".to_string(),
+                    id: "/blog/first/".to_string(),
+                    dynamic_wins: false,
+                },
+                super::Problem::Shadowed {
+                    pattern: "/blog/<string:slug>/".to_string(),
+                    id: "/blog/missing/".to_string(),
+                    dynamic_wins: true,
+                },
+            ]
+        );
+
+        let duplicate = sitemap(indoc::indoc! {"
+            # Home: /
+
+            - Intro: /intro/
+            - Start: /intro/
+        "});
+        assert_eq!(
+            super::check(Some(&duplicate), None, "www.amitu.com", &[], &[], |v| Some(
+                v.to_string()
+            )),
+            vec![super::Problem::Duplicate {
+                id: "/intro/".to_string(),
+                titles: vec!["Intro".to_string(), "Start".to_string()],
+            }]
+        );
+    }
+}
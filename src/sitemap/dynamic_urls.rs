@@ -44,6 +44,17 @@ impl DynamicUrls {
             });
         }
 
+        // parameter sources are only used by `fpm build`, but are checked here with the urls so a
+        // wrong one fails early
+        for (url, extra_data) in dynamic_urls.urls() {
+            fpm::sitemap::utils::UrlPattern::parse(url).map_err(|e| {
+                fpm::sitemap::ParseError::InvalidDynamicUrls {
+                    message: format!("{}: {}", url, e),
+                }
+            })?;
+            ParamSource::from_extra_data(extra_data)?;
+        }
+
         Ok(dynamic_urls)
     }
 
@@ -98,107 +109,276 @@ impl DynamicUrls {
         false
     }
 
+    /// Document of the dynamic url matching `path`, and its path parameters. When more than
+    /// one url matches, the most specific one wins, part by part a static part beats a
+    /// constrained parameter, which beats a typed parameter, which beats `<path:..>`. Urls
+    /// matching equally are resolved in the order they are written.
     pub fn resolve_document(&self, path: &str) -> fpm::Result<ResolveDocOutput> {
-        fn resolve_in_toc(
-            toc: &fpm::sitemap::toc::TocItem,
-            path: &str,
-        ) -> fpm::Result<ResolveDocOutput> {
-            if !toc.path_parameters.is_empty() {
-                // path: /arpita/foo/28/
-                // request: arpita foo 28
-                // sitemap: [string,integer]
-                // Mapping: arpita -> string, foo -> foo, 28 -> integer
-                let params = fpm::sitemap::utils::parse_named_params(
-                    path,
-                    toc.id.as_str(),
-                    toc.path_parameters.as_slice(),
-                );
-
-                if params.is_ok() {
-                    return Ok((toc.document.clone(), params?));
-                }
+        let mut resolved: Option<(&String, Vec<(String, ftd::Value)>, Vec<u8>)> = None;
+        for (url, extra_data) in self.urls() {
+            let document = match extra_data.get("document") {
+                Some(document) => document,
+                None => continue,
+            };
+            let pattern = fpm::sitemap::utils::UrlPattern::parse(url)
+                .map_err(|e| fpm::Error::GenericError(format!("{}: {}", url, e)))?;
+            let (params, specificity) = match pattern.matches(path) {
+                Some(matched) => matched,
+                None => continue,
+            };
+            if resolved
+                .as_ref()
+                .map(|(_, _, best)| specificity.gt(best))
+                .unwrap_or(true)
+            {
+                resolved = Some((document, params, specificity));
             }
+        }
+        Ok(match resolved {
+            Some((document, params, _)) => (Some(document.to_string()), params),
+            None => (None, vec![]),
+        })
+    }
+}
 
-            for child in toc.children.iter() {
-                let (document, path_prams) = resolve_in_toc(child, path)?;
-                if document.is_some() {
-                    return Ok((document, path_prams));
-                }
-            }
+/// Where `fpm build` gets the values of the path parameters of a dynamic url from, a page is
+/// built for every row. Rows are objects with the path parameter names as keys.
+///
+/// ```ftd
+/// -- fpm.dynamic-urls:
+///
+/// # People
+/// - Person
+///   url: /person/<string:name>/
+///   document: person.ftd
+///   params-db: people.sqlite
+///   params-query: SELECT name FROM person
+/// ```
+///
+/// `params-json: <file>` and `params-http: <url>` give the rows as a JSON list instead.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParamSource {
+    /// `params-query` run on the sqlite `params-db`, the column names are the parameter names
+    Query { db: String, query: String },
+    /// `params-json`, path of a JSON file in the package
+    Json(String),
+    /// `params-http`, url returning JSON
+    Http(String),
+}
 
-            Ok((None, vec![]))
+impl ParamSource {
+    pub fn from_extra_data(
+        extra_data: &std::collections::BTreeMap<String, String>,
+    ) -> Result<Option<Self>, fpm::sitemap::ParseError> {
+        let get = |key: &str| extra_data.get(key).map(|v| v.trim().to_string());
+        let invalid = |message: &str| fpm::sitemap::ParseError::InvalidDynamicUrls {
+            message: format!(
+                "{}, url: {}",
+                message,
+                extra_data
+                    .get("url")
+                    .map(String::as_str)
+                    .unwrap_or_default()
+            ),
+        };
+        match (
+            get("params-query"),
+            get("params-db"),
+            get("params-json"),
+            get("params-http"),
+        ) {
+            (None, None, None, None) => Ok(None),
+            (Some(query), Some(db), None, None) => Ok(Some(ParamSource::Query { db, query })),
+            (Some(_), None, None, None) => Err(invalid("`params-query` needs `params-db`")),
+            (None, Some(_), None, None) => Err(invalid("`params-db` needs `params-query`")),
+            (None, None, Some(file), None) => Ok(Some(ParamSource::Json(file))),
+            (None, None, None, Some(url)) => Ok(Some(ParamSource::Http(url))),
+            _ => Err(invalid(
+                "only one of `params-query`, `params-json` and `params-http` can be used",
+            )),
         }
+    }
 
-        fn resolve_in_sub_section(
-            sub_section: &fpm::sitemap::section::Subsection,
-            path: &str,
-        ) -> fpm::Result<ResolveDocOutput> {
-            if !sub_section.path_parameters.is_empty() {
-                // path: /arpita/foo/28/
-                // request: arpita foo 28
-                // sitemap: [string,integer]
-                // Mapping: arpita -> string, foo -> foo, 28 -> integer
-                if let Some(id) = sub_section.id.as_ref() {
-                    let params = fpm::sitemap::utils::parse_named_params(
-                        path,
-                        id.as_str(),
-                        sub_section.path_parameters.as_slice(),
-                    );
-
-                    if params.is_ok() {
-                        return Ok((sub_section.document.clone(), params?));
-                    }
-                }
+    /// Rows of parameter values, `config.root` is the base of the relative paths
+    pub(crate) async fn rows(
+        &self,
+        config: &fpm::Config,
+    ) -> fpm::Result<Vec<serde_json::Map<String, serde_json::Value>>> {
+        let value: serde_json::Value = match self {
+            ParamSource::Query { db, query } => {
+                let db = config.root.join(db);
+                return tokio::task::block_in_place(|| query_rows(&db, query.as_str()));
             }
-            for toc in sub_section.toc.iter() {
-                let (document, path_params) = resolve_in_toc(toc, path)?;
-                if document.is_some() {
-                    return Ok((document, path_params));
-                }
+            ParamSource::Json(file) => {
+                serde_json::from_slice(tokio::fs::read(config.root.join(file)).await?.as_slice())?
             }
-
-            Ok((None, vec![]))
+            ParamSource::Http(url) => fpm::http::get_json(url.as_str()).await?,
+        };
+        match value {
+            serde_json::Value::Array(rows) => rows
+                .into_iter()
+                .map(|row| match row {
+                    serde_json::Value::Object(row) => Ok(row),
+                    t => fpm::usage_error(format!(
+                        "dynamic url parameters should be objects, found: {}",
+                        t
+                    )),
+                })
+                .collect(),
+            t => fpm::usage_error(format!(
+                "dynamic url parameters should be a list of objects, found: {}",
+                t
+            )),
         }
+    }
+}
 
-        fn resolve_in_section(
-            section: &fpm::sitemap::section::Section,
-            path: &str,
-        ) -> fpm::Result<ResolveDocOutput> {
-            // path: /abrark/foo/28/
-            // In sitemap url: /<string:username>/foo/<integer:age>/
-            if !section.path_parameters.is_empty() {
-                // path: /abrark/foo/28/
-                // request: abrark foo 28
-                // sitemap: [string,integer]
-                // params_matches: abrark -> string, foo -> foo, 28 -> integer
-                let params = fpm::sitemap::utils::parse_named_params(
-                    path,
-                    section.id.as_str(),
-                    section.path_parameters.as_slice(),
-                );
-
-                if params.is_ok() {
-                    return Ok((section.document.clone(), params?));
+fn query_rows(
+    db: &camino::Utf8Path,
+    query: &str,
+) -> fpm::Result<Vec<serde_json::Map<String, serde_json::Value>>> {
+    let error = |e: rusqlite::Error| fpm::Error::UsageError {
+        message: format!("dynamic url parameters query failed on `{}`: {}", db, e),
+    };
+    let conn =
+        rusqlite::Connection::open_with_flags(db, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .map_err(error)?;
+    let mut stmt = conn.prepare(query).map_err(error)?;
+    let columns: Vec<String> = stmt
+        .column_names()
+        .into_iter()
+        .map(ToString::to_string)
+        .collect();
+    let mut rows = stmt.query([]).map_err(error)?;
+    let mut result = vec![];
+    while let Some(row) = rows.next().map_err(error)? {
+        let mut object = serde_json::Map::new();
+        for (i, column) in columns.iter().enumerate() {
+            let value = match row.get::<usize, rusqlite::types::Value>(i).map_err(error)? {
+                rusqlite::types::Value::Null => serde_json::Value::Null,
+                rusqlite::types::Value::Integer(v) => serde_json::Value::Number(v.into()),
+                rusqlite::types::Value::Real(v) => serde_json::Number::from_f64(v)
+                    .map(serde_json::Value::Number)
+                    .unwrap_or(serde_json::Value::Null),
+                rusqlite::types::Value::Text(v) => serde_json::Value::String(v),
+                rusqlite::types::Value::Blob(_) => {
+                    return fpm::usage_error(format!(
+                        "dynamic url parameter `{}` can not be a blob",
+                        column
+                    ))
                 }
-            }
+            };
+            object.insert(column.to_string(), value);
+        }
+        result.push(object);
+    }
+    Ok(result)
+}
 
-            for subsection in section.subsections.iter() {
-                let (document, path_params) = resolve_in_sub_section(subsection, path)?;
-                if document.is_some() {
-                    return Ok((document, path_params));
-                }
+/// A dynamic url with a document, and where `fpm build` gets its parameters from
+#[derive(Debug, Clone, PartialEq)]
+pub struct DynamicUrl {
+    /// /person/<string:name>/
+    pub url: String,
+    pub document: String,
+    pub path_parameters: Vec<(String, String)>,
+    pub param_source: Option<ParamSource>,
+}
+
+impl DynamicUrl {
+    /// Url with the path parameters replaced by the values in `row`, percent-encoded, to link to
+    /// the page, `/person/<string:name>/` => `/person/Arpita%20Jaiswal/`
+    pub fn url_with_params(
+        &self,
+        row: &serde_json::Map<String, serde_json::Value>,
+    ) -> fpm::Result<String> {
+        self.pattern()?
+            .to_url(|name| row_value(row, name))
+            .map_err(|e| self.row_error(e, row))
+    }
+
+    /// Path of the page with the path parameters replaced by the values in `row` as they are,
+    /// `/person/<string:name>/` => `/person/Arpita Jaiswal/`, the page is built there
+    pub fn path_with_params(
+        &self,
+        row: &serde_json::Map<String, serde_json::Value>,
+    ) -> fpm::Result<String> {
+        self.pattern()?
+            .to_path(|name| row_value(row, name))
+            .map_err(|e| self.row_error(e, row))
+    }
+
+    fn pattern(&self) -> fpm::Result<fpm::sitemap::utils::UrlPattern> {
+        fpm::sitemap::utils::UrlPattern::parse(self.url.as_str())
+            .map_err(|e| fpm::Error::UsageError { message: e })
+    }
+
+    fn row_error(&self, e: String, row: &serde_json::Map<String, serde_json::Value>) -> fpm::Error {
+        fpm::Error::UsageError {
+            message: format!("{} of {} in {:?}", e, self.url, row),
+        }
+    }
+}
+
+fn row_value(row: &serde_json::Map<String, serde_json::Value>, name: &str) -> Option<String> {
+    match row.get(name) {
+        Some(serde_json::Value::String(v)) => Some(v.to_string()),
+        Some(serde_json::Value::Number(v)) => Some(v.to_string()),
+        Some(serde_json::Value::Bool(v)) => Some(v.to_string()),
+        _ => None,
+    }
+}
+
+impl DynamicUrls {
+    /// (url, extra data) of the sections, subsections and toc items which have path parameters
+    fn urls(&self) -> Vec<(&str, &std::collections::BTreeMap<String, String>)> {
+        fn toc_urls<'a>(
+            toc: &'a fpm::sitemap::toc::TocItem,
+            urls: &mut Vec<(&'a str, &'a std::collections::BTreeMap<String, String>)>,
+        ) {
+            if !toc.path_parameters.is_empty() {
+                urls.push((toc.id.as_str(), &toc.extra_data));
+            }
+            for child in toc.children.iter() {
+                toc_urls(child, urls);
             }
-            Ok((None, vec![]))
         }
 
+        let mut urls = vec![];
         for section in self.sections.iter() {
-            let (document, path_params) = resolve_in_section(section, path)?;
-            if document.is_some() {
-                return Ok((document, path_params));
+            if !section.path_parameters.is_empty() {
+                urls.push((section.id.as_str(), &section.extra_data));
+            }
+            for subsection in section.subsections.iter() {
+                if let Some(id) = subsection.id.as_ref() {
+                    if !subsection.path_parameters.is_empty() {
+                        urls.push((id.as_str(), &subsection.extra_data));
+                    }
+                }
+                for toc in subsection.toc.iter() {
+                    toc_urls(toc, &mut urls);
+                }
             }
         }
+        urls
+    }
 
-        Ok((None, vec![]))
+    /// All the dynamic urls with a document
+    pub fn dynamic_urls(&self) -> fpm::Result<Vec<DynamicUrl>> {
+        let mut dynamic_urls = vec![];
+        for (url, extra_data) in self.urls() {
+            let document = match extra_data.get("document") {
+                Some(document) => document.to_string(),
+                None => continue,
+            };
+            dynamic_urls.push(DynamicUrl {
+                url: url.to_string(),
+                document,
+                path_parameters: fpm::sitemap::utils::parse_path_params(url),
+                param_source: ParamSource::from_extra_data(extra_data)?,
+            });
+        }
+        Ok(dynamic_urls)
     }
 }
 
@@ -319,4 +499,127 @@ mod tests {
 
         assert_eq!(left, right)
     }
+
+    #[test]
+    fn param_source() {
+        let extra_data = |data: &[(&str, &str)]| {
+            data.iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect::<std::collections::BTreeMap<String, String>>()
+        };
+        assert_eq!(
+            super::ParamSource::from_extra_data(&extra_data(&[("document", "person.ftd")])),
+            Ok(None)
+        );
+        assert_eq!(
+            super::ParamSource::from_extra_data(&extra_data(&[
+                ("params-db", "people.sqlite"),
+                ("params-query", "SELECT name FROM person"),
+            ])),
+            Ok(Some(super::ParamSource::Query {
+                db: "people.sqlite".to_string(),
+                query: "SELECT name FROM person".to_string()
+            }))
+        );
+        assert!(super::ParamSource::from_extra_data(&extra_data(&[(
+            "params-query",
+            "SELECT name FROM person"
+        )]))
+        .is_err());
+        assert!(super::ParamSource::from_extra_data(&extra_data(&[
+            ("params-json", "people.json"),
+            ("params-http", "https://example.com/people.json"),
+        ]))
+        .is_err());
+    }
+
+    #[test]
+    fn url_with_params() {
+        let dynamic_url = super::DynamicUrl {
+            url: "/person/<string:name>/age/<integer:age>/".to_string(),
+            document: "person.ftd".to_string(),
+            path_parameters: vec![
+                ("string".to_string(), "name".to_string()),
+                ("integer".to_string(), "age".to_string()),
+            ],
+            param_source: None,
+        };
+        let row = serde_json::json!({"name": "arpita", "age": 28});
+        assert_eq!(
+            dynamic_url
+                .url_with_params(row.as_object().unwrap())
+                .unwrap(),
+            "/person/arpita/age/28/"
+        );
+        let row = serde_json::json!({"name": "Arpita Jaiswal", "age": 28});
+        assert_eq!(
+            dynamic_url
+                .url_with_params(row.as_object().unwrap())
+                .unwrap(),
+            "/person/Arpita%20Jaiswal/age/28/"
+        );
+        assert_eq!(
+            dynamic_url
+                .path_with_params(row.as_object().unwrap())
+                .unwrap(),
+            "/person/Arpita Jaiswal/age/28/"
+        );
+        // `fpm build` takes the path parameters of the page from the path
+        assert_eq!(
+            fpm::sitemap::utils::parse_named_params(
+                "/person/Arpita Jaiswal/age/28/",
+                dynamic_url.url.as_str()
+            )
+            .unwrap()[0],
+            (
+                "name".to_string(),
+                ftd::Value::String {
+                    text: "Arpita Jaiswal".to_string(),
+                    source: ftd::TextSource::Default
+                }
+            )
+        );
+        let row = serde_json::json!({"name": "a/b", "age": 28});
+        assert!(dynamic_url
+            .url_with_params(row.as_object().unwrap())
+            .is_err());
+    }
+
+    #[test]
+    fn resolve_document_precedence() {
+        let dynamic_urls = fpm::sitemap::DynamicUrls::parse(
+            &std::collections::HashMap::new(),
+            "abrark.com",
+            r#"
+# Docs
+- Page
+  url: /docs/<string:page>/
+  document: page.ftd
+- Rest
+  url: /docs/<path:rest>/
+  document: rest.ftd
+- Version
+  url: /docs/<string:version:v[0-9]+>/
+  document: version.ftd
+"#,
+        )
+        .unwrap();
+        let document = |path: &str| dynamic_urls.resolve_document(path).unwrap().0;
+        assert_eq!(document("/docs/v2/").as_deref(), Some("version.ftd"));
+        assert_eq!(document("/docs/intro/").as_deref(), Some("page.ftd"));
+        assert_eq!(document("/docs/intro/setup/").as_deref(), Some("rest.ftd"));
+        assert_eq!(document("/blog/"), None);
+
+        assert!(fpm::sitemap::DynamicUrls::parse(
+            &std::collections::HashMap::new(),
+            "abrark.com",
+            r#"
+# Docs
+- Rest
+  url: /docs/<path:rest>/setup/
+  document: rest.ftd
+"#,
+        )
+        .is_err());
+    }
 }
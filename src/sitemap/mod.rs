@@ -13,7 +13,10 @@
 /// In above example, the id starts with `#` becomes the section. Similarly the id
 /// starts with `##` becomes the subsection and then the id starts with `-` becomes
 /// the table od content (TOC).
+pub mod auto;
+pub mod check;
 pub mod dynamic_urls;
+pub mod navigation;
 pub mod section;
 pub mod toc;
 pub mod utils;
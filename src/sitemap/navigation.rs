@@ -0,0 +1,364 @@
+// The pages around the current document in the sitemap, for the `breadcrumbs`, `prev-next` and
+// `children` processors. Entries with `skip`, and the ones nested in them, are left out. Entries
+// with `bury` are left out of `prev-next` and `children` but are still in the breadcrumbs of the
+// pages nested in them. `readable` tells if the reader can read the page at a url.
+
+/// An entry of the sitemap as the navigation processors give it
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct Page {
+    /// `None` for an entry with only a title
+    pub url: Option<String>,
+    pub title: String,
+    #[serde(rename = "nav-title")]
+    pub nav_title: Option<String>,
+    #[serde(rename = "extra-data")]
+    pub extra_data: Vec<fpm::library::KeyValueData>,
+}
+
+#[derive(Debug, Default, PartialEq, serde::Serialize)]
+pub struct PrevNext {
+    pub previous: Option<Page>,
+    pub next: Option<Page>,
+}
+
+struct Entry {
+    page: Page,
+    /// `skip` of the entry or of an entry it is nested in
+    skip: bool,
+    bury: bool,
+    parent: Option<usize>,
+}
+
+fn page(
+    id: &str,
+    title: &Option<String>,
+    nav_title: &Option<String>,
+    extra_data: &std::collections::BTreeMap<String, String>,
+) -> Page {
+    Page {
+        url: if id.trim().is_empty() || title.as_deref() == Some(id) {
+            None
+        } else {
+            Some(id.to_string())
+        },
+        title: title.clone().unwrap_or_else(|| id.to_string()),
+        nav_title: nav_title.clone(),
+        extra_data: extra_data
+            .iter()
+            .map(|(k, v)| fpm::library::KeyValueData::from(k.to_string(), v.to_string()))
+            .collect(),
+    }
+}
+
+fn push(entries: &mut Vec<Entry>, page: Page, skip: bool, bury: bool, parent: Option<usize>) {
+    let skip = skip || parent.map(|v| entries[v].skip).unwrap_or(false);
+    entries.push(Entry {
+        page,
+        skip,
+        bury,
+        parent,
+    });
+}
+
+/// Every entry of the sitemap in reading order
+fn entries(sitemap: &super::Sitemap) -> Vec<Entry> {
+    fn toc_entries(entries: &mut Vec<Entry>, toc: &super::toc::TocItem, parent: Option<usize>) {
+        push(
+            entries,
+            page(&toc.id, &toc.title, &toc.nav_title, &toc.extra_data),
+            toc.skip,
+            toc.bury,
+            parent,
+        );
+        let index = entries.len() - 1;
+        for child in toc.children.iter() {
+            toc_entries(entries, child, Some(index));
+        }
+    }
+
+    let mut entries = vec![];
+    for section in sitemap.sections.iter() {
+        push(
+            &mut entries,
+            page(
+                &section.id,
+                &section.title,
+                &section.nav_title,
+                &section.extra_data,
+            ),
+            section.skip,
+            section.bury,
+            None,
+        );
+        let section_index = entries.len() - 1;
+        for subsection in section.subsections.iter() {
+            let parent = match subsection.id.as_ref() {
+                Some(id) => {
+                    push(
+                        &mut entries,
+                        page(
+                            id,
+                            &subsection.title,
+                            &subsection.nav_title,
+                            &subsection.extra_data,
+                        ),
+                        subsection.skip,
+                        subsection.bury,
+                        Some(section_index),
+                    );
+                    entries.len() - 1
+                }
+                None => section_index,
+            };
+            for toc in subsection.toc.iter() {
+                toc_entries(&mut entries, toc, Some(parent));
+            }
+        }
+    }
+    entries
+}
+
+fn normalize(url: &str) -> &str {
+    url.split('#')
+        .next()
+        .unwrap_or(url)
+        .trim()
+        .trim_matches('/')
+}
+
+fn is_current(entry: &Entry, current: &str) -> bool {
+    entry
+        .page
+        .url
+        .as_deref()
+        .map(|v| normalize(v) == normalize(current))
+        .unwrap_or(false)
+}
+
+/// The first entry of the document `current`
+fn position(entries: &[Entry], current: &str) -> Option<usize> {
+    entries.iter().position(|v| is_current(v, current))
+}
+
+/// The entries `current` is nested in, the outermost first, followed by the entry of `current`
+pub fn breadcrumbs(
+    sitemap: &super::Sitemap,
+    current: &str,
+    readable: impl Fn(&str) -> bool,
+) -> Vec<Page> {
+    let entries = entries(sitemap);
+    let mut index = match position(&entries, current) {
+        Some(index) => index,
+        None => return vec![],
+    };
+    let mut crumbs = vec![entries[index].page.clone()];
+    while let Some(parent) = entries[index].parent {
+        index = parent;
+        let entry = &entries[index];
+        if !entry.skip && entry.page.url.as_deref().map_or(true, &readable) {
+            crumbs.push(entry.page.clone());
+        }
+    }
+    crumbs.reverse();
+    // `- Blog: /blog/` in `# Blog: /blog/` is the same page
+    crumbs.dedup_by(|a, b| a.url.is_some() && a.url == b.url);
+    crumbs
+}
+
+/// The pages before and after `current` in reading order, external links are no pages
+pub fn prev_next(
+    sitemap: &super::Sitemap,
+    current: &str,
+    readable: impl Fn(&str) -> bool,
+) -> PrevNext {
+    let entries = entries(sitemap);
+    let index = match position(&entries, current) {
+        Some(index) => index,
+        None => return PrevNext::default(),
+    };
+    let is_page = |entry: &&Entry| {
+        !entry.skip
+            && !entry.bury
+            && !is_current(entry, current)
+            && entry
+                .page
+                .url
+                .as_deref()
+                .map(|v| !fpm::http::url_regex().is_match(v) && readable(v))
+                .unwrap_or(false)
+    };
+    PrevNext {
+        previous: entries[..index]
+            .iter()
+            .rev()
+            .find(is_page)
+            .map(|v| v.page.clone()),
+        next: entries[index + 1..]
+            .iter()
+            .find(is_page)
+            .map(|v| v.page.clone()),
+    }
+}
+
+/// The entries nested right in the entry of `current`
+pub fn children(
+    sitemap: &super::Sitemap,
+    current: &str,
+    readable: impl Fn(&str) -> bool,
+) -> Vec<Page> {
+    let entries = entries(sitemap);
+    let index = match position(&entries, current) {
+        Some(index) => index,
+        None => return vec![],
+    };
+    entries
+        .iter()
+        .filter(|v| {
+            v.parent == Some(index)
+                && !v.skip
+                && !v.bury
+                && !is_current(v, current)
+                && v.page.url.as_deref().map_or(true, &readable)
+        })
+        .map(|v| v.page.clone())
+        .collect()
+}
+
+/// The urls of the entries, to find the ones the reader can not read
+pub fn urls(sitemap: &super::Sitemap) -> Vec<String> {
+    entries(sitemap)
+        .into_iter()
+        .filter_map(|v| v.page.url)
+        .filter(|v| !fpm::http::url_regex().is_match(v))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    fn sitemap() -> super::super::Sitemap {
+        let mut parser = super::super::SitemapParser {
+            state: super::super::ParsingState::WaitingForSection,
+            sections: vec![],
+            temp_item: None,
+            doc_name: "www.amitu.com".to_string(),
+        };
+        let body = indoc::indoc! {"
+            # Home: /
+
+            # Blog: /blog/
+
+            - Blog: /blog/
+            - First Post: /blog/first/
+              author: amitu
+            - Draft: /blog/draft/
+              skip: true
+              - Draft Notes: /blog/draft/notes/
+            - Second Post: /blog/second/
+            - Private: /blog/private/
+            - Archive: /blog/archive/
+              bury: true
+              - Old Post: /blog/archive/old/
+
+            # Docs
+
+            ## Setup: /docs/setup/
+
+            - Linux: /docs/setup/linux/
+            - GitHub: https://github.com/amitu
+        "};
+        for line in body.split('\n') {
+            parser.read_line(line, &Default::default()).unwrap();
+        }
+        if parser.temp_item.is_some() {
+            parser.eval_temp_item(&Default::default()).unwrap();
+        }
+        super::super::Sitemap {
+            sections: super::super::construct_tree_util(parser.finalize().unwrap()),
+            readers: vec![],
+            writers: vec![],
+        }
+    }
+
+    fn readable(url: &str) -> bool {
+        url != "/blog/private/"
+    }
+
+    fn urls(pages: &[super::Page]) -> Vec<Option<&str>> {
+        pages.iter().map(|v| v.url.as_deref()).collect()
+    }
+
+    #[test]
+    fn breadcrumbs() {
+        let sitemap = sitemap();
+        assert_eq!(
+            urls(&super::breadcrumbs(&sitemap, "/blog/first/", readable)),
+            vec![Some("/blog/"), Some("/blog/first/")]
+        );
+        assert_eq!(
+            urls(&super::breadcrumbs(
+                &sitemap,
+                "/blog/archive/old/",
+                readable
+            )),
+            vec![
+                Some("/blog/"),
+                Some("/blog/archive/"),
+                Some("/blog/archive/old/")
+            ]
+        );
+        let crumbs = super::breadcrumbs(&sitemap, "docs/setup/linux", readable);
+        assert_eq!(
+            urls(&crumbs),
+            vec![None, Some("/docs/setup/"), Some("/docs/setup/linux/")]
+        );
+        assert_eq!(crumbs[0].title, "Docs");
+        assert!(super::breadcrumbs(&sitemap, "/missing/", readable).is_empty());
+    }
+
+    #[test]
+    fn prev_next() {
+        let sitemap = sitemap();
+        let prev_next = |current: &str| {
+            let v = super::prev_next(&sitemap, current, readable);
+            (v.previous.and_then(|v| v.url), v.next.and_then(|v| v.url))
+        };
+        assert_eq!(prev_next("/"), (None, Some("/blog/".to_string())));
+        assert_eq!(
+            prev_next("/blog/"),
+            (Some("/".to_string()), Some("/blog/first/".to_string()))
+        );
+        assert_eq!(
+            prev_next("/blog/second/"),
+            (
+                Some("/blog/first/".to_string()),
+                Some("/blog/archive/old/".to_string())
+            )
+        );
+        assert_eq!(
+            prev_next("/docs/setup/linux/"),
+            (Some("/docs/setup/".to_string()), None)
+        );
+    }
+
+    #[test]
+    fn children() {
+        let sitemap = sitemap();
+        let children = super::children(&sitemap, "/blog/", readable);
+        assert_eq!(
+            urls(&children),
+            vec![Some("/blog/first/"), Some("/blog/second/")]
+        );
+        assert_eq!(
+            children[0].extra_data,
+            vec![fpm::library::KeyValueData::from(
+                "author".to_string(),
+                "amitu".to_string()
+            )]
+        );
+        assert_eq!(
+            urls(&super::children(&sitemap, "/docs/setup/", readable)),
+            vec![Some("/docs/setup/linux/"), Some("https://github.com/amitu")]
+        );
+        assert!(super::children(&sitemap, "/blog/first/", readable).is_empty());
+    }
+}
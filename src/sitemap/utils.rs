@@ -1,80 +1,321 @@
 // # Input
 // request_url: /arpita/foo/28/
 // sitemap_url: /<string:username>/foo/<integer:age>/
-// params_types: [(string, username), (integer, age)]
 // # Output
-// true
+// [(username, arpita), (age, 28)]
 
 pub fn parse_named_params(
     request_url: &str,
     sitemap_url: &str,
-    params_type: &[(String, String)],
 ) -> fpm::Result<Vec<(String, ftd::Value)>> {
-    use itertools::Itertools;
-    // request_attrs: [arpita, foo, 28]
-    let request_attrs = request_url.trim_matches('/').split('/').collect_vec();
-    // sitemap_attrs: [<string:username>, foo, <integer:age>]
-    let sitemap_attrs = sitemap_url.trim_matches('/').split('/').collect_vec();
+    let pattern = UrlPattern::parse(sitemap_url)
+        .map_err(|e| fpm::Error::GenericError(format!("{}: {}", sitemap_url, e)))?;
+    match pattern.matches(request_url) {
+        Some((path_parameters, _)) => Ok(path_parameters),
+        None => Err(fpm::Error::GenericError(format!(
+            "{} does not match {}",
+            request_url, sitemap_url
+        ))),
+    }
+}
 
-    // This should go to config request [username: arpita, age: 28]
+/// Parsed dynamic url, every part between `/` is a static value or a parameter
+///
+/// - `<string:username>`: any value of the type, `string`, `integer`, `decimal` or `boolean`
+/// - `<string:slug:[a-z0-9-]+>`: value matching the regex
+/// - `<integer:page?>`: optional, the url matches with or without it
+/// - `<path:rest>`: the rest of the url, one or more parts, only as the last part
+#[derive(Debug, Clone)]
+pub struct UrlPattern {
+    segments: Vec<Segment>,
+}
 
-    if request_attrs.len().ne(&sitemap_attrs.len()) {
-        return Err(fpm::Error::GenericError("".to_string()));
-    }
+#[derive(Debug, Clone)]
+enum Segment {
+    Static(String),
+    Param {
+        kind: String,
+        name: String,
+        constraint: Option<regex::Regex>,
+        optional: bool,
+    },
+    CatchAll {
+        name: String,
+    },
+}
 
-    // [(param_name, value)]
-    let mut path_parameters: Vec<(String, ftd::Value)> = vec![];
+// How specific a matched part of the url is, a url matching more specifically is preferred
+const STATIC_MATCH: u8 = 3;
+const CONSTRAINED_MATCH: u8 = 2;
+const TYPED_MATCH: u8 = 1;
+const CATCH_ALL_MATCH: u8 = 0;
 
-    // For every element either value should match or request attribute type should match to
-    // sitemap's params_types
-    let mut type_matches_count = 0;
-    for idx in 0..request_attrs.len() {
-        // either value match or type match
-        let value_match = request_attrs[idx].eq(sitemap_attrs[idx]);
-        if value_match {
-            continue;
-        }
+impl UrlPattern {
+    pub fn parse(url: &str) -> Result<UrlPattern, String> {
+        let mut segments: Vec<Segment> = vec![];
+        let mut names = std::collections::HashSet::new();
+        for part in url_parts(url) {
+            if let Some(Segment::CatchAll { name }) = segments.last() {
+                return Err(format!(
+                    "`<path:{}>` should be the last part of the url",
+                    name
+                ));
+            }
+            let inner = match part.strip_prefix('<').and_then(|v| v.strip_suffix('>')) {
+                Some(inner) => inner,
+                None if part.contains('<') || part.contains('>') => {
+                    return Err(format!(
+                        "`{}`: a parameter should be the whole part between `/`",
+                        part
+                    ))
+                }
+                None => {
+                    segments.push(Segment::Static(part.to_string()));
+                    continue;
+                }
+            };
 
-        let parsed_value = {
-            // request's attribute value type == type stored in sitemap:params_type
-            let attribute_value = request_attrs[idx];
-            assert!(params_type.len() > type_matches_count);
-            let attribute_type = &params_type[type_matches_count].0;
-            dbg!(&attribute_value, attribute_type);
-            value_parse_to_type(attribute_value, attribute_type)
-        };
-        match parsed_value {
-            Ok(value) => {
-                let attribute_name = params_type[type_matches_count].1.to_string();
-                path_parameters.push((attribute_name, value));
+            let mut pieces = inner.splitn(3, ':');
+            let kind = pieces.next().unwrap_or_default().trim();
+            let name = match pieces.next() {
+                Some(name) => name.trim(),
+                None => return Err(format!("`{}`: expected `<type:name>`", part)),
+            };
+            let constraint = pieces.next();
+            let (name, optional) = match name.strip_suffix('?') {
+                Some(name) => (name.trim(), true),
+                None => (name, false),
+            };
+            if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+                return Err(format!("`{}`: invalid parameter name `{}`", part, name));
             }
-            Err(e) => return Err(fpm::Error::GenericError(e.to_string())),
-        };
+            if !names.insert(name.to_string()) {
+                return Err(format!("`{}`: parameter `{}` is repeated", part, name));
+            }
+
+            if kind.eq("path") {
+                if optional || constraint.is_some() {
+                    return Err(format!(
+                        "`{}`: `path` parameters can't be optional or constrained",
+                        part
+                    ));
+                }
+                segments.push(Segment::CatchAll {
+                    name: name.to_string(),
+                });
+                continue;
+            }
+            if !["string", "integer", "decimal", "boolean"].contains(&kind) {
+                return Err(format!(
+                    "`{}`: unknown parameter type `{}`, expected string, integer, decimal, \
+                    boolean or path",
+                    part, kind
+                ));
+            }
+            let constraint = match constraint {
+                Some(constraint) => Some(
+                    regex::Regex::new(format!("^(?:{})$", constraint).as_str())
+                        .map_err(|e| format!("`{}`: invalid regex: {}", part, e))?,
+                ),
+                None => None,
+            };
+            segments.push(Segment::Param {
+                kind: kind.to_string(),
+                name: name.to_string(),
+                constraint,
+                optional,
+            });
+        }
+        Ok(UrlPattern { segments })
+    }
+
+    /// Path parameters if `request_url` matches, and how specific the match is, a match with a
+    /// greater specificity is a better match
+    pub fn matches(&self, request_url: &str) -> Option<(Vec<(String, ftd::Value)>, Vec<u8>)> {
+        let request_parts: Vec<&str> = url_parts(request_url).collect();
+        let mut path_parameters = vec![];
+        let mut specificity = vec![];
+        if self.match_from(
+            0,
+            request_parts.as_slice(),
+            &mut path_parameters,
+            &mut specificity,
+        ) {
+            Some((path_parameters, specificity))
+        } else {
+            None
+        }
+    }
+
+    /// Url with the parameters replaced by `value(name)`, percent-encoded, for links to the page.
+    /// An optional parameter without a value is left out.
+    pub fn to_url(&self, value: impl Fn(&str) -> Option<String>) -> Result<String, String> {
+        self.fill(value, encode_segment)
+    }
 
-        type_matches_count += 1;
+    /// Path of the page with the parameters replaced by `value(name)` as they are, where it is
+    /// written in the `.build` folder. `.` and `..` are no values, they would step out of it.
+    pub fn to_path(&self, value: impl Fn(&str) -> Option<String>) -> Result<String, String> {
+        self.fill(value, |v| v.to_string())
     }
-    return Ok(path_parameters);
 
-    fn value_parse_to_type(value: &str, r#type: &str) -> fpm::Result<ftd::Value> {
-        match r#type {
-            "string" => Ok(ftd::Value::String {
-                text: value.to_string(),
-                source: ftd::TextSource::Default,
-            }),
-            "integer" => {
-                let value = value.parse::<i64>()?;
-                Ok(ftd::Value::Integer { value })
+    fn fill(
+        &self,
+        value: impl Fn(&str) -> Option<String>,
+        encode: fn(&str) -> String,
+    ) -> Result<String, String> {
+        let mut parts = vec![];
+        for segment in self.segments.iter() {
+            match segment {
+                Segment::Static(part) => parts.push(part.to_string()),
+                Segment::Param {
+                    kind,
+                    name,
+                    constraint,
+                    optional,
+                } => match value(name.as_str()) {
+                    Some(v) => {
+                        if v.is_empty()
+                            || v == "."
+                            || v == ".."
+                            || v.contains('/')
+                            || value_parse_to_type(v.as_str(), kind.as_str()).is_err()
+                            || !constraint.as_ref().map(|c| c.is_match(&v)).unwrap_or(true)
+                        {
+                            return Err(format!(
+                                "`{}` can not be the value of the parameter `{}`",
+                                v, name
+                            ));
+                        }
+                        parts.push(encode(v.as_str()));
+                    }
+                    None if *optional => continue,
+                    None => return Err(format!("no value for the parameter `{}`", name)),
+                },
+                Segment::CatchAll { name } => match value(name.as_str()) {
+                    Some(v) if !v.trim_matches('/').is_empty() => {
+                        let v = v.trim_matches('/');
+                        if v.split('/').any(|v| v.is_empty() || v == "." || v == "..") {
+                            return Err(format!(
+                                "`{}` can not be the value of the parameter `{}`",
+                                v, name
+                            ));
+                        }
+                        parts.push(v.split('/').map(encode).collect::<Vec<_>>().join("/"))
+                    }
+                    _ => return Err(format!("no value for the parameter `{}`", name)),
+                },
             }
-            "decimal" => {
-                let value = value.parse::<f64>()?;
-                Ok(ftd::Value::Decimal { value })
+        }
+        if parts.is_empty() {
+            return Ok("/".to_string());
+        }
+        Ok(format!("/{}/", parts.join("/")))
+    }
+
+    fn match_from(
+        &self,
+        index: usize,
+        request_parts: &[&str],
+        path_parameters: &mut Vec<(String, ftd::Value)>,
+        specificity: &mut Vec<u8>,
+    ) -> bool {
+        let segment = match self.segments.get(index) {
+            Some(segment) => segment,
+            None => return request_parts.is_empty(),
+        };
+        let (params_len, specificity_len) = (path_parameters.len(), specificity.len());
+        match segment {
+            Segment::Static(value) => {
+                if request_parts.first() == Some(&value.as_str()) {
+                    specificity.push(STATIC_MATCH);
+                    if self.match_from(index + 1, &request_parts[1..], path_parameters, specificity)
+                    {
+                        return true;
+                    }
+                }
             }
-            "boolean" => {
-                let value = value.parse::<bool>()?;
-                Ok(ftd::Value::Boolean { value })
+            Segment::Param {
+                kind,
+                name,
+                constraint,
+                optional,
+            } => {
+                // a present value is preferred over skipping an optional parameter
+                if let Some(value) = request_parts.first() {
+                    let constrained = constraint.as_ref().map(|v| v.is_match(value));
+                    if let (Ok(parsed), true) = (
+                        value_parse_to_type(value, kind.as_str()),
+                        constrained.unwrap_or(true),
+                    ) {
+                        specificity.push(if constrained.is_some() {
+                            CONSTRAINED_MATCH
+                        } else {
+                            TYPED_MATCH
+                        });
+                        path_parameters.push((name.to_string(), parsed));
+                        if self.match_from(
+                            index + 1,
+                            &request_parts[1..],
+                            path_parameters,
+                            specificity,
+                        ) {
+                            return true;
+                        }
+                        path_parameters.truncate(params_len);
+                        specificity.truncate(specificity_len);
+                    }
+                }
+                if *optional {
+                    return self.match_from(index + 1, request_parts, path_parameters, specificity);
+                }
+            }
+            Segment::CatchAll { name } => {
+                if !request_parts.is_empty() {
+                    specificity.extend(request_parts.iter().map(|_| CATCH_ALL_MATCH));
+                    path_parameters.push((
+                        name.to_string(),
+                        ftd::Value::String {
+                            text: request_parts.join("/"),
+                            source: ftd::TextSource::Default,
+                        },
+                    ));
+                    return true;
+                }
             }
-            _ => unimplemented!(),
         }
+        path_parameters.truncate(params_len);
+        specificity.truncate(specificity_len);
+        false
+    }
+}
+
+fn url_parts(url: &str) -> impl Iterator<Item = &str> {
+    url.trim_matches('/').split('/').filter(|v| !v.is_empty())
+}
+
+fn value_parse_to_type(value: &str, r#type: &str) -> fpm::Result<ftd::Value> {
+    match r#type {
+        "string" => Ok(ftd::Value::String {
+            text: value.to_string(),
+            source: ftd::TextSource::Default,
+        }),
+        "integer" => {
+            let value = value.parse::<i64>()?;
+            Ok(ftd::Value::Integer { value })
+        }
+        "decimal" => {
+            let value = value.parse::<f64>()?;
+            Ok(ftd::Value::Decimal { value })
+        }
+        "boolean" => {
+            let value = value.parse::<bool>()?;
+            Ok(ftd::Value::Boolean { value })
+        }
+        t => Err(fpm::Error::GenericError(format!(
+            "unknown parameter type: {}",
+            t
+        ))),
     }
 }
 
@@ -83,7 +324,7 @@ pub fn parse_path_params(url: &str) -> Vec<(String, String)> {
     fn path_params_regex() -> &'static regex::Regex {
         static PP: once_cell::sync::OnceCell<regex::Regex> = once_cell::sync::OnceCell::new();
         PP.get_or_init(|| {
-            regex::Regex::new(r"<\s*([a-z]\w+)\s*:\s*([a-z|A-Z|0-9|_]\w+)\s*>")
+            regex::Regex::new(r"<\s*([a-z]\w+)\s*:\s*([a-z|A-Z|0-9|_]\w*)\s*\??\s*(:[^>]*)?>")
                 .expect("PATH_PARAMS: Regex is wrong")
         })
     }
@@ -95,6 +336,20 @@ pub fn parse_path_params(url: &str) -> Vec<(String, String)> {
         .collect::<Vec<_>>()
 }
 
+/// `v` with every byte but the unreserved characters of RFC 3986 percent-encoded, to be one
+/// segment of a url
+fn encode_segment(v: &str) -> String {
+    let mut encoded = String::new();
+    for b in v.bytes() {
+        if b.is_ascii_alphanumeric() || matches!(b, b'-' | b'.' | b'_' | b'~') {
+            encoded.push(b as char);
+        } else {
+            encoded.push_str(format!("%{:02X}", b).as_str());
+        }
+    }
+    encoded
+}
+
 #[cfg(test)]
 mod tests {
     use ftd::TextSource;
@@ -121,14 +376,8 @@ mod tests {
 
     #[test]
     fn parse_named_params() {
-        let output = super::parse_named_params(
-            "/arpita/foo/28/",
-            "/<string:username>/foo/<integer:age>/",
-            &[
-                ("string".to_string(), "username".to_string()),
-                ("integer".to_string(), "age".to_string()),
-            ],
-        );
+        let output =
+            super::parse_named_params("/arpita/foo/28/", "/<string:username>/foo/<integer:age>/");
 
         assert_eq!(
             output.unwrap(),
@@ -150,18 +399,11 @@ mod tests {
         // Input:
         // request_url: /arpita/foo/28/
         // sitemap_url: /<string:username>/foo/<integer:age>/
-        // params_types: [(string, username), (integer, age)]
         // Output: true
         // Reason: Everything is matching
 
-        let output = super::parse_named_params(
-            "/arpita/foo/28/",
-            "/<string:username>/foo/<integer:age>/",
-            &[
-                ("string".to_string(), "username".to_string()),
-                ("integer".to_string(), "age".to_string()),
-            ],
-        );
+        let output =
+            super::parse_named_params("/arpita/foo/28/", "/<string:username>/foo/<integer:age>/");
 
         assert!(output.is_ok())
     }
@@ -171,17 +413,10 @@ mod tests {
         // Input:
         // request_url: /arpita/foo/28/
         // sitemap_url: /<integer:username>/foo/<integer:age>/
-        // params_types: [(integer, username), (integer, age)]
         // Output: false
         // Reason: `arpita` can not be converted into `integer`
-        let output = super::parse_named_params(
-            "/arpita/foo/28/",
-            "/<integer:username>/foo/<integer:age>/",
-            &[
-                ("integer".to_string(), "username".to_string()),
-                ("integer".to_string(), "age".to_string()),
-            ],
-        );
+        let output =
+            super::parse_named_params("/arpita/foo/28/", "/<integer:username>/foo/<integer:age>/");
 
         assert!(output.is_err())
     }
@@ -191,19 +426,122 @@ mod tests {
         // Input:
         // request_url: /arpita/foo/
         // sitemap_url: /<string:username>/foo/<integer:age>/
-        // params_types: [(string, username), (integer, age)]
         // Output: false
         // Reason: There is nothing to match in request_url after `foo`
         //         against with sitemap_url `<integer:age>`
-        let output = super::parse_named_params(
-            "/arpita/foo/",
-            "/<string:username>/foo/<integer:age>/",
-            &[
-                ("string".to_string(), "username".to_string()),
-                ("integer".to_string(), "age".to_string()),
-            ],
-        );
+        let output =
+            super::parse_named_params("/arpita/foo/", "/<string:username>/foo/<integer:age>/");
 
         assert!(output.is_err())
     }
+
+    #[test]
+    fn parse_named_params_constrained() {
+        let sitemap_url = "/blog/<string:slug:[a-z0-9-]+>/";
+        assert_eq!(
+            super::parse_named_params("/blog/hello-world/", sitemap_url).unwrap(),
+            vec![(
+                "slug".to_string(),
+                ftd::Value::String {
+                    text: "hello-world".to_string(),
+                    source: TextSource::Default
+                }
+            )]
+        );
+        assert!(super::parse_named_params("/blog/Hello_World/", sitemap_url).is_err());
+    }
+
+    #[test]
+    fn parse_named_params_optional() {
+        let sitemap_url = "/blog/<integer:page?>/";
+        assert_eq!(
+            super::parse_named_params("/blog/2/", sitemap_url).unwrap(),
+            vec![("page".to_string(), ftd::Value::Integer { value: 2 })]
+        );
+        assert_eq!(
+            super::parse_named_params("/blog/", sitemap_url).unwrap(),
+            vec![]
+        );
+        assert!(super::parse_named_params("/blog/two/", sitemap_url).is_err());
+    }
+
+    #[test]
+    fn parse_named_params_catch_all() {
+        let sitemap_url = "/docs/<path:rest>/";
+        assert_eq!(
+            super::parse_named_params("/docs/a/b/c/", sitemap_url).unwrap(),
+            vec![(
+                "rest".to_string(),
+                ftd::Value::String {
+                    text: "a/b/c".to_string(),
+                    source: TextSource::Default
+                }
+            )]
+        );
+        assert!(super::parse_named_params("/docs/", sitemap_url).is_err());
+    }
+
+    #[test]
+    fn to_url() {
+        let to_url = |pattern: &str, v: &str| {
+            super::UrlPattern::parse(pattern)
+                .unwrap()
+                .to_url(|_| Some(v.to_string()))
+        };
+        assert_eq!(
+            to_url("/person/<string:name>/", "Arpita Jaiswal"),
+            Ok("/person/Arpita%20Jaiswal/".to_string())
+        );
+        assert_eq!(
+            to_url("/person/<string:name>/", "a?b#c"),
+            Ok("/person/a%3Fb%23c/".to_string())
+        );
+        assert!(to_url("/person/<string:name>/", ".").is_err());
+        assert!(to_url("/person/<string:name>/", "..").is_err());
+        assert!(to_url("/person/<string:name>/", "../etc").is_err());
+        assert_eq!(
+            to_url("/docs/<path:rest>/", "setup/linux os"),
+            Ok("/docs/setup/linux%20os/".to_string())
+        );
+        assert!(to_url("/docs/<path:rest>/", "setup/../../etc").is_err());
+        assert!(to_url("/docs/<path:rest>/", "./setup").is_err());
+        assert_eq!(
+            super::UrlPattern::parse("/person/<string:name>/")
+                .unwrap()
+                .to_path(|_| Some("Arpita Jaiswal".to_string())),
+            Ok("/person/Arpita Jaiswal/".to_string())
+        );
+        assert!(super::UrlPattern::parse("/person/<string:name>/")
+            .unwrap()
+            .to_path(|_| Some("..".to_string()))
+            .is_err());
+    }
+
+    #[test]
+    fn url_pattern_errors() {
+        assert!(super::UrlPattern::parse("/<path:rest>/foo/").is_err());
+        assert!(super::UrlPattern::parse("/<float:price>/").is_err());
+        assert!(super::UrlPattern::parse("/<string:slug:[a-z>/").is_err());
+        assert!(super::UrlPattern::parse("/<string:a>/<integer:a>/").is_err());
+        assert!(super::UrlPattern::parse("/foo-<string:a>/").is_err());
+        assert!(super::UrlPattern::parse("/<string:>/").is_err());
+    }
+
+    #[test]
+    fn url_pattern_precedence() {
+        let specificity = |pattern: &str, url: &str| {
+            super::UrlPattern::parse(pattern)
+                .unwrap()
+                .matches(url)
+                .unwrap()
+                .1
+        };
+        let url = "/docs/intro/";
+        assert!(specificity("/docs/intro/", url) > specificity("/docs/<string:page:[a-z]+>/", url));
+        assert!(
+            specificity("/docs/<string:page:[a-z]+>/", url)
+                > specificity("/docs/<string:page>/", url)
+        );
+        assert!(specificity("/docs/<string:page>/", url) > specificity("/docs/<path:rest>/", url));
+    }
 }
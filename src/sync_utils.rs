@@ -25,6 +25,135 @@ impl Status {
     }
 }
 
+/// Markers around the hunks edited on both sides, same as `git`:
+///
+/// ```text
+/// <<<<<<< ours
+/// our lines
+/// =======
+/// their lines
+/// >>>>>>> theirs
+/// ```
+const CONFLICT_OURS_MARKER: &str = "<<<<<<<";
+const CONFLICT_ANCESTOR_MARKER: &str = "|||||||";
+const CONFLICT_SEPARATOR: &str = "=======";
+const CONFLICT_THEIRS_MARKER: &str = ">>>>>>>";
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum MergeResult {
+    /// Both sides edited different lines, all the hunks are applied
+    Merged(Vec<u8>),
+    /// Non-overlapping hunks are applied, overlapping ones are written with conflict markers
+    Conflicted(Vec<u8>),
+    /// One of the versions is not text, e.g. an image, it can't be merged line by line
+    Binary,
+}
+
+/// Line level three-way merge of `ours` and `theirs`, `ancestor` being the version both of
+/// them started from (the version in `.history`). Without the ancestor nothing tells which side
+/// changed which lines, so unless both sides are the same the whole file is one conflict.
+pub(crate) fn merge(ancestor: Option<&[u8]>, ours: &[u8], theirs: &[u8]) -> MergeResult {
+    let (ours, theirs) = match (std::str::from_utf8(ours), std::str::from_utf8(theirs)) {
+        (Ok(ours), Ok(theirs)) => (ours, theirs),
+        _ => return MergeResult::Binary,
+    };
+
+    if has_conflict_markers(ours) {
+        // conflicts of the last merge are not resolved yet, merging again would nest markers
+        return MergeResult::Conflicted(ours.as_bytes().to_vec());
+    }
+
+    let ancestor = match ancestor.map(std::str::from_utf8) {
+        Some(Ok(ancestor)) => ancestor,
+        Some(Err(_)) => return MergeResult::Binary,
+        None if ours.eq(theirs) => return MergeResult::Merged(ours.as_bytes().to_vec()),
+        None => {
+            let line = |v: &str| {
+                if v.is_empty() || v.ends_with('\n') {
+                    v.to_string()
+                } else {
+                    format!("{}\n", v)
+                }
+            };
+            return MergeResult::Conflicted(
+                format!(
+                    "{} ours\n{}{}\n{}{} theirs\n",
+                    CONFLICT_OURS_MARKER,
+                    line(ours),
+                    CONFLICT_SEPARATOR,
+                    line(theirs),
+                    CONFLICT_THEIRS_MARKER
+                )
+                .into_bytes(),
+            );
+        }
+    };
+
+    match diffy::MergeOptions::new()
+        .set_conflict_style(diffy::ConflictStyle::Merge)
+        .merge(ancestor, ours, theirs)
+    {
+        Ok(data) => MergeResult::Merged(data.into_bytes()),
+        Err(data) => MergeResult::Conflicted(data.into_bytes()),
+    }
+}
+
+/// The version `ours` and `theirs` started from, for `merge`. It is not in history if it was
+/// pruned by `fpm gc`, or never synced to this clone.
+pub(crate) async fn read_ancestor(path: &camino::Utf8Path) -> fpm::Result<Option<Vec<u8>>> {
+    if !fpm::history_store::exists(path) {
+        return Ok(None);
+    }
+    Ok(Some(fpm::history_store::read(path).await?))
+}
+
+pub(crate) fn has_conflict_markers(content: &str) -> bool {
+    let mut lines = content.lines();
+    lines.any(|l| l.starts_with(CONFLICT_OURS_MARKER))
+        && lines.any(|l| l.starts_with(CONFLICT_SEPARATOR))
+        && lines.any(|l| l.starts_with(CONFLICT_THEIRS_MARKER))
+}
+
+/// Resolves every conflict in `content` by picking our side (`use_ours`) or their side of it.
+/// Lines outside the markers, including the hunks merged cleanly, are kept as is.
+pub(crate) fn resolve_conflict_markers(content: &str, use_ours: bool) -> String {
+    #[derive(Clone, Copy)]
+    enum Side {
+        Common,
+        Ours,
+        Ancestor,
+        Theirs,
+    }
+
+    let mut side = Side::Common;
+    let mut resolved = String::new();
+    for line in content.split_inclusive('\n') {
+        let next = match side {
+            Side::Common if line.starts_with(CONFLICT_OURS_MARKER) => Some(Side::Ours),
+            Side::Ours if line.starts_with(CONFLICT_ANCESTOR_MARKER) => Some(Side::Ancestor),
+            Side::Ours | Side::Ancestor if line.starts_with(CONFLICT_SEPARATOR) => {
+                Some(Side::Theirs)
+            }
+            Side::Theirs if line.starts_with(CONFLICT_THEIRS_MARKER) => Some(Side::Common),
+            _ => None,
+        };
+        if let Some(next) = next {
+            side = next;
+            continue;
+        }
+        let keep = match side {
+            Side::Common => true,
+            Side::Ours => use_ours,
+            Side::Theirs => !use_ours,
+            Side::Ancestor => false,
+        };
+        if keep {
+            resolved.push_str(line);
+        }
+    }
+    resolved
+}
+
 #[derive(Debug, Clone)]
 pub enum FileStatus {
     Add {
@@ -212,7 +341,7 @@ impl fpm::Config {
             let content =
                 tokio::fs::read(self.root.join(workspace_entry.filename.as_str())).await?;
             let history_path = self.history_path(filename.as_str(), version);
-            let history_content = tokio::fs::read(history_path).await?;
+            let history_content = fpm::history_store::read(&history_path).await?;
             if sha2::Sha256::digest(&content).eq(&sha2::Sha256::digest(&history_content)) {
                 changed_files.push(FileStatus::Uptodate {
                     path: workspace_entry.filename.to_string(),
@@ -278,7 +407,7 @@ impl fpm::Config {
                         continue;
                     };
                     let history_path = self.history_path(path, server_version);
-                    let history_content = tokio::fs::read(history_path).await?;
+                    let history_content = fpm::history_store::read(&history_path).await?;
                     if sha2::Sha256::digest(content).eq(&sha2::Sha256::digest(history_content)) {
                         already_added_files.push(fpm::workspace::WorkspaceEntry {
                             filename: path.to_string(),
@@ -309,38 +438,43 @@ impl fpm::Config {
                         continue;
                     }
 
-                    if server_file_edit.version.eq(version) {
+                    if std::str::from_utf8(content)
+                        .map(has_conflict_markers)
+                        .unwrap_or(false)
+                    {
+                        // unresolved conflict of an earlier merge, must not be synced
+                        *status = Status::Conflict(server_file_edit.version);
                         continue;
                     }
 
-                    let ancestor_content = if let Ok(content) =
-                        tokio::fs::read_to_string(self.history_path(path, *version)).await
-                    {
-                        content
-                    } else {
-                        // binary file like images, can't resolve conflict
-                        *status = Status::Conflict(server_file_edit.version);
+                    if server_file_edit.version.eq(version) {
                         continue;
-                    };
+                    }
+
+                    let ancestor_content =
+                        read_ancestor(&self.history_path(path, *version)).await?;
 
                     // attempt resolving conflict
-                    let theirs_content = tokio::fs::read_to_string(
-                        self.history_path(path, server_file_edit.version),
+                    let theirs_content = fpm::history_store::read(
+                        &self.history_path(path, server_file_edit.version),
                     )
                     .await?;
-                    let ours_content = String::from_utf8(content.clone())?;
 
-                    match diffy::MergeOptions::new()
-                        .set_conflict_style(diffy::ConflictStyle::Merge)
-                        .merge(&ancestor_content, &ours_content, &theirs_content)
-                    {
-                        Ok(data) => {
-                            fpm::utils::update(self.root.join(filename), data.as_bytes()).await?;
-                            *content = data.as_bytes().to_vec();
+                    match merge(ancestor_content.as_deref(), content, &theirs_content) {
+                        MergeResult::Merged(data) => {
+                            fpm::utils::update(self.root.join(filename), &data).await?;
+                            *content = data;
                             *version = server_file_edit.version;
                         }
-                        Err(_) => {
-                            // can't resolve conflict, so cannot sync
+                        MergeResult::Conflicted(data) => {
+                            // can't resolve all the hunks, so cannot sync, the overlapping
+                            // ones are marked in the file for `fpm resolve-conflict`
+                            fpm::utils::update(self.root.join(filename), &data).await?;
+                            *content = data;
+                            *status = Status::Conflict(server_file_edit.version);
+                        }
+                        MergeResult::Binary => {
+                            // binary file like images, can't resolve conflict
                             *status = Status::Conflict(server_file_edit.version);
                         }
                     }
@@ -382,3 +516,77 @@ impl fpm::Config {
         Ok((already_added_files, already_removed_files))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn merge_non_overlapping() {
+        let ancestor = "a\nb\nc\nd\ne\n";
+        let ours = "a\nB\nc\nd\ne\n";
+        let theirs = "a\nb\nc\nd\nE\n";
+        assert_eq!(
+            super::merge(
+                Some(ancestor.as_bytes()),
+                ours.as_bytes(),
+                theirs.as_bytes()
+            ),
+            super::MergeResult::Merged("a\nB\nc\nd\nE\n".as_bytes().to_vec())
+        );
+    }
+
+    #[test]
+    fn merge_overlapping() {
+        let ancestor = "a\nb\nc\nd\ne\n";
+        let ours = "A\nb\nc\nd\nours\n";
+        let theirs = "a\nb\nc\nd\ntheirs\n";
+        let merged = match super::merge(
+            Some(ancestor.as_bytes()),
+            ours.as_bytes(),
+            theirs.as_bytes(),
+        ) {
+            super::MergeResult::Conflicted(merged) => String::from_utf8(merged).unwrap(),
+            t => panic!("expected conflict, found: {:?}", t),
+        };
+        assert!(super::has_conflict_markers(merged.as_str()));
+        assert_eq!(
+            super::resolve_conflict_markers(merged.as_str(), true),
+            "A\nb\nc\nd\nours\n"
+        );
+        assert_eq!(
+            super::resolve_conflict_markers(merged.as_str(), false),
+            "A\nb\nc\nd\ntheirs\n"
+        );
+    }
+
+    #[test]
+    fn merge_without_ancestor() {
+        assert_eq!(
+            super::merge(None, "a\nb\n".as_bytes(), "a\nb\n".as_bytes()),
+            super::MergeResult::Merged("a\nb\n".as_bytes().to_vec())
+        );
+        let merged = match super::merge(None, "a\nB\n".as_bytes(), "a\nb".as_bytes()) {
+            super::MergeResult::Conflicted(merged) => String::from_utf8(merged).unwrap(),
+            t => panic!("expected conflict, found: {:?}", t),
+        };
+        assert_eq!(
+            super::resolve_conflict_markers(merged.as_str(), true),
+            "a\nB\n"
+        );
+        assert_eq!(
+            super::resolve_conflict_markers(merged.as_str(), false),
+            "a\nb\n"
+        );
+    }
+
+    #[test]
+    fn merge_binary() {
+        assert_eq!(
+            super::merge(
+                Some(&[0, 159, 146, 150][..]),
+                &[0, 159, 146, 151],
+                &[0, 159, 146, 152]
+            ),
+            super::MergeResult::Binary
+        );
+    }
+}
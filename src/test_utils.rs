@@ -0,0 +1,62 @@
+//! Fixtures shared by the tests of the crate
+
+/// A directory in the temporary directory of the system the tests write a package in, removed
+/// when it is dropped, so a failing test does not leave it behind
+pub(crate) struct TempPackage {
+    pub(crate) root: camino::Utf8PathBuf,
+}
+
+impl TempPackage {
+    /// An empty `fpm-<name>-<process id>` directory, a directory left by an earlier run is
+    /// removed first
+    pub(crate) fn empty(name: &str) -> TempPackage {
+        let root = camino::Utf8PathBuf::from_path_buf(std::env::temp_dir().join(format!(
+            "fpm-{}-{}",
+            name,
+            std::process::id()
+        )))
+        .unwrap();
+        if root.exists() {
+            std::fs::remove_dir_all(&root).unwrap();
+        }
+        std::fs::create_dir_all(&root).unwrap();
+        TempPackage { root }
+    }
+
+    /// The package `amitu.com`, with only its `FPM.ftd`
+    pub(crate) fn new(name: &str) -> TempPackage {
+        let package = TempPackage::empty(name);
+        package.write("FPM.ftd", "-- import: fpm\n\n-- fpm.package: amitu.com\n");
+        package
+    }
+
+    /// Writes `content` in the file at `path`, relative to the root, with its parent directories
+    pub(crate) fn write(&self, path: &str, content: &str) {
+        let path = self.root.join(path);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).unwrap();
+        }
+        std::fs::write(path, content).unwrap();
+    }
+
+    pub(crate) async fn config(&self) -> fpm::Config {
+        fpm::Config::read(Some(self.root.to_string()), false, None)
+            .await
+            .unwrap()
+    }
+}
+
+impl Drop for TempPackage {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.root);
+    }
+}
+
+/// Runs `f` to completion on a new runtime, for the tests of async functions
+pub(crate) fn block_on<F: std::future::Future>(f: F) -> F::Output {
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .unwrap()
+        .block_on(f)
+}
@@ -0,0 +1,139 @@
+/// Where `fpm serve` gets its certificate from when TLS is enabled.
+#[derive(Debug, Clone)]
+pub enum Certificate {
+    /// PEM encoded certificate chain and private key provided via `--tls-cert` and
+    /// `--tls-key`.
+    Files {
+        cert: camino::Utf8PathBuf,
+        key: camino::Utf8PathBuf,
+    },
+    /// Generate (or reuse a previously generated) self-signed certificate. Only meant for
+    /// local testing, browsers are going to show a warning for these.
+    SelfSigned,
+}
+
+#[derive(Debug, Clone)]
+pub struct TlsOptions {
+    pub certificate: Certificate,
+    /// If set, plain HTTP is also served on this port, and every request on it is
+    /// redirected to the HTTPS one.
+    pub http_redirect_port: Option<u16>,
+    /// `max-age` sent in `Strict-Transport-Security` header, `0` disables HSTS.
+    pub hsts_max_age: u64,
+}
+
+impl TlsOptions {
+    pub(crate) fn hsts_header(&self) -> Option<String> {
+        if self.hsts_max_age == 0 {
+            return None;
+        }
+        Some(format!("max-age={}", self.hsts_max_age))
+    }
+}
+
+pub(crate) async fn server_config(
+    options: &TlsOptions,
+    bind_address: &str,
+) -> fpm::Result<rustls::ServerConfig> {
+    let (cert, key) = match options.certificate {
+        Certificate::Files { ref cert, ref key } => (cert.to_owned(), key.to_owned()),
+        Certificate::SelfSigned => self_signed(bind_address).await?,
+    };
+
+    let certs = read_certificates(&cert)?;
+    let key = read_private_key(&key)?;
+
+    rustls::ServerConfig::builder()
+        .with_safe_defaults()
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .map_err(|e| fpm::Error::UsageError {
+            message: format!("invalid tls certificate/key ({}): {}", cert, e),
+        })
+}
+
+fn read_certificates(path: &camino::Utf8Path) -> fpm::Result<Vec<rustls::Certificate>> {
+    let mut reader = std::io::BufReader::new(std::fs::File::open(path)?);
+    let certs: Vec<rustls::Certificate> = rustls_pemfile::certs(&mut reader)?
+        .into_iter()
+        .map(rustls::Certificate)
+        .collect();
+    if certs.is_empty() {
+        return Err(fpm::Error::UsageError {
+            message: format!("no certificate found in {}", path),
+        });
+    }
+    Ok(certs)
+}
+
+fn read_private_key(path: &camino::Utf8Path) -> fpm::Result<rustls::PrivateKey> {
+    let mut reader = std::io::BufReader::new(std::fs::File::open(path)?);
+    for item in rustls_pemfile::read_all(&mut reader)? {
+        match item {
+            rustls_pemfile::Item::PKCS8Key(key)
+            | rustls_pemfile::Item::RSAKey(key)
+            | rustls_pemfile::Item::ECKey(key) => return Ok(rustls::PrivateKey(key)),
+            _ => continue,
+        }
+    }
+    Err(fpm::Error::UsageError {
+        message: format!("no private key found in {}", path),
+    })
+}
+
+/// Self-signed certificates are kept in `.packages/.tls/` so we do not generate a new one,
+/// and the browser does not ask to trust a new certificate, on every `fpm serve`.
+async fn self_signed(
+    bind_address: &str,
+) -> fpm::Result<(camino::Utf8PathBuf, camino::Utf8PathBuf)> {
+    let root = camino::Utf8PathBuf::from_path_buf(std::env::current_dir()?)
+        .expect("FPM-Error: Unable to change path")
+        .join(".packages")
+        .join(".tls");
+    let cert_path = root.join("cert.pem");
+    let key_path = root.join("key.pem");
+    if cert_path.exists() && key_path.exists() {
+        return Ok((cert_path, key_path));
+    }
+
+    let mut names = vec!["localhost".to_string(), "127.0.0.1".to_string()];
+    if !names.iter().any(|v| v.eq(bind_address)) && !bind_address.eq("0.0.0.0") {
+        names.push(bind_address.to_string());
+    }
+    let certificate = rcgen::generate_simple_self_signed(names).map_err(|e| {
+        fpm::Error::GenericError(format!("failed to generate self-signed certificate: {}", e))
+    })?;
+    let cert = certificate
+        .serialize_pem()
+        .map_err(|e| fpm::Error::GenericError(e.to_string()))?;
+
+    fpm::utils::update(&cert_path, cert.as_bytes()).await?;
+    fpm::utils::update(
+        &key_path,
+        certificate.serialize_private_key_pem().as_bytes(),
+    )
+    .await?;
+    println!("Generated self-signed certificate: {}", cert_path);
+    Ok((cert_path, key_path))
+}
+
+/// Handler for the plain HTTP listener: send the client to the same url on the HTTPS port.
+pub(crate) fn redirect_to_https(
+    req: &actix_web::HttpRequest,
+    https_port: u16,
+) -> fpm::http::Response {
+    let host = req.connection_info().host().to_string();
+    let host = match host.rsplit_once(':') {
+        // ipv6 hosts look like `[::1]:8000`, only strip the port part
+        Some((h, port)) if port.chars().all(|c| c.is_ascii_digit()) => h.to_string(),
+        _ => host,
+    };
+    let location = if https_port == 443 {
+        format!("https://{}{}", host, req.uri())
+    } else {
+        format!("https://{}:{}{}", host, https_port, req.uri())
+    };
+    actix_web::HttpResponse::PermanentRedirect()
+        .insert_header((actix_web::http::header::LOCATION, location))
+        .finish()
+}
@@ -28,11 +28,11 @@ pub(crate) async fn get_tracking_info(
 pub(crate) async fn get_tracking_info_(
     track_path: &camino::Utf8PathBuf,
 ) -> fpm::Result<Vec<fpm::track::TrackingInfo>> {
-    if !track_path.exists() {
+    if !fpm::history_store::exists(track_path) {
         return fpm::usage_error(format!("No tracking found for {}", track_path));
     }
 
-    let doc = tokio::fs::read_to_string(&track_path).await?;
+    let doc = fpm::history_store::read_to_string(track_path).await?;
     resolve_tracking_info(&doc, track_path).await
 }
 
@@ -35,3 +35,123 @@ pub(crate) fn get_tracks(
     }
     Ok(tracks)
 }
+
+#[derive(serde::Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum TrackState {
+    NeverMarked,
+    Outdated,
+    UpToDate,
+}
+
+impl TrackState {
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            TrackState::NeverMarked => "never-marked",
+            TrackState::Outdated => "outdated",
+            TrackState::UpToDate => "up-to-date",
+        }
+    }
+}
+
+/// `source` tracks `target`, `fpm start-tracking <source> --target <target>`
+#[derive(serde::Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) struct TrackRelation {
+    pub source: String,
+    pub target: String,
+    pub status: TrackState,
+    /// Time between the version of the target last marked up to date and its latest version
+    pub outdated_seconds: Option<u64>,
+    /// Snapshot timestamp of the target when last marked up to date
+    #[serde(skip)]
+    pub marked_timestamp: Option<u128>,
+    /// Latest snapshot timestamp of the target
+    #[serde(skip)]
+    pub target_timestamp: u128,
+}
+
+/// Every source→target relation in `.tracks`, sorted by source and target
+pub(crate) async fn get_track_relations(config: &fpm::Config) -> fpm::Result<Vec<TrackRelation>> {
+    use itertools::Itertools;
+
+    let track_dir = config.track_dir();
+    if !track_dir.exists() {
+        return Ok(vec![]);
+    }
+    let snapshots = fpm::snapshot::get_latest_snapshots(&config.root).await?;
+    let sources = ignore::WalkBuilder::new(&track_dir)
+        .hidden(false)
+        .build()
+        .flatten()
+        .filter(|v| v.file_type().map(|t| t.is_file()).unwrap_or(false))
+        .filter_map(|v| camino::Utf8PathBuf::from_path_buf(v.into_path()).ok())
+        .filter_map(|v| {
+            v.strip_prefix(&track_dir)
+                .ok()
+                .and_then(|v| v.as_str().strip_suffix(".track"))
+                .map(ToString::to_string)
+        })
+        // the tracks of the change requests are kept in `.tracks/-/<cr number>/`
+        .filter(|v| !v.starts_with("-/"))
+        .sorted()
+        .collect_vec();
+
+    let mut relations = vec![];
+    for source in sources {
+        relations.extend(get_source_relations(
+            config.root.as_str(),
+            source.as_str(),
+            &snapshots,
+        )?);
+    }
+    Ok(relations)
+}
+
+/// The relations of `source`, targets removed or never synced are warned about and left out
+pub(crate) fn get_source_relations(
+    base_path: &str,
+    source: &str,
+    snapshots: &std::collections::BTreeMap<String, u128>,
+) -> fpm::Result<Vec<TrackRelation>> {
+    let tracks = get_tracks(base_path, &fpm::utils::track_path(source, base_path))?;
+    let mut relations = vec![];
+    for track in tracks.into_values() {
+        // tracking of the original file by a translation package
+        if source.eq(track.filename.as_str()) && track.last_merged_version.is_some() {
+            continue;
+        }
+        let target_timestamp = match snapshots.get(&track.filename) {
+            Some(timestamp) => *timestamp,
+            None => {
+                fpm::warning!(
+                    "{} is tracked by {}, but {} is either removed or never synced",
+                    track.filename,
+                    source,
+                    track.filename
+                );
+                continue;
+            }
+        };
+        let (status, outdated_seconds) = match track.other_timestamp {
+            None => (TrackState::NeverMarked, None),
+            Some(marked) if marked == target_timestamp => (TrackState::UpToDate, None),
+            Some(marked) => (
+                TrackState::Outdated,
+                Some(
+                    std::time::Duration::from_nanos(target_timestamp.saturating_sub(marked) as u64)
+                        .as_secs(),
+                ),
+            ),
+        };
+        relations.push(TrackRelation {
+            source: source.to_string(),
+            target: track.filename,
+            status,
+            outdated_seconds,
+            marked_timestamp: track.other_timestamp,
+            target_timestamp,
+        });
+    }
+    Ok(relations)
+}
@@ -118,13 +118,15 @@ impl TranslatedDocument {
                 config.original_path()?.as_str(),
                 last_marked_on,
             );
-            let last_marked_on_data = tokio::fs::read_to_string(last_marked_on_path).await?;
+            let last_marked_on_data =
+                fpm::history_store::read_to_string(&last_marked_on_path).await?;
             let original_latest_path = fpm::utils::history_path(
                 original.get_id().as_str(),
                 config.original_path()?.as_str(),
                 original_latest,
             );
-            let original_latest_data = tokio::fs::read_to_string(original_latest_path).await?;
+            let original_latest_data =
+                fpm::history_store::read_to_string(&original_latest_path).await?;
 
             let patch = diffy::create_patch(&last_marked_on_data, &original_latest_data);
             Ok(patch.to_string().replace("---", "\\---"))
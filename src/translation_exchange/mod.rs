@@ -0,0 +1,455 @@
+//! Translatable strings of ftd documents, exchanged with translators as XLIFF or gettext PO so
+//! they can be translated in CAT tools instead of editing `.ftd` files.
+//!
+//! The captions, text headers and bodies of the sections are the strings, the rest of the
+//! document is kept as is. A string is identified by the index of its section in the document,
+//! `s3.caption`, `s3.title` or `s3.body`, so the translations are put back in the same place of
+//! the original document on import.
+
+mod po;
+pub mod provider;
+mod xliff;
+
+// `$name` references in the strings, they are not text
+static VARIABLE: once_cell::sync::Lazy<regex::Regex> = once_cell::sync::Lazy::new(|| {
+    regex::Regex::new(r"\$[A-Za-z_][\w-]*(\.[A-Za-z_][\w-]*)*").unwrap()
+});
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Xliff,
+    Po,
+}
+
+impl Format {
+    pub fn from_name(name: &str) -> fpm::Result<Format> {
+        match name {
+            "xliff" | "xlf" => Ok(Format::Xliff),
+            "po" => Ok(Format::Po),
+            t => fpm::usage_error(format!("unknown format `{}`, expected xliff or po", t)),
+        }
+    }
+
+    pub fn from_path(path: &camino::Utf8Path) -> Option<Format> {
+        match path.extension() {
+            Some("xlf") | Some("xliff") => Some(Format::Xliff),
+            Some("po") | Some("pot") => Some(Format::Po),
+            _ => None,
+        }
+    }
+}
+
+/// A translatable string of a document
+#[derive(Debug, Clone, PartialEq)]
+pub struct Unit {
+    /// `s<section index>.caption`, `s<section index>.<header>` or `s<section index>.body`
+    pub id: String,
+    /// `<section name> <caption|header|body>`, shown to the translators as context
+    pub note: String,
+    pub source: String,
+    pub target: Option<String>,
+    /// the target is the translation of an older source, it has to be checked again
+    pub needs_review: bool,
+}
+
+/// The strings of a document, `id` is the path of the document in the package
+#[derive(Debug, Clone, PartialEq)]
+pub struct Document {
+    pub id: String,
+    pub units: Vec<Unit>,
+}
+
+pub fn export(
+    documents: &[Document],
+    source_language: &str,
+    target_language: Option<&str>,
+    format: Format,
+) -> String {
+    match format {
+        Format::Xliff => xliff::write(documents, source_language, target_language),
+        Format::Po => po::write(documents, target_language),
+    }
+}
+
+pub fn import(content: &str, format: Format) -> fpm::Result<Vec<Document>> {
+    let documents = match format {
+        Format::Xliff => xliff::read(content)?,
+        Format::Po => po::read(content)?,
+    };
+    for document in documents.iter() {
+        if document.id.starts_with('/')
+            || document.id.contains('\\')
+            || document.id.split('/').any(|v| v == "..")
+        {
+            return fpm::usage_error(format!("invalid document `{}`", document.id));
+        }
+    }
+    Ok(documents)
+}
+
+/// The translatable strings of the document
+pub fn extract(content: &str) -> Vec<Unit> {
+    pieces(content)
+        .into_iter()
+        .filter_map(|piece| match piece {
+            Piece::Text(_) => None,
+            Piece::Unit { id, note, text, .. } => Some(Unit {
+                id,
+                note,
+                source: text.to_string(),
+                target: None,
+                needs_review: false,
+            }),
+        })
+        .collect()
+}
+
+/// Fills the targets of `units` of the original document with the strings of the `translated`
+/// document, the ones whose source changed since the original was `last_marked` up to date need
+/// review
+pub fn prefill(units: &mut [Unit], last_marked: &str, translated: &str) {
+    let last_marked: std::collections::HashMap<String, String> = extract(last_marked)
+        .into_iter()
+        .map(|v| (v.id, v.source))
+        .collect();
+    let translated: std::collections::HashMap<String, String> = extract(translated)
+        .into_iter()
+        .map(|v| (v.id, v.source))
+        .collect();
+    for unit in units.iter_mut() {
+        if let Some(target) = translated.get(&unit.id) {
+            unit.target = Some(target.to_string());
+            unit.needs_review = last_marked.get(&unit.id) != Some(&unit.source);
+        }
+    }
+}
+
+/// The original document with the strings replaced by the targets of `units`, and the number of
+/// strings without a reviewed translation. A string whose source does not match the original
+/// anymore is left untranslated.
+pub fn apply(content: &str, units: &[Unit]) -> (String, usize) {
+    let units: std::collections::HashMap<&str, &Unit> =
+        units.iter().map(|v| (v.id.as_str(), v)).collect();
+    let mut untranslated = 0;
+    let mut translated = String::new();
+    for piece in pieces(content) {
+        match piece {
+            Piece::Text(text) => translated.push_str(text),
+            Piece::Unit {
+                id,
+                text,
+                multiline,
+                ..
+            } => {
+                let target = units
+                    .get(id.as_str())
+                    .filter(|unit| unit.source.trim() == text)
+                    .and_then(|unit| {
+                        unit.target
+                            .as_ref()
+                            .filter(|v| !v.trim().is_empty())
+                            .map(|v| (v.trim(), unit.needs_review))
+                    });
+                match target {
+                    Some((target, needs_review)) => {
+                        if needs_review {
+                            untranslated += 1;
+                        }
+                        if multiline {
+                            translated.push_str(target);
+                        } else {
+                            // captions and headers are single line
+                            translated.push_str(
+                                target
+                                    .split_whitespace()
+                                    .collect::<Vec<_>>()
+                                    .join(" ")
+                                    .as_str(),
+                            );
+                        }
+                    }
+                    None => {
+                        untranslated += 1;
+                        translated.push_str(text);
+                    }
+                }
+            }
+        }
+    }
+    (translated, untranslated)
+}
+
+// sections whose caption, headers and body are not text
+const STRUCTURE_SECTIONS: &[&str] = &["import", "end", "record", "or-type", "component", "export"];
+// declarations of variables whose value is never text, `optional` ones are left out too
+const NON_TEXT_DECLARATIONS: &[&str] = &["boolean", "integer", "decimal", "optional"];
+// values which are ftd keywords and not text
+const KEYWORDS: &[&str] = &["true", "false", "null"];
+// headers which are text even when they are a single word
+const TEXT_HEADERS: &[&str] = &[
+    "title",
+    "description",
+    "caption",
+    "text",
+    "label",
+    "alt",
+    "placeholder",
+    "heading",
+    "subtitle",
+    "tooltip",
+    "message",
+];
+// headers which are never text
+const NON_TEXT_HEADERS: &[&str] = &[
+    "id", "if", "link", "src", "url", "role", "region", "document",
+];
+
+enum Piece<'a> {
+    Text(&'a str),
+    Unit {
+        id: String,
+        note: String,
+        text: &'a str,
+        multiline: bool,
+    },
+}
+
+struct Section<'a> {
+    index: usize,
+    name: &'a str,
+    translatable: bool,
+}
+
+/// The document split in strings and the rest, joining the pieces gives back the document
+fn pieces(content: &str) -> Vec<Piece> {
+    let mut pieces = vec![];
+    let mut section: Option<Section> = None;
+    let mut sections = 0;
+    // start of the body of the current section
+    let mut body: Option<usize> = None;
+    let mut offset = 0;
+    for line in content.split_inclusive('\n') {
+        let start = offset;
+        offset += line.len();
+        let section_line = line.starts_with("-- ") || line.starts_with("/-- ");
+        if let Some(body_start) = body {
+            if !section_line {
+                continue;
+            }
+            push_body(&mut pieces, &content[body_start..start], section.as_ref());
+            body = None;
+        }
+
+        if section_line {
+            let commented = line.starts_with('/');
+            let head_start = if commented { 4 } else { 3 };
+            let head_end = line.find(':').unwrap_or(line.len());
+            if head_end < head_start {
+                pieces.push(Piece::Text(line));
+                continue;
+            }
+            let head = &line[head_start..head_end];
+            let kind = head.split_whitespace().next().unwrap_or_default();
+            let current = Section {
+                index: sections,
+                name: head.split_whitespace().last().unwrap_or_default(),
+                translatable: !commented
+                    && !STRUCTURE_SECTIONS.contains(&kind)
+                    && !NON_TEXT_DECLARATIONS.contains(&kind)
+                    && !kind.starts_with("fpm."),
+            };
+            sections += 1;
+            if current.translatable && head_end < line.len() && is_text(&line[head_end + 1..]) {
+                push_value(
+                    &mut pieces,
+                    line,
+                    head_end + 1,
+                    format!("s{}.caption", current.index),
+                    format!("{} caption", current.name),
+                );
+            } else {
+                pieces.push(Piece::Text(line));
+            }
+            section = Some(current);
+            continue;
+        }
+
+        let current = match section {
+            Some(ref current) => current,
+            None => {
+                pieces.push(Piece::Text(line));
+                continue;
+            }
+        };
+        if line.trim().is_empty() {
+            pieces.push(Piece::Text(line));
+            body = Some(offset);
+            continue;
+        }
+        let header = match line.find(':') {
+            Some(colon) if current.translatable && !line.starts_with(";;") => {
+                let key = line[..colon].split_whitespace().last().unwrap_or_default();
+                let value = &line[colon + 1..];
+                if is_text_header(key, value) {
+                    Some((colon, key))
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        };
+        match header {
+            Some((colon, key)) => push_value(
+                &mut pieces,
+                line,
+                colon + 1,
+                format!("s{}.{}", current.index, key),
+                format!("{} {}", current.name, key),
+            ),
+            None => pieces.push(Piece::Text(line)),
+        }
+    }
+    if let Some(body_start) = body {
+        push_body(&mut pieces, &content[body_start..], section.as_ref());
+    }
+    pieces
+}
+
+fn push_value<'a>(
+    pieces: &mut Vec<Piece<'a>>,
+    line: &'a str,
+    value_start: usize,
+    id: String,
+    note: String,
+) {
+    let value = &line[value_start..];
+    let leading = value.len() - value.trim_start().len();
+    let text = value.trim();
+    pieces.push(Piece::Text(&line[..value_start + leading]));
+    pieces.push(Piece::Unit {
+        id,
+        note,
+        text,
+        multiline: false,
+    });
+    pieces.push(Piece::Text(&line[value_start + leading + text.len()..]));
+}
+
+fn push_body<'a>(pieces: &mut Vec<Piece<'a>>, body: &'a str, section: Option<&Section>) {
+    let section = match section {
+        Some(section) if section.translatable && !section.name.ends_with("code") => section,
+        _ => {
+            pieces.push(Piece::Text(body));
+            return;
+        }
+    };
+    if !is_text(body) {
+        pieces.push(Piece::Text(body));
+        return;
+    }
+    let leading = body.len() - body.trim_start().len();
+    let text = body.trim();
+    pieces.push(Piece::Text(&body[..leading]));
+    pieces.push(Piece::Unit {
+        id: format!("s{}.body", section.index),
+        note: format!("{} body", section.name),
+        text,
+        multiline: true,
+    });
+    pieces.push(Piece::Text(&body[leading + text.len()..]));
+}
+
+fn is_text(value: &str) -> bool {
+    let value = value.trim();
+    !value.starts_with('$')
+        && !value.starts_with('/')
+        && !value.contains("://")
+        && !KEYWORDS.iter().any(|v| value.eq_ignore_ascii_case(v))
+        && value.chars().any(char::is_alphabetic)
+}
+
+fn is_text_header(key: &str, value: &str) -> bool {
+    !key.starts_with('$')
+        && !NON_TEXT_HEADERS.contains(&key)
+        && (TEXT_HEADERS.contains(&key) || value.split_whitespace().nth(1).is_some())
+        && is_text(value)
+}
+
+#[cfg(test)]
+mod tests {
+    const DOCUMENT: &str = indoc::indoc! {"
+        -- import: fpm
+
+        -- ds.page: Hello World
+        id: hello
+        description: A page that greets
+
+        -- ds.markdown:
+
+        Welcome to **fpm**, $name.
+
+        -- ds.code:
+        lang: rs
+
+        fn main() {}
+
+        -- integer count: 10
+
+        -- boolean show-footer: true
+
+        -- optional string tagline: Hello there
+
+        -- ds.text: true
+        heading: false
+
+        -- end: ds.page
+    "};
+
+    #[test]
+    fn extract() {
+        let units = super::extract(DOCUMENT);
+        assert_eq!(
+            units
+                .iter()
+                .map(|v| (v.id.as_str(), v.source.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("s1.caption", "Hello World"),
+                ("s1.description", "A page that greets"),
+                ("s2.body", "Welcome to **fpm**, $name."),
+            ]
+        );
+    }
+
+    #[test]
+    fn apply() {
+        let mut units = super::extract(DOCUMENT);
+        units[0].target = Some("Namaste Duniya".to_string());
+        units[2].target = Some("fpm mein swagat hai, $name.".to_string());
+        let (translated, untranslated) = super::apply(DOCUMENT, &units);
+        assert_eq!(untranslated, 1);
+        assert_eq!(
+            translated,
+            DOCUMENT
+                .replace("Hello World", "Namaste Duniya")
+                .replace("Welcome to **fpm**, $name.", "fpm mein swagat hai, $name.")
+        );
+        assert_eq!(super::apply(DOCUMENT, &[]), (DOCUMENT.to_string(), 3));
+    }
+
+    #[test]
+    fn exchange() {
+        let mut units = super::extract(DOCUMENT);
+        units[0].target = Some("Namaste \"Duniya\" & <sab>".to_string());
+        units[1].target = Some("Abhivadan".to_string());
+        units[1].needs_review = true;
+        let documents = vec![super::Document {
+            id: "index.ftd".to_string(),
+            units,
+        }];
+        for format in [super::Format::Xliff, super::Format::Po] {
+            let exported = super::export(&documents, "en", Some("hi"), format);
+            assert_eq!(super::import(exported.as_str(), format).unwrap(), documents);
+        }
+    }
+}
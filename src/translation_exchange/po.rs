@@ -0,0 +1,158 @@
+//! gettext PO, an entry per string with `<document>#<string id>` as the `msgctxt`. Translations
+//! which need review are `fuzzy`.
+
+pub(super) fn write(documents: &[super::Document], target_language: Option<&str>) -> String {
+    let mut po =
+        "msgid \"\"\nmsgstr \"\"\n\"Content-Type: text/plain; charset=UTF-8\\n\"\n".to_string();
+    if let Some(language) = target_language {
+        po.push_str(format!("\"Language: {}\\n\"\n", escape(language)).as_str());
+    }
+    for document in documents {
+        for unit in document.units.iter() {
+            po.push_str(format!("\n#. {}\n", unit.note).as_str());
+            if unit.needs_review && unit.target.is_some() {
+                po.push_str("#, fuzzy\n");
+            }
+            po.push_str(
+                format!(
+                    "msgctxt {}\nmsgid {}\nmsgstr {}\n",
+                    quote(format!("{}#{}", document.id, unit.id).as_str()),
+                    quote(unit.source.as_str()),
+                    quote(unit.target.as_deref().unwrap_or_default())
+                )
+                .as_str(),
+            );
+        }
+    }
+    po
+}
+
+#[derive(Default)]
+struct Entry {
+    note: Option<String>,
+    fuzzy: bool,
+    msgctxt: Option<String>,
+    msgid: Option<String>,
+    msgstr: Option<String>,
+}
+
+pub(super) fn read(content: &str) -> fpm::Result<Vec<super::Document>> {
+    let mut documents: Vec<super::Document> = vec![];
+    let mut entry = Entry::default();
+    for (line_number, line) in content.lines().enumerate() {
+        let line = line.trim();
+        let invalid = || fpm::Error::UsageError {
+            message: format!("invalid PO line {}: `{}`", line_number + 1, line),
+        };
+        // entries are separated by empty lines, but a new entry starting right after the
+        // `msgstr` of the previous one is fine too
+        if !line.is_empty()
+            && !line.starts_with('"')
+            && !line.starts_with("msgstr")
+            && entry.msgstr.is_some()
+        {
+            add_entry(&mut documents, std::mem::take(&mut entry))?;
+        }
+        if line.is_empty() {
+            add_entry(&mut documents, std::mem::take(&mut entry))?;
+        } else if let Some(flags) = line.strip_prefix("#,") {
+            entry.fuzzy = flags.split(',').any(|v| v.trim() == "fuzzy");
+        } else if let Some(note) = line.strip_prefix("#.") {
+            entry.note = Some(note.trim().to_string());
+        } else if line.starts_with('#') {
+            continue;
+        } else if let Some(value) = line.strip_prefix("msgctxt ") {
+            entry.msgctxt = Some(unquote(value).ok_or_else(invalid)?);
+        } else if let Some(value) = line.strip_prefix("msgid ") {
+            entry.msgid = Some(unquote(value).ok_or_else(invalid)?);
+        } else if let Some(value) = line.strip_prefix("msgstr ") {
+            entry.msgstr = Some(unquote(value).ok_or_else(invalid)?);
+        } else if line.starts_with('"') {
+            let value = unquote(line).ok_or_else(invalid)?;
+            match (&mut entry.msgstr, &mut entry.msgid, &mut entry.msgctxt) {
+                (Some(msgstr), _, _) => msgstr.push_str(value.as_str()),
+                (None, Some(msgid), _) => msgid.push_str(value.as_str()),
+                (None, None, Some(msgctxt)) => msgctxt.push_str(value.as_str()),
+                _ => return Err(invalid()),
+            }
+        } else {
+            return Err(invalid());
+        }
+    }
+    add_entry(&mut documents, entry)?;
+    Ok(documents)
+}
+
+fn add_entry(documents: &mut Vec<super::Document>, entry: Entry) -> fpm::Result<()> {
+    let (msgctxt, msgid) = match (entry.msgctxt, entry.msgid) {
+        (Some(msgctxt), Some(msgid)) => (msgctxt, msgid),
+        // the header has no `msgctxt`
+        (None, _) => return Ok(()),
+        (Some(msgctxt), None) => {
+            return fpm::usage_error(format!("PO entry {} without msgid", msgctxt))
+        }
+    };
+    let (document_id, unit_id) = match msgctxt.rsplit_once('#') {
+        Some(v) => v,
+        None => {
+            return fpm::usage_error(format!(
+                "PO entry `{}` is not from `fpm translation export`, msgctxt should be \
+                <document>#<string id>",
+                msgctxt
+            ))
+        }
+    };
+    let unit = super::Unit {
+        id: unit_id.to_string(),
+        note: entry.note.unwrap_or_default(),
+        source: msgid,
+        target: entry.msgstr.filter(|v| !v.is_empty()),
+        needs_review: entry.fuzzy,
+    };
+    match documents.iter_mut().find(|v| v.id == document_id) {
+        Some(document) => document.units.push(unit),
+        None => documents.push(super::Document {
+            id: document_id.to_string(),
+            units: vec![unit],
+        }),
+    }
+    Ok(())
+}
+
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+}
+
+// multi line strings are written a line per line
+fn quote(s: &str) -> String {
+    if !s.contains('\n') {
+        return format!("\"{}\"", escape(s));
+    }
+    let mut quoted = "\"\"".to_string();
+    for line in s.split_inclusive('\n') {
+        quoted.push_str(format!("\n\"{}\"", escape(line)).as_str());
+    }
+    quoted
+}
+
+fn unquote(s: &str) -> Option<String> {
+    let s = s.trim().strip_prefix('"')?.strip_suffix('"')?;
+    let mut unquoted = String::new();
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unquoted.push(c);
+            continue;
+        }
+        match chars.next()? {
+            'n' => unquoted.push('\n'),
+            't' => unquoted.push('\t'),
+            'r' => unquoted.push('\r'),
+            c => unquoted.push(c),
+        }
+    }
+    Some(unquoted)
+}
@@ -0,0 +1,261 @@
+//! Machine translation providers, `fpm translate` drafts the missing and out-dated documents of a
+//! translation package with one.
+
+pub const PROVIDERS: &[&str] = &["libretranslate"];
+
+/// Translates texts from one language to another
+pub trait Provider {
+    /// `texts` translated from the `source` to the `target` language, in the same order
+    fn translate<'a>(
+        &'a self,
+        texts: &'a [String],
+        source: &'a str,
+        target: &'a str,
+    ) -> futures::future::BoxFuture<'a, fpm::Result<Vec<String>>>;
+}
+
+pub fn from_name(
+    name: &str,
+    url: Option<&str>,
+    api_key: Option<&str>,
+) -> fpm::Result<Box<dyn Provider + Send + Sync>> {
+    match name {
+        "libretranslate" => Ok(Box::new(LibreTranslate {
+            url: url
+                .unwrap_or(LibreTranslate::DEFAULT_URL)
+                .trim_end_matches('/')
+                .to_string(),
+            api_key: api_key.map(ToString::to_string),
+        })),
+        t => fpm::usage_error(format!(
+            "unknown translation provider `{}`, available: {}",
+            t,
+            PROVIDERS.join(", ")
+        )),
+    }
+}
+
+/// [LibreTranslate](https://libretranslate.com), or any server with its `/translate` API
+pub struct LibreTranslate {
+    pub url: String,
+    pub api_key: Option<String>,
+}
+
+impl LibreTranslate {
+    pub const DEFAULT_URL: &'static str = "http://127.0.0.1:5000";
+}
+
+#[derive(serde::Serialize)]
+struct LibreTranslateRequest<'a> {
+    q: &'a [String],
+    source: &'a str,
+    target: &'a str,
+    format: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    api_key: Option<&'a str>,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(untagged)]
+enum LibreTranslateResponse {
+    Translated {
+        #[serde(rename = "translatedText")]
+        translated_text: Vec<String>,
+    },
+    Error {
+        error: String,
+    },
+}
+
+impl Provider for LibreTranslate {
+    fn translate<'a>(
+        &'a self,
+        texts: &'a [String],
+        source: &'a str,
+        target: &'a str,
+    ) -> futures::future::BoxFuture<'a, fpm::Result<Vec<String>>> {
+        Box::pin(async move {
+            if texts.is_empty() {
+                return Ok(vec![]);
+            }
+            let response: LibreTranslateResponse = reqwest::Client::new()
+                .post(format!("{}/translate", self.url))
+                .header(reqwest::header::USER_AGENT, "fpm")
+                .json(&LibreTranslateRequest {
+                    q: texts,
+                    source,
+                    target,
+                    format: "text",
+                    api_key: self.api_key.as_deref(),
+                })
+                .send()
+                .await?
+                .json()
+                .await?;
+            match response {
+                LibreTranslateResponse::Translated { translated_text }
+                    if translated_text.len() == texts.len() =>
+                {
+                    Ok(translated_text)
+                }
+                LibreTranslateResponse::Translated { translated_text } => {
+                    Err(fpm::Error::APIResponseError(format!(
+                        "{} returned {} translations for {} texts",
+                        self.url,
+                        translated_text.len(),
+                        texts.len()
+                    )))
+                }
+                LibreTranslateResponse::Error { error } => Err(fpm::Error::APIResponseError(
+                    format!("{}: {}", self.url, error),
+                )),
+            }
+        })
+    }
+}
+
+// texts sent to the provider in one request
+const BATCH_SIZE: usize = 50;
+
+/// Translates the units without a reviewed target with `provider`, the translations are drafts
+/// which need review. `$name` references are sent as `[[0]]`, `[[1]]`.. so they are kept as is,
+/// a unit whose references are lost in the translation is left untranslated. Returns the number
+/// of units translated.
+pub async fn draft(
+    provider: &(dyn Provider + Send + Sync),
+    units: &mut [super::Unit],
+    source: &str,
+    target: &str,
+) -> fpm::Result<usize> {
+    let mut pending: Vec<&mut super::Unit> = units
+        .iter_mut()
+        .filter(|v| v.target.is_none() || v.needs_review)
+        .collect();
+    let mut translated = 0;
+    for batch in pending.chunks_mut(BATCH_SIZE) {
+        let (texts, references): (Vec<String>, Vec<Vec<String>>) =
+            batch.iter().map(|v| protect(v.source.as_str())).unzip();
+        let translations = provider.translate(&texts, source, target).await?;
+        for ((unit, translation), references) in batch.iter_mut().zip(translations).zip(references)
+        {
+            match restore(translation.as_str(), &references) {
+                Some(translation) => {
+                    unit.target = Some(translation);
+                    unit.needs_review = true;
+                    translated += 1;
+                }
+                None => {
+                    fpm::warning!(
+                        "`{}` lost its references in the translation, it is not translated",
+                        unit.source
+                    );
+                }
+            }
+        }
+    }
+    Ok(translated)
+}
+
+fn protect(text: &str) -> (String, Vec<String>) {
+    let mut references = vec![];
+    let text = super::VARIABLE
+        .replace_all(text, |c: &regex::Captures| {
+            references.push(c[0].to_string());
+            format!("[[{}]]", references.len() - 1)
+        })
+        .to_string();
+    (text, references)
+}
+
+fn restore(text: &str, references: &[String]) -> Option<String> {
+    let mut text = text.to_string();
+    for (i, reference) in references.iter().enumerate() {
+        let placeholder = format!("[[{}]]", i);
+        if !text.contains(placeholder.as_str()) {
+            return None;
+        }
+        text = text.replace(placeholder.as_str(), reference);
+    }
+    Some(text)
+}
+
+#[cfg(test)]
+mod tests {
+    /// A LibreTranslate stub answering one request, the translation of a text is `[hi] <text>`
+    fn stub_server() -> String {
+        use std::io::{BufRead, Read, Write};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = std::io::BufReader::new(stream);
+            let mut content_length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line.trim().is_empty() {
+                    break;
+                }
+                if let Some((name, value)) = line.split_once(':') {
+                    if name.eq_ignore_ascii_case("content-length") {
+                        content_length = value.trim().parse().unwrap();
+                    }
+                }
+            }
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body).unwrap();
+            let request: serde_json::Value = serde_json::from_slice(&body).unwrap();
+            assert_eq!(request["target"], "hi");
+            let translated: Vec<String> = request["q"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|v| format!("[hi] {}", v.as_str().unwrap()))
+                .collect();
+            let response = serde_json::json!({ "translatedText": translated }).to_string();
+            write!(
+                reader.get_mut(),
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\
+                Connection: close\r\n\r\n{}",
+                response.len(),
+                response
+            )
+            .unwrap();
+        });
+        url
+    }
+
+    #[test]
+    fn draft() {
+        let provider =
+            super::from_name("libretranslate", Some(stub_server().as_str()), None).unwrap();
+        let mut units = fpm::translation_exchange::extract(indoc::indoc! {"
+            -- ds.page: Hello $name
+
+            -- ds.markdown:
+
+            Welcome
+        "});
+        units[1].target = Some("Swagat".to_string());
+
+        let translated =
+            fpm::test_utils::block_on(super::draft(provider.as_ref(), &mut units, "en", "hi"))
+                .unwrap();
+        assert_eq!(translated, 1);
+        assert_eq!(units[0].target.as_deref(), Some("[hi] Hello $name"));
+        assert!(units[0].needs_review);
+        assert_eq!(units[1].target.as_deref(), Some("Swagat"));
+    }
+
+    #[test]
+    fn restore() {
+        let (text, references) = super::protect("Hi $name, see $fpm.package.name");
+        assert_eq!(text, "Hi [[0]], see [[1]]");
+        assert_eq!(
+            super::restore("Namaste [[0]], dekho [[1]]", &references).as_deref(),
+            Some("Namaste $name, dekho $fpm.package.name")
+        );
+        assert_eq!(super::restore("Namaste", &references), None);
+    }
+}
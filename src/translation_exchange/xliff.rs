@@ -0,0 +1,176 @@
+//! XLIFF 1.2, a `<file>` per document and a `<trans-unit>` per string. Variable references in the
+//! strings, `$name`, are `<ph>` placeholders so CAT tools keep them as they are.
+
+static FILE: once_cell::sync::Lazy<regex::Regex> =
+    once_cell::sync::Lazy::new(|| regex::Regex::new(r"(?s)<file\b([^>]*)>(.*?)</file>").unwrap());
+static TRANS_UNIT: once_cell::sync::Lazy<regex::Regex> = once_cell::sync::Lazy::new(|| {
+    regex::Regex::new(r"(?s)<trans-unit\b([^>]*)>(.*?)</trans-unit>").unwrap()
+});
+static SOURCE: once_cell::sync::Lazy<regex::Regex> =
+    once_cell::sync::Lazy::new(|| regex::Regex::new(r"(?s)<source\b[^>]*>(.*?)</source>").unwrap());
+static TARGET: once_cell::sync::Lazy<regex::Regex> = once_cell::sync::Lazy::new(|| {
+    regex::Regex::new(r"(?s)<target\b([^>]*)>(.*?)</target>").unwrap()
+});
+static NOTE: once_cell::sync::Lazy<regex::Regex> =
+    once_cell::sync::Lazy::new(|| regex::Regex::new(r"(?s)<note\b[^>]*>(.*?)</note>").unwrap());
+static ATTRIBUTE: once_cell::sync::Lazy<regex::Regex> =
+    once_cell::sync::Lazy::new(|| regex::Regex::new(r#"([\w:-]+)\s*=\s*"([^"]*)""#).unwrap());
+static CHARACTER_REFERENCE: once_cell::sync::Lazy<regex::Regex> =
+    once_cell::sync::Lazy::new(|| regex::Regex::new(r"&#(x[0-9a-fA-F]+|[0-9]+);").unwrap());
+static PLACEHOLDER: once_cell::sync::Lazy<regex::Regex> =
+    once_cell::sync::Lazy::new(|| regex::Regex::new(r"(?s)<ph\b[^>]*>(.*?)</ph>").unwrap());
+static EMPTY_ELEMENT: once_cell::sync::Lazy<regex::Regex> =
+    once_cell::sync::Lazy::new(|| regex::Regex::new(r"<[^>]*/>").unwrap());
+
+pub(super) fn write(
+    documents: &[super::Document],
+    source_language: &str,
+    target_language: Option<&str>,
+) -> String {
+    let mut xliff = r#"<?xml version="1.0" encoding="UTF-8"?>
+<xliff version="1.2" xmlns="urn:oasis:names:tc:xliff:document:1.2">
+"#
+    .to_string();
+    let target_language = target_language
+        .map(|v| format!(r#" target-language="{}""#, escape(v)))
+        .unwrap_or_default();
+    for document in documents {
+        xliff.push_str(
+            format!(
+                "  <file original=\"{}\" source-language=\"{}\"{} datatype=\"plaintext\">\n    <body>\n",
+                escape(document.id.as_str()),
+                escape(source_language),
+                target_language
+            )
+            .as_str(),
+        );
+        for unit in document.units.iter() {
+            xliff.push_str(
+                format!(
+                    "      <trans-unit id=\"{}\" xml:space=\"preserve\">\n        <source>{}</source>\n",
+                    escape(unit.id.as_str()),
+                    with_placeholders(unit.source.as_str())
+                )
+                .as_str(),
+            );
+            if let Some(ref target) = unit.target {
+                let state = if unit.needs_review {
+                    "needs-review-translation"
+                } else {
+                    "translated"
+                };
+                xliff.push_str(
+                    format!(
+                        "        <target state=\"{}\">{}</target>\n",
+                        state,
+                        with_placeholders(target.as_str())
+                    )
+                    .as_str(),
+                );
+            }
+            xliff.push_str(
+                format!(
+                    "        <note>{}</note>\n      </trans-unit>\n",
+                    escape(unit.note.as_str())
+                )
+                .as_str(),
+            );
+        }
+        xliff.push_str("    </body>\n  </file>\n");
+    }
+    xliff.push_str("</xliff>\n");
+    xliff
+}
+
+pub(super) fn read(content: &str) -> fpm::Result<Vec<super::Document>> {
+    let mut documents = vec![];
+    for file in FILE.captures_iter(content) {
+        let id = match attribute(&file[1], "original") {
+            Some(id) => id,
+            None => return fpm::usage_error("XLIFF `<file>` without `original`".to_string()),
+        };
+        let mut units = vec![];
+        for unit in TRANS_UNIT.captures_iter(&file[2]) {
+            let unit_id = match attribute(&unit[1], "id") {
+                Some(unit_id) => unit_id,
+                None => {
+                    return fpm::usage_error(format!("XLIFF `<trans-unit>` without `id` in {}", id))
+                }
+            };
+            let source = match SOURCE.captures(&unit[2]) {
+                Some(source) => text(&source[1]),
+                None => {
+                    return fpm::usage_error(format!(
+                        "XLIFF `<trans-unit>` {} without `<source>` in {}",
+                        unit_id, id
+                    ))
+                }
+            };
+            let target = TARGET.captures(&unit[2]);
+            let needs_review = target
+                .as_ref()
+                .and_then(|v| attribute(&v[1], "state"))
+                .map(|v| v.starts_with("needs-review") || v.eq("needs-translation") || v.eq("new"))
+                .unwrap_or(false);
+            units.push(super::Unit {
+                id: unit_id,
+                note: NOTE
+                    .captures(&unit[2])
+                    .map(|v| text(&v[1]))
+                    .unwrap_or_default(),
+                source,
+                target: target.map(|v| text(&v[2])).filter(|v| !v.is_empty()),
+                needs_review,
+            });
+        }
+        documents.push(super::Document { id, units });
+    }
+    Ok(documents)
+}
+
+fn attribute(attributes: &str, name: &str) -> Option<String> {
+    ATTRIBUTE
+        .captures_iter(attributes)
+        .find(|v| &v[1] == name)
+        .map(|v| unescape(&v[2]))
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn unescape(s: &str) -> String {
+    let s = CHARACTER_REFERENCE.replace_all(s, |c: &regex::Captures| {
+        let code = match c[1].strip_prefix('x') {
+            Some(hex) => u32::from_str_radix(hex, 16).ok(),
+            None => c[1].parse::<u32>().ok(),
+        };
+        code.and_then(char::from_u32)
+            .map(|v| v.to_string())
+            .unwrap_or_default()
+    });
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+fn with_placeholders(s: &str) -> String {
+    let mut id = 0;
+    super::VARIABLE
+        .replace_all(escape(s).as_str(), |c: &regex::Captures| {
+            id += 1;
+            format!("<ph id=\"{}\">{}</ph>", id, &c[0])
+        })
+        .to_string()
+}
+
+// text content, placeholders are replaced by what they stand for
+fn text(s: &str) -> String {
+    let s = PLACEHOLDER.replace_all(s, "$1");
+    unescape(EMPTY_ELEMENT.replace_all(&s, "").as_ref())
+}
@@ -256,6 +256,23 @@ pub(crate) fn seconds_to_human(s: u64) -> String {
     }
 }
 
+/// `s` seconds as a length of time, like "3 hours", in its largest whole unit
+pub(crate) fn seconds_to_duration(s: u64) -> String {
+    let (count, unit) = match s {
+        s if s < 60 => (s, "second"),
+        s if s < 3600 => (s / 60, "minute"),
+        s if s < 3600 * 24 => (s / 3600, "hour"),
+        s if s < 3600 * 24 * 30 => (s / 3600 / 24, "day"),
+        s if s < 3600 * 24 * 365 => (s / 3600 / 24 / 30, "month"),
+        s => (s / 3600 / 24 / 365, "year"),
+    };
+    if count == 1 {
+        format!("1 {}", unit)
+    } else {
+        format!("{} {}s", count, unit)
+    }
+}
+
 pub(crate) fn validate_base_url(package: &fpm::Package) -> fpm::Result<()> {
     if package.download_base_url.is_none() {
         warning!("expected base in fpm.package: {:?}", package.name);
@@ -264,7 +281,6 @@ pub(crate) fn validate_base_url(package: &fpm::Package) -> fpm::Result<()> {
     Ok(())
 }
 
-#[allow(dead_code)]
 pub fn escape_ftd(file: &str) -> String {
     use itertools::Itertools;
 
@@ -373,7 +389,12 @@ pub fn replace_markers_2021(
     s.replace("__ftd_doc_title__", title)
         .replace(
             "__ftd_canonical_url__",
-            config.package.generate_canonical_url(main_id).as_str(),
+            format!(
+                "{}{}",
+                config.package.generate_canonical_url(main_id),
+                fpm::package::languages::hreflang_links(&config.package, main_id)
+            )
+            .as_str(),
         )
         .replace(
             "__favicon_html_tag__",
@@ -613,6 +634,15 @@ mod tests {
             ]
         )
     }
+
+    #[test]
+    fn seconds_to_duration() {
+        assert_eq!(super::seconds_to_duration(0), "0 seconds");
+        assert_eq!(super::seconds_to_duration(1), "1 second");
+        assert_eq!(super::seconds_to_duration(3 * 3600 + 59), "3 hours");
+        assert_eq!(super::seconds_to_duration(24 * 3600), "1 day");
+        assert_eq!(super::seconds_to_duration(400 * 24 * 3600), "1 year");
+    }
 }
 
 pub fn ignore_headers() -> Vec<&'static str> {